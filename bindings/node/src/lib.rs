@@ -0,0 +1,123 @@
+use argon2_rs::{Algorithm, Argon2, error::Error};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+/// The parameters of a hash, mirrors [`Argon2`]
+#[napi(object)]
+pub struct Options {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    /// Defaults to 64 bytes
+    pub hash_length: Option<u32>,
+    /// `"argon2d"`, `"argon2i"` or `"argon2id"` (default)
+    pub algorithm: Option<String>,
+}
+
+impl TryFrom<Options> for Argon2 {
+    type Error = napi::Error;
+
+    fn try_from(options: Options) -> Result<Self> {
+        let algorithm = match options.algorithm.as_deref() {
+            None | Some("argon2id") => Algorithm::Argon2id,
+            Some("argon2i") => Algorithm::Argon2i,
+            Some("argon2d") => Algorithm::Argon2d,
+            Some(other) => {
+                return Err(napi::Error::new(
+                    Status::InvalidArg,
+                    format!("unknown algorithm: {other}"),
+                ));
+            }
+        };
+
+        let mut argon2 = Argon2::new(options.m_cost, options.t_cost, options.p_cost)
+            .with_algorithm(algorithm);
+
+        if let Some(hash_length) = options.hash_length {
+            argon2 = argon2.with_hash_length(hash_length as u64);
+        }
+
+        Ok(argon2)
+    }
+}
+
+fn to_napi_err(error: Error) -> napi::Error {
+    napi::Error::new(Status::GenericFailure, error.to_string())
+}
+
+fn password_bytes(password: Either<String, Buffer>) -> Vec<u8> {
+    match password {
+        Either::A(s) => s.into_bytes(),
+        Either::B(b) => b.to_vec(),
+    }
+}
+
+pub struct HashTask {
+    argon2: Argon2,
+    password: Vec<u8>,
+    salt: Vec<u8>,
+}
+
+impl Task for HashTask {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<String> {
+        self.argon2
+            .hash_password_encoded(&self.password, &self.salt)
+            .map_err(to_napi_err)
+    }
+
+    fn resolve(&mut self, _env: Env, output: String) -> Result<String> {
+        Ok(output)
+    }
+}
+
+pub struct VerifyTask {
+    password: Vec<u8>,
+    encoded: String,
+}
+
+impl Task for VerifyTask {
+    type Output = bool;
+    type JsValue = bool;
+
+    fn compute(&mut self) -> Result<bool> {
+        Argon2::verify_encoded(&self.password, &self.encoded).map_err(to_napi_err)
+    }
+
+    fn resolve(&mut self, _env: Env, output: bool) -> Result<bool> {
+        Ok(output)
+    }
+}
+
+/// Hashes the password on the libuv thread pool and resolves to the PHC string
+#[napi(ts_return_type = "Promise<string>")]
+pub fn hash(
+    password: Either<String, Buffer>,
+    salt: Buffer,
+    options: Options,
+) -> Result<AsyncTask<HashTask>> {
+    Ok(AsyncTask::new(HashTask {
+        argon2: options.try_into()?,
+        password: password_bytes(password),
+        salt: salt.to_vec(),
+    }))
+}
+
+/// Verifies the password against a PHC string on the libuv thread pool
+#[napi(ts_return_type = "Promise<boolean>")]
+pub fn verify(password: Either<String, Buffer>, encoded: String) -> AsyncTask<VerifyTask> {
+    AsyncTask::new(VerifyTask {
+        password: password_bytes(password),
+        encoded,
+    })
+}
+
+/// Whether a PHC string was created with parameters other than `options`
+#[napi]
+pub fn needs_rehash(encoded: String, options: Options) -> Result<bool> {
+    Argon2::try_from(options)?
+        .needs_rehash(&encoded)
+        .map_err(to_napi_err)
+}
@@ -0,0 +1,104 @@
+use argon2_rs::{Algorithm, Argon2, error::Error};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+/// A password given either as `str` or `bytes`
+#[derive(FromPyObject)]
+enum Password<'a> {
+    Str(String),
+    Bytes(&'a [u8]),
+}
+
+impl AsRef<[u8]> for Password<'_> {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Password::Str(s) => s.as_bytes(),
+            Password::Bytes(b) => b,
+        }
+    }
+}
+
+fn to_py_err(error: Error) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn argon2(
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    hash_length: u64,
+    algorithm: &str,
+) -> PyResult<Argon2> {
+    let algorithm = match algorithm {
+        "argon2d" => Algorithm::Argon2d,
+        "argon2i" => Algorithm::Argon2i,
+        "argon2id" => Algorithm::Argon2id,
+        _ => return Err(PyValueError::new_err(format!("unknown algorithm: {algorithm}"))),
+    };
+
+    Ok(Argon2::new(m_cost, t_cost, p_cost)
+        .with_hash_length(hash_length)
+        .with_algorithm(algorithm))
+}
+
+/// Hashes the password and returns the raw hash as `bytes`
+///
+/// The GIL is released while hashing.
+#[pyfunction]
+#[pyo3(signature = (password, salt, m_cost, t_cost, p_cost, hash_length = 64, algorithm = "argon2id"))]
+#[allow(clippy::too_many_arguments)]
+fn hash<'py>(
+    py: Python<'py>,
+    password: Password<'_>,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    hash_length: u64,
+    algorithm: &str,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let argon2 = argon2(m_cost, t_cost, p_cost, hash_length, algorithm)?;
+    let hash = py
+        .allow_threads(|| argon2.hash_password(&password, salt))
+        .map_err(to_py_err)?;
+    Ok(PyBytes::new_bound(py, &hash))
+}
+
+/// Hashes the password and returns the PHC string
+///
+/// The GIL is released while hashing.
+#[pyfunction]
+#[pyo3(signature = (password, salt, m_cost, t_cost, p_cost, hash_length = 64, algorithm = "argon2id"))]
+#[allow(clippy::too_many_arguments)]
+fn hash_encoded(
+    py: Python<'_>,
+    password: Password<'_>,
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    hash_length: u64,
+    algorithm: &str,
+) -> PyResult<String> {
+    let argon2 = argon2(m_cost, t_cost, p_cost, hash_length, algorithm)?;
+    py.allow_threads(|| argon2.hash_password_encoded(&password, salt))
+        .map_err(to_py_err)
+}
+
+/// Verifies the password against a PHC string
+///
+/// The GIL is released while hashing.
+#[pyfunction]
+fn verify(py: Python<'_>, password: Password<'_>, encoded: &str) -> PyResult<bool> {
+    py.allow_threads(|| Argon2::verify_encoded(&password, encoded))
+        .map_err(to_py_err)
+}
+
+#[pymodule]
+fn argon2_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(hash, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_encoded, m)?)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,18 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // The optimized implementation of argon2-sys picks its instruction set at compile time with
+    // `-march=native`, the probe is compiled the same way to report which one it picked
+    #[cfg(feature = "simd")]
+    {
+        let arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+        if arch == "x86" || arch == "x86_64" {
+            println!("cargo:rerun-if-changed=src/simd.c");
+            cc::Build::new()
+                .file("src/simd.c")
+                .flag_if_supported("-march=native")
+                .warnings(false)
+                .compile("argon2rs_simd");
+        }
+    }
+}
@@ -0,0 +1,51 @@
+#ifndef ARGON2RS_H
+#define ARGON2RS_H
+
+/*
+ * C API of argon2-rs, built with:
+ *
+ *     cargo rustc --release --features capi --crate-type cdylib
+ *
+ * Error codes of the C implementation keep their libargon2 values (ARGON2_VERIFY_MISMATCH = -35,
+ * ARGON2_DECODING_FAIL = -32, ...).
+ */
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define ARGON2RS_OK 0
+#define ARGON2RS_PEPPER_REQUIRED -100
+#define ARGON2RS_ERROR -101
+
+/* Same values as argon2_type of libargon2 */
+#define ARGON2RS_ARGON2D 0
+#define ARGON2RS_ARGON2I 1
+#define ARGON2RS_ARGON2ID 2
+
+int argon2rs_hash(uint32_t t_cost, uint32_t m_cost, uint32_t parallelism,
+                  const void *pwd, size_t pwdlen,
+                  const void *salt, size_t saltlen,
+                  void *hash, size_t hashlen,
+                  uint32_t type);
+
+int argon2rs_hash_encoded(uint32_t t_cost, uint32_t m_cost, uint32_t parallelism,
+                          const void *pwd, size_t pwdlen,
+                          const void *salt, size_t saltlen,
+                          size_t hashlen,
+                          char *encoded, size_t encodedlen,
+                          uint32_t type);
+
+int argon2rs_verify(const char *encoded, const void *pwd, size_t pwdlen);
+
+/* crypt(3)-compatible: setting is "$argon2id$v=19$m=...,t=...,p=...$<salt>" or a complete hash */
+int argon2rs_crypt(const char *phrase, const char *setting, char *output, size_t outputlen);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ARGON2RS_H */
@@ -0,0 +1,407 @@
+use super::{Argon2, Error, backend, output_buffer};
+
+#[cfg(all(feature = "hardened-memory", target_os = "linux"))]
+mod harden;
+#[cfg(all(feature = "hardened-memory", target_os = "linux"))]
+pub use harden::HardenedAllocator;
+#[cfg(all(feature = "guard-pages", any(unix, windows)))]
+mod guard;
+#[cfg(all(feature = "guard-pages", any(unix, windows)))]
+pub use guard::GuardedMemory;
+mod heapless;
+pub use heapless::{MAX_HEAPLESS_HASH_LENGTH, StaticHasher};
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+mod hugepages;
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+pub use hugepages::{HUGE_PAGE_SIZE, HugePageMemory, HugePages};
+#[cfg(all(feature = "locked-memory", any(unix, windows)))]
+mod locked;
+#[cfg(all(feature = "locked-memory", any(unix, windows)))]
+pub use locked::{LockedAllocator, LockedBytes, LockedSecret, memlock_limit};
+#[cfg(all(feature = "numa", target_os = "linux"))]
+mod numa;
+#[cfg(all(feature = "numa", target_os = "linux"))]
+pub use numa::{NumaAllocator, NumaPolicy};
+
+/// The size of a memory page, the unit memory is protected and locked in
+#[cfg(all(any(feature = "guard-pages", feature = "locked-memory"), unix))]
+fn page_size() -> usize {
+    match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    }
+}
+
+#[cfg(all(any(feature = "guard-pages", feature = "locked-memory"), windows))]
+fn page_size() -> usize {
+    use windows_sys::Win32::System::SystemInformation::{GetSystemInfo, SYSTEM_INFO};
+
+    let mut info: SYSTEM_INFO = unsafe { std::mem::zeroed() };
+    unsafe { GetSystemInfo(&mut info) };
+    info.dwPageSize as usize
+}
+
+/// The size of an Argon2 memory block in bytes
+pub const BLOCK_SIZE: usize = 1024;
+
+/// A 1 KiB block of Argon2 working memory, aligned to a cache line
+#[repr(C, align(64))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Block(pub [u64; BLOCK_SIZE / 8]);
+
+impl Block {
+    /// A block of zeroes
+    pub const ZERO: Self = Self([0; BLOCK_SIZE / 8]);
+
+    /// Allocates `blocks` zeroed blocks on the heap
+    ///
+    /// The memory comes zeroed from the allocator, large allocations are not touched until the
+    /// hash writes to them. Aborts the process if the allocation fails, see [`Block::try_alloc`].
+    pub fn alloc(blocks: usize) -> Box<[Block]> {
+        let layout = std::alloc::Layout::array::<Block>(blocks)
+            .expect("memory size overflows the address space");
+        Self::try_alloc(blocks).unwrap_or_else(|| std::alloc::handle_alloc_error(layout))
+    }
+
+    /// Allocates `blocks` zeroed blocks on the heap, `None` if the memory is not available
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::allocator::Block;
+    ///
+    /// assert!(Block::try_alloc(usize::MAX / 2).is_none());
+    /// assert_eq!(Block::try_alloc(8).unwrap().len(), 8);
+    /// ```
+    pub fn try_alloc(blocks: usize) -> Option<Box<[Block]>> {
+        let layout = std::alloc::Layout::array::<Block>(blocks).ok()?;
+        if layout.size() == 0 {
+            return Some(Box::new([]));
+        }
+
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            return None;
+        }
+        // Zeroes are a valid block and the layout is the one `Box` frees with
+        Some(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr.cast(), blocks)) })
+    }
+
+    /// Reinterprets a byte buffer as blocks
+    ///
+    /// The bytes before the first 64-byte boundary and after the last whole block are unused, so
+    /// the buffer should be up to 63 bytes larger than the blocks it is meant to hold.
+    pub fn from_bytes_mut(bytes: &mut [u8]) -> &mut [Block] {
+        // A block is plain integers, every bit pattern is a valid block
+        let (_, blocks, _) = unsafe { bytes.align_to_mut::<Block>() };
+        blocks
+    }
+}
+
+/// The whole pages within the given memory, system calls that take memory ranges only accept
+/// whole pages
+#[cfg(all(
+    target_os = "linux",
+    any(feature = "hardened-memory", feature = "numa")
+))]
+fn whole_pages(ptr: *const u8, len: usize) -> Option<(*mut libc::c_void, usize)> {
+    let page = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    };
+    let start = (ptr as usize).next_multiple_of(page);
+    let end = (ptr as usize + len) / page * page;
+    (start < end).then_some((start as *mut libc::c_void, end - start))
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+/// Provides the working memory of the C implementation
+///
+/// The C implementation asks for its working memory through the `allocate_cbk` and `free_cbk`
+/// callbacks of `argon2_context`. Implementing this trait plugs an arena, locked memory or an
+/// instrumented allocator into those callbacks without any unsafe code.
+///
+/// Both methods are called on the thread that hashes, at most once per hash. The memory is wiped
+/// by the C implementation before it is handed back to [`MemoryAllocator::free`]. A panic in
+/// either method is resumed on the calling thread once the C implementation has returned.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, allocator::{Block, MemoryAllocator}};
+///
+/// #[derive(Default)]
+/// struct Counting {
+///     peak: usize,
+/// }
+///
+/// impl MemoryAllocator for Counting {
+///     fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+///         self.peak = self.peak.max(blocks);
+///         Block::try_alloc(blocks)
+///     }
+/// }
+///
+/// let mut allocator = Counting::default();
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let hash = argon2
+///     .hash_password_with_allocator("password", b"somesaltsomesalt", &mut allocator)
+///     .unwrap();
+///
+/// assert_eq!(hash, argon2.hash_password("password", b"somesaltsomesalt").unwrap());
+/// assert_eq!(allocator.peak, 1024);
+/// ```
+pub trait MemoryAllocator {
+    /// Allocates at least `blocks` blocks of working memory
+    ///
+    /// Returning `None` or fewer blocks fails the hash with [`Error::OutOfMemory`].
+    fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>>;
+
+    /// Takes back memory returned by [`MemoryAllocator::allocate`], drops it by default
+    fn free(&mut self, memory: Box<[Block]>) {
+        drop(memory);
+    }
+}
+
+impl<A: MemoryAllocator + ?Sized> MemoryAllocator for &mut A {
+    fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+        (**self).allocate(blocks)
+    }
+
+    fn free(&mut self, memory: Box<[Block]>) {
+        (**self).free(memory)
+    }
+}
+
+impl Argon2 {
+    /// The number of blocks of working memory a hash with these parameters uses
+    ///
+    /// This is `m_cost` rounded the same way as the C implementation: at least 8 blocks per lane
+    /// and a multiple of 4 blocks per lane.
+    pub fn memory_blocks(&self) -> usize {
+        // At most `u32::MAX` blocks, only the size in bytes can overflow a 32-bit `usize`
+        (self.working_memory_bytes() / BLOCK_SIZE as u64) as usize
+    }
+
+    /// Hashes the given password into `out` entirely within caller-supplied working memory
+    ///
+    /// Nothing is allocated on the heap with a `p_cost` of 1 and without the `CLEAR_PASSWORD`
+    /// and `CLEAR_SECRET` flags, otherwise the C implementation allocates its thread handles and
+    /// this crate a copy of the password. The memory is wiped before this returns.
+    ///
+    /// Returns [`Argon2Error::MemoryAllocationError`](crate::error::Argon2Error::MemoryAllocationError)
+    /// if `memory` is smaller than [`Argon2::memory_blocks`].
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `out` - The buffer to write the hash into, its length is the length of the hash
+    /// - `memory` - The working memory, at least [`Argon2::memory_blocks`] blocks
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, allocator::Block};
+    ///
+    /// let argon2 = Argon2::new(64, 2, 1);
+    /// let mut memory = [Block::ZERO; 64];
+    /// let mut key = [0u8; 32];
+    ///
+    /// argon2
+    ///     .hash_password_in_memory("password", b"somesaltsomesalt", &mut key, &mut memory)
+    ///     .unwrap();
+    /// ```
+    pub fn hash_password_in_memory(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        out: &mut [u8],
+        memory: &mut [Block],
+    ) -> Result<(), Error> {
+        backend::hash_in_memory(self, password.as_ref(), salt, &[], out, memory)
+    }
+
+    /// Hashes the given password in working memory from the given allocator
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `allocator` - Provides the working memory, see [`MemoryAllocator`]
+    pub fn hash_password_with_allocator(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        allocator: &mut impl MemoryAllocator,
+    ) -> Result<Vec<u8>, Error> {
+        let mut hash = output_buffer(self.hash_len()?)?;
+        backend::hash_with_allocator(self, password.as_ref(), salt, &[], &mut hash, allocator)?;
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Argon2Error;
+
+    #[derive(Default)]
+    struct Pool {
+        free: Vec<Box<[Block]>>,
+        allocations: usize,
+        frees: usize,
+    }
+
+    impl MemoryAllocator for Pool {
+        fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+            self.allocations += 1;
+            match self.free.pop() {
+                Some(memory) if memory.len() >= blocks => Some(memory),
+                _ => Some(Block::alloc(blocks)),
+            }
+        }
+
+        fn free(&mut self, memory: Box<[Block]>) {
+            assert!(memory.iter().all(|block| *block == Block::ZERO));
+            self.frees += 1;
+            self.free.push(memory);
+        }
+    }
+
+    #[test]
+    fn test_allocator_is_called() {
+        let argon2 = Argon2::new(2048, 1, 4);
+        let salt = b"somesaltsomesalt";
+        let mut pool = Pool::default();
+
+        for password in ["password", "another password"] {
+            assert_eq!(
+                argon2
+                    .hash_password_with_allocator(password, salt, &mut pool)
+                    .unwrap(),
+                argon2.hash_password(password, salt).unwrap()
+            );
+        }
+
+        assert_eq!(pool.allocations, 2);
+        assert_eq!(pool.frees, 2);
+        assert_eq!(pool.free.len(), 1);
+    }
+
+    #[test]
+    fn test_allocator_failure() {
+        struct Failing(Option<usize>);
+
+        impl MemoryAllocator for Failing {
+            fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+                self.0.map(|short| Block::alloc(blocks - short))
+            }
+        }
+
+        let argon2 = Argon2::new(1024, 2, 1);
+        for mut allocator in [Failing(None), Failing(Some(1))] {
+            assert!(matches!(
+                argon2.hash_password_with_allocator(
+                    "password",
+                    b"somesaltsomesalt",
+                    &mut allocator
+                ),
+                Err(Error::OutOfMemory)
+            ));
+        }
+
+        // The thread local state is reset after a failure
+        assert!(
+            argon2
+                .hash_password("password", b"somesaltsomesalt")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_allocator_panic() {
+        struct Panicking {
+            in_free: bool,
+        }
+
+        impl MemoryAllocator for Panicking {
+            fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+                assert!(self.in_free, "allocate");
+                Some(Block::alloc(blocks))
+            }
+
+            fn free(&mut self, _memory: Box<[Block]>) {
+                panic!("free");
+            }
+        }
+
+        let argon2 = Argon2::new(1024, 2, 2);
+        let salt = b"somesaltsomesalt";
+        for in_free in [false, true] {
+            let result = std::panic::catch_unwind(|| {
+                let mut allocator = Panicking { in_free };
+                argon2.hash_password_with_allocator("password", salt, &mut allocator)
+            });
+            let payload = result.unwrap_err();
+            let message = match payload.downcast_ref::<&str>() {
+                Some(message) => message.to_string(),
+                None => payload.downcast_ref::<String>().unwrap().clone(),
+            };
+            assert_eq!(message.contains("free"), in_free, "{message}");
+        }
+
+        assert!(argon2.hash_password("password", salt).is_ok());
+    }
+
+    #[test]
+    fn test_hash_in_memory() {
+        let salt = b"somesaltsomesalt";
+
+        for argon2 in [Argon2::new(64, 2, 1), Argon2::new(100, 1, 3)] {
+            let mut memory = Block::alloc(argon2.memory_blocks());
+            let mut hash = [0u8; 32];
+            argon2
+                .hash_password_in_memory("password", salt, &mut hash, &mut memory)
+                .unwrap();
+
+            let expected = argon2.clone().with_hash_length(32);
+            assert_eq!(
+                hash.to_vec(),
+                expected.hash_password("password", salt).unwrap()
+            );
+            assert!(memory.iter().all(|block| *block == Block::ZERO));
+
+            let short = &mut memory[1..];
+            assert!(matches!(
+                argon2.hash_password_in_memory("password", salt, &mut hash, short),
+                Err(Error::Argon2(Argon2Error::MemoryAllocationError))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_block_from_bytes() {
+        let mut bytes = vec![0u8; 4 * BLOCK_SIZE + 63];
+        let blocks = Block::from_bytes_mut(&mut bytes);
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks.as_ptr() as usize % 64, 0);
+
+        let argon2 = Argon2::new(8, 1, 1);
+        let mut hash = [0u8; 32];
+        let bytes = &mut vec![0u8; argon2.memory_blocks() * BLOCK_SIZE + 63];
+        argon2
+            .hash_password_in_memory(
+                "password",
+                b"somesaltsomesalt",
+                &mut hash,
+                Block::from_bytes_mut(bytes),
+            )
+            .unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+use super::{BLOCK_SIZE, Block, page_size};
+use crate::Error;
+use std::ptr::NonNull;
+
+/// Working memory between two inaccessible guard pages
+///
+/// The blocks end right where the trailing guard page starts, an access past the end of the
+/// memory faults immediately instead of corrupting adjacent heap data. The leading guard page
+/// catches accesses before the start once they cross the padding in front of the blocks, which
+/// is less than a page.
+///
+/// The guard pages are `PROT_NONE` mappings on Unix and reserved but uncommitted pages on
+/// Windows. Hash with it through
+/// [`Argon2::hash_password_in_memory`](crate::Argon2::hash_password_in_memory), the memory must be
+/// exactly [`Argon2::memory_blocks`](crate::Argon2::memory_blocks) long for the guard page to sit
+/// right after the blocks the implementation uses.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, allocator::GuardedMemory};
+///
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let mut memory = GuardedMemory::new(argon2.memory_blocks()).unwrap();
+///
+/// let mut key = [0u8; 32];
+/// argon2
+///     .hash_password_in_memory("password", b"somesaltsomesalt", &mut key, &mut memory)
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct GuardedMemory {
+    mapping: NonNull<u8>,
+    mapped: usize,
+    blocks: NonNull<Block>,
+    len: usize,
+}
+
+// The mapping is owned and only accessed through `&self` or `&mut self`
+unsafe impl Send for GuardedMemory {}
+unsafe impl Sync for GuardedMemory {}
+
+impl GuardedMemory {
+    /// Maps `blocks` blocks with a guard page on either side
+    pub fn new(blocks: usize) -> Result<Self, Error> {
+        let page = page_size();
+        let overflow = || Error::Memory("memory size overflows the address space".to_string());
+        let bytes = blocks.checked_mul(BLOCK_SIZE).ok_or_else(overflow)?;
+        let accessible = bytes
+            .max(1)
+            .checked_next_multiple_of(page)
+            .ok_or_else(overflow)?;
+        let mapped = accessible.checked_add(2 * page).ok_or_else(overflow)?;
+
+        let mapping = map(mapped, page, accessible)?;
+        // Blocks are 1 KiB and pages a multiple of it, the blocks stay aligned
+        let blocks_ptr = unsafe { mapping.as_ptr().add(page + accessible - bytes) };
+
+        #[cfg(all(feature = "hardened-memory", target_os = "linux"))]
+        super::harden::advise(unsafe { mapping.as_ptr().add(page) }, accessible, true);
+
+        Ok(Self {
+            mapping,
+            mapped,
+            blocks: NonNull::new(blocks_ptr.cast()).expect("mapping is not null"),
+            len: blocks,
+        })
+    }
+}
+
+impl std::ops::Deref for GuardedMemory {
+    type Target = [Block];
+
+    fn deref(&self) -> &[Block] {
+        // Anonymous mappings are zeroed and every bit pattern is a valid block
+        unsafe { std::slice::from_raw_parts(self.blocks.as_ptr(), self.len) }
+    }
+}
+
+impl std::ops::DerefMut for GuardedMemory {
+    fn deref_mut(&mut self) -> &mut [Block] {
+        unsafe { std::slice::from_raw_parts_mut(self.blocks.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for GuardedMemory {
+    fn drop(&mut self) {
+        unmap(self.mapping, self.mapped);
+    }
+}
+
+/// Maps `mapped` inaccessible bytes and makes the `accessible` bytes after the first `page` bytes
+/// readable and writable
+#[cfg(unix)]
+fn map(mapped: usize, page: usize, accessible: usize) -> Result<NonNull<u8>, Error> {
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            mapped,
+            libc::PROT_NONE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(Error::Memory(std::io::Error::last_os_error().to_string()));
+    }
+
+    let inner = unsafe { ptr.cast::<u8>().add(page) };
+    let protected =
+        unsafe { libc::mprotect(inner.cast(), accessible, libc::PROT_READ | libc::PROT_WRITE) };
+    if protected != 0 {
+        let error = std::io::Error::last_os_error();
+        unsafe { libc::munmap(ptr, mapped) };
+        return Err(Error::Memory(error.to_string()));
+    }
+    Ok(NonNull::new(ptr.cast()).expect("mmap returned null"))
+}
+
+#[cfg(windows)]
+fn map(mapped: usize, page: usize, accessible: usize) -> Result<NonNull<u8>, Error> {
+    use windows_sys::Win32::System::Memory::{
+        MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_NOACCESS, PAGE_READWRITE, VirtualAlloc,
+        VirtualFree,
+    };
+
+    let ptr = unsafe { VirtualAlloc(std::ptr::null(), mapped, MEM_RESERVE, PAGE_NOACCESS) };
+    if ptr.is_null() {
+        return Err(Error::Memory(std::io::Error::last_os_error().to_string()));
+    }
+
+    // Reserved pages that are not committed fault on every access
+    let inner = unsafe { ptr.cast::<u8>().add(page) };
+    let committed = unsafe { VirtualAlloc(inner.cast(), accessible, MEM_COMMIT, PAGE_READWRITE) };
+    if committed.is_null() {
+        let error = std::io::Error::last_os_error();
+        unsafe { VirtualFree(ptr, 0, MEM_RELEASE) };
+        return Err(Error::Memory(error.to_string()));
+    }
+    Ok(NonNull::new(ptr.cast()).expect("VirtualAlloc returned null"))
+}
+
+#[cfg(unix)]
+fn unmap(mapping: NonNull<u8>, mapped: usize) {
+    unsafe { libc::munmap(mapping.as_ptr().cast(), mapped) };
+}
+
+#[cfg(windows)]
+fn unmap(mapping: NonNull<u8>, _mapped: usize) {
+    use windows_sys::Win32::System::Memory::{MEM_RELEASE, VirtualFree};
+
+    unsafe { VirtualFree(mapping.as_ptr().cast(), 0, MEM_RELEASE) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Argon2;
+
+    #[test]
+    fn test_guarded_memory() {
+        let argon2 = Argon2::new(1024, 2, 2);
+        let salt = b"somesaltsomesalt";
+        let expected = argon2
+            .clone()
+            .with_hash_length(32)
+            .hash_password("password", salt)
+            .unwrap();
+
+        let mut memory = GuardedMemory::new(argon2.memory_blocks()).unwrap();
+        assert_eq!(memory.len(), argon2.memory_blocks());
+        assert_eq!(memory.as_ptr() as usize % 64, 0);
+        // The blocks end at the trailing guard page
+        let end = memory.as_ptr() as usize + memory.len() * BLOCK_SIZE;
+        assert_eq!(end % page_size(), 0);
+
+        let mut hash = [0u8; 32];
+        argon2
+            .hash_password_in_memory("password", salt, &mut hash, &mut memory)
+            .unwrap();
+        assert_eq!(hash.to_vec(), expected);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_guard_pages_inaccessible() {
+        let memory = GuardedMemory::new(3).unwrap();
+        let page = page_size();
+        let end = memory.as_ptr() as usize + memory.len() * BLOCK_SIZE;
+
+        // Reads the protection back from the process map instead of faulting
+        let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+        let protection = |address: usize| {
+            maps.lines()
+                .find_map(|line| {
+                    let (range, rest) = line.split_once(' ')?;
+                    let (start, end) = range.split_once('-')?;
+                    let start = usize::from_str_radix(start, 16).ok()?;
+                    let end = usize::from_str_radix(end, 16).ok()?;
+                    (start..end)
+                        .contains(&address)
+                        .then(|| rest[..4].to_string())
+                })
+                .unwrap()
+        };
+
+        assert_eq!(protection(end), "---p");
+        assert_eq!(protection(end - page), "rw-p");
+        assert_eq!(protection(memory.mapping.as_ptr() as usize), "---p");
+    }
+}
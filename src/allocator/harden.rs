@@ -0,0 +1,91 @@
+use super::{BLOCK_SIZE, Block, MemoryAllocator, whole_pages};
+
+/// Excludes the whole pages of the given memory from core dumps and wipes them in forked
+/// children, or undoes it with `harden` set to `false`
+///
+/// The advice sticks to the pages after the memory is freed, heap memory must be unadvised before
+/// it is handed back. Failures are ignored, `MADV_WIPEONFORK` needs Linux 4.14.
+pub(crate) fn advise(ptr: *const u8, len: usize, harden: bool) {
+    let Some((ptr, len)) = whole_pages(ptr, len) else {
+        return;
+    };
+
+    let (dump, fork) = if harden {
+        (libc::MADV_DONTDUMP, libc::MADV_WIPEONFORK)
+    } else {
+        (libc::MADV_DODUMP, libc::MADV_KEEPONFORK)
+    };
+
+    unsafe {
+        libc::madvise(ptr, len, dump);
+        libc::madvise(ptr, len, fork);
+    }
+}
+
+/// Allocates working memory that is excluded from core dumps and wiped in forked children
+///
+/// The memory is marked with `MADV_DONTDUMP` and `MADV_WIPEONFORK` so password-derived state never
+/// ends up in a core dump or survives into a child process. Only whole pages can be marked, the
+/// partial pages at both ends of the memory are left as they are.
+///
+/// [`LockedAllocator`](super::LockedAllocator) does the same with the `locked-memory` feature.
+#[derive(Debug, Default)]
+pub struct HardenedAllocator;
+
+impl MemoryAllocator for HardenedAllocator {
+    fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+        let memory = Block::try_alloc(blocks)?;
+        advise(memory.as_ptr().cast(), blocks * BLOCK_SIZE, true);
+        Some(memory)
+    }
+
+    fn free(&mut self, memory: Box<[Block]>) {
+        advise(memory.as_ptr().cast(), memory.len() * BLOCK_SIZE, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Argon2;
+
+    #[test]
+    fn test_hardened_allocator() {
+        let argon2 = Argon2::new(256, 2, 1);
+        let salt = b"somesaltsomesalt";
+        assert_eq!(
+            argon2
+                .hash_password_with_allocator("password", salt, &mut HardenedAllocator)
+                .unwrap(),
+            argon2.hash_password("password", salt).unwrap()
+        );
+    }
+
+    /// Forks and returns whether the child sees the given block wiped
+    fn wiped_in_child(block: &Block) -> bool {
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            let wiped = *block == Block::ZERO;
+            unsafe { libc::_exit(if wiped { 0 } else { 1 }) };
+        }
+
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0) };
+        libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0
+    }
+
+    #[test]
+    fn test_advise_wipes_on_fork() {
+        let mut memory = Block::alloc(64);
+        let len = memory.len() * BLOCK_SIZE;
+        let middle = memory.len() / 2;
+        memory.iter_mut().for_each(|block| block.0.fill(u64::MAX));
+
+        advise(memory.as_ptr().cast(), len, true);
+        assert!(wiped_in_child(&memory[middle]));
+        assert_eq!(memory[middle].0[0], u64::MAX);
+
+        advise(memory.as_ptr().cast(), len, false);
+        assert!(!wiped_in_child(&memory[middle]));
+    }
+}
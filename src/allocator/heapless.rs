@@ -0,0 +1,191 @@
+use super::{Block, backend};
+use crate::{ARGON2_MAX_OUTLEN, ARGON2_MIN_OUTLEN, Argon2, Error, Flags, constant_time_eq};
+
+/// The longest hash [`StaticHasher::verify`] compares
+pub const MAX_HEAPLESS_HASH_LENGTH: usize = 64;
+
+/// Hashes entirely within a fixed-size buffer without allocating, for embedded targets
+///
+/// The working memory is `BLOCKS` KiB kept inline, so the largest memory cost is chosen at compile
+/// time. Place the hasher in a `static` to keep it off the stack. Every limit is checked up front
+/// and reported as [`Error::HeaplessLimit`] instead of panicking or allocating:
+///
+/// - the memory cost must fit in `BLOCKS` blocks, see [`Argon2::memory_blocks`]
+/// - the lanes must be computed by a single thread, set [`Argon2::with_threads`] to 1 if `p_cost`
+///   is greater than 1, the C implementation allocates its threads otherwise
+/// - [`Flags::CLEAR_PASSWORD`] and [`Flags::CLEAR_SECRET`] are not supported, they need a heap copy
+///   of the password and the secret
+/// - pre-hashing and normalization, see [`Argon2::with_prehash`] and
+///   [`Argon2::with_normalization`], are not supported, they replace the password with a heap copy
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, allocator::StaticHasher};
+///
+/// static mut HASHER: StaticHasher<64> = StaticHasher::new();
+///
+/// #[allow(static_mut_refs)]
+/// let hasher = unsafe { &mut HASHER };
+///
+/// let argon2 = Argon2::new(64, 3, 1);
+/// let key: [u8; 32] = hasher.hash(&argon2, "password", b"somesaltsomesalt").unwrap();
+/// assert!(hasher.verify(&argon2, "password", b"somesaltsomesalt", &key).unwrap());
+///
+/// assert!(hasher.hash::<32>(&Argon2::new(128, 3, 1), "password", b"somesaltsomesalt").is_err());
+/// ```
+pub struct StaticHasher<const BLOCKS: usize> {
+    memory: [Block; BLOCKS],
+}
+
+impl<const BLOCKS: usize> StaticHasher<BLOCKS> {
+    pub const fn new() -> Self {
+        Self {
+            memory: [Block::ZERO; BLOCKS],
+        }
+    }
+
+    /// The largest memory cost in KiB this hasher can hash with
+    pub const fn max_m_cost(&self) -> usize {
+        BLOCKS
+    }
+
+    /// Hashes the given password into a fixed-size array
+    ///
+    /// The length of the hash is `N`, the `hash_length` of `argon2` is ignored.
+    ///
+    /// ## Arguments
+    ///
+    /// - `argon2` - The parameters to hash with
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    pub fn hash<const N: usize>(
+        &mut self,
+        argon2: &Argon2,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<[u8; N], Error> {
+        const {
+            assert!(
+                N as u64 >= ARGON2_MIN_OUTLEN && N as u64 <= ARGON2_MAX_OUTLEN,
+                "hash length is out of the range supported by Argon2"
+            )
+        };
+
+        let mut out = [0u8; N];
+        self.hash_into(argon2, password.as_ref(), salt, &mut out)?;
+        Ok(out)
+    }
+
+    /// Verifies the given password against a raw hash of at most
+    /// [`MAX_HEAPLESS_HASH_LENGTH`] bytes
+    pub fn verify(
+        &mut self,
+        argon2: &Argon2,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        hash: &[u8],
+    ) -> Result<bool, Error> {
+        if hash.len() > MAX_HEAPLESS_HASH_LENGTH {
+            return Err(Error::HeaplessLimit("hash is longer than 64 bytes"));
+        }
+
+        let mut out = [0u8; MAX_HEAPLESS_HASH_LENGTH];
+        let out = &mut out[..hash.len()];
+        self.hash_into(argon2, password.as_ref(), salt, out)?;
+        Ok(constant_time_eq(out, hash))
+    }
+
+    fn hash_into(
+        &mut self,
+        argon2: &Argon2,
+        password: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), Error> {
+        if argon2.threads() > 1 {
+            return Err(Error::HeaplessLimit(
+                "the lanes must be computed by a single thread",
+            ));
+        }
+        if argon2.memory_blocks() > BLOCKS {
+            return Err(Error::HeaplessLimit("m_cost exceeds the static memory"));
+        }
+        if argon2.flags.contains(Flags::CLEAR_PASSWORD)
+            || argon2.flags.contains(Flags::CLEAR_SECRET)
+        {
+            return Err(Error::HeaplessLimit(
+                "CLEAR_PASSWORD and CLEAR_SECRET need a heap copy",
+            ));
+        }
+        if argon2.prehash.is_some() || argon2.normalization.is_some() {
+            return Err(Error::HeaplessLimit(
+                "pre-hashing and normalization need a heap copy",
+            ));
+        }
+
+        backend::hash_in_memory(argon2, password, salt, &[], out, &mut self.memory)
+    }
+}
+
+impl<const BLOCKS: usize> Default for StaticHasher<BLOCKS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_hasher() {
+        let mut hasher = Box::new(StaticHasher::<256>::new());
+        let salt = b"somesaltsomesalt";
+        let argon2 = Argon2::new(256, 2, 1);
+
+        let key: [u8; 32] = hasher.hash(&argon2, "password", salt).unwrap();
+        assert_eq!(
+            key.to_vec(),
+            argon2
+                .clone()
+                .with_hash_length(32)
+                .hash_password("password", salt)
+                .unwrap()
+        );
+        assert!(hasher.verify(&argon2, "password", salt, &key).unwrap());
+        assert!(!hasher.verify(&argon2, "wrong", salt, &key).unwrap());
+
+        // Several lanes computed by a single thread
+        let lanes = Argon2::new(256, 2, 4).with_threads(1);
+        let key: [u8; 32] = hasher.hash(&lanes, "password", salt).unwrap();
+        assert_eq!(
+            key.to_vec(),
+            Argon2::new(256, 2, 4)
+                .with_hash_length(32)
+                .hash_password("password", salt)
+                .unwrap()
+        );
+
+        let limits = [
+            Argon2::new(512, 2, 1),
+            Argon2::new(256, 2, 2),
+            argon2.clone().with_flags(Flags::CLEAR_PASSWORD),
+            argon2.clone().with_prehash(1024),
+            argon2
+                .clone()
+                .with_normalization(crate::normalize::Normalization::Nfc),
+        ];
+        for argon2 in limits {
+            assert!(matches!(
+                hasher.hash::<32>(&argon2, "password", salt),
+                Err(Error::HeaplessLimit(_))
+            ));
+        }
+
+        assert!(matches!(
+            hasher.verify(&argon2, "password", salt, &[0u8; 65]),
+            Err(Error::HeaplessLimit(_))
+        ));
+    }
+}
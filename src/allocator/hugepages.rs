@@ -0,0 +1,183 @@
+use super::{BLOCK_SIZE, Block};
+use crate::Error;
+use std::ptr::NonNull;
+
+/// The default huge page size on x86_64 and aarch64
+pub const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// How [`HugePageMemory`] is backed by huge pages
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HugePages {
+    /// Mapped with `MAP_HUGETLB` from the pages reserved in `/proc/sys/vm/nr_hugepages`
+    Explicit,
+    /// Mapped at a huge page boundary and advised with `MADV_HUGEPAGE`, the kernel backs it with
+    /// transparent huge pages when it can
+    Transparent,
+}
+
+/// Working memory backed by huge pages
+///
+/// With memory costs in the GiB range every block access misses the TLB when the memory is
+/// backed by 4 KiB pages. Huge pages cover the same memory with 512 times fewer TLB entries.
+///
+/// Hash with it through [`Argon2::hash_password_in_memory`](crate::Argon2::hash_password_in_memory).
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, allocator::HugePageMemory};
+///
+/// let argon2 = Argon2::new(4096, 2, 1);
+/// let mut memory = HugePageMemory::new(argon2.memory_blocks()).unwrap();
+///
+/// let mut key = [0u8; 32];
+/// argon2
+///     .hash_password_in_memory("password", b"somesaltsomesalt", &mut key, &mut memory)
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct HugePageMemory {
+    ptr: NonNull<Block>,
+    blocks: usize,
+    mapped: usize,
+    kind: HugePages,
+}
+
+// The mapping is owned and only accessed through `&self` or `&mut self`
+unsafe impl Send for HugePageMemory {}
+unsafe impl Sync for HugePageMemory {}
+
+impl HugePageMemory {
+    /// Maps `blocks` blocks with explicit huge pages, or transparent ones if none are reserved
+    pub fn new(blocks: usize) -> Result<Self, Error> {
+        Self::with_kind(blocks, HugePages::Explicit)
+            .or_else(|_| Self::with_kind(blocks, HugePages::Transparent))
+    }
+
+    /// Maps `blocks` blocks with the given kind of huge pages
+    ///
+    /// [`HugePages::Explicit`] fails if not enough huge pages are reserved.
+    pub fn with_kind(blocks: usize, kind: HugePages) -> Result<Self, Error> {
+        let mapped = blocks
+            .checked_mul(BLOCK_SIZE)
+            .and_then(|bytes| bytes.max(1).checked_next_multiple_of(HUGE_PAGE_SIZE))
+            .ok_or_else(|| Error::Memory("memory size overflows the address space".to_string()))?;
+
+        let ptr = match kind {
+            HugePages::Explicit => map(mapped, libc::MAP_HUGETLB)?,
+            HugePages::Transparent => map_transparent(mapped)?,
+        };
+
+        // The mapping is never reused by the heap, the advice needs no undoing
+        #[cfg(feature = "hardened-memory")]
+        super::harden::advise(ptr.as_ptr(), mapped, true);
+
+        Ok(Self {
+            ptr: ptr.cast(),
+            blocks,
+            mapped,
+            kind,
+        })
+    }
+
+    /// The kind of huge pages backing the memory
+    pub fn kind(&self) -> HugePages {
+        self.kind
+    }
+}
+
+impl std::ops::Deref for HugePageMemory {
+    type Target = [Block];
+
+    fn deref(&self) -> &[Block] {
+        // Anonymous mappings are zeroed and every bit pattern is a valid block
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.blocks) }
+    }
+}
+
+impl std::ops::DerefMut for HugePageMemory {
+    fn deref_mut(&mut self) -> &mut [Block] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.blocks) }
+    }
+}
+
+impl Drop for HugePageMemory {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.as_ptr().cast(), self.mapped) };
+    }
+}
+
+fn map(len: usize, flags: i32) -> Result<NonNull<u8>, Error> {
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+
+    if ptr == libc::MAP_FAILED {
+        return Err(Error::Memory(std::io::Error::last_os_error().to_string()));
+    }
+    Ok(NonNull::new(ptr.cast()).expect("mmap returned null"))
+}
+
+/// Maps `len` bytes at a huge page boundary, the kernel only uses transparent huge pages for
+/// aligned ranges
+fn map_transparent(len: usize) -> Result<NonNull<u8>, Error> {
+    let padded = len
+        .checked_add(HUGE_PAGE_SIZE)
+        .ok_or_else(|| Error::Memory("memory size overflows the address space".to_string()))?;
+    let ptr = map(padded, 0)?.as_ptr();
+
+    let head = ptr.align_offset(HUGE_PAGE_SIZE);
+    let tail = padded - head - len;
+    unsafe {
+        if head > 0 {
+            libc::munmap(ptr.cast(), head);
+        }
+        if tail > 0 {
+            libc::munmap(ptr.add(head + len).cast(), tail);
+        }
+    }
+
+    let ptr = unsafe { ptr.add(head) };
+    // Only a hint, it fails if transparent huge pages are disabled in the kernel
+    unsafe { libc::madvise(ptr.cast(), len, libc::MADV_HUGEPAGE) };
+    Ok(NonNull::new(ptr).expect("mmap returned null"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Argon2;
+
+    #[test]
+    fn test_huge_page_memory() {
+        let argon2 = Argon2::new(4096, 1, 2);
+        let salt = b"somesaltsomesalt";
+        let expected = argon2
+            .clone()
+            .with_hash_length(32)
+            .hash_password("password", salt)
+            .unwrap();
+
+        let mut memory =
+            HugePageMemory::with_kind(argon2.memory_blocks(), HugePages::Transparent).unwrap();
+        assert_eq!(memory.len(), argon2.memory_blocks());
+        assert_eq!(memory.as_ptr() as usize % HUGE_PAGE_SIZE, 0);
+
+        let mut hash = [0u8; 32];
+        argon2
+            .hash_password_in_memory("password", salt, &mut hash, &mut memory)
+            .unwrap();
+        assert_eq!(hash.to_vec(), expected);
+
+        // Falls back to transparent huge pages when none are reserved
+        let memory = HugePageMemory::new(argon2.memory_blocks()).unwrap();
+        assert_eq!(memory.len(), argon2.memory_blocks());
+    }
+}
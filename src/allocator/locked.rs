@@ -0,0 +1,345 @@
+use super::{BLOCK_SIZE, Block, MemoryAllocator, page_size};
+use crate::{Argon2, Error, backend, output::wipe};
+use std::alloc::Layout;
+use std::ptr::NonNull;
+
+/// The maximum number of bytes the process may lock into RAM, `None` if unlimited or unknown
+///
+/// This is the soft `RLIMIT_MEMLOCK` on Unix, often only 64 KiB or 8 MiB for unprivileged
+/// processes. Memory already locked by the process counts against the same limit.
+pub fn memlock_limit() -> Option<usize> {
+    #[cfg(unix)]
+    {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) } != 0
+            || limit.rlim_cur == libc::RLIM_INFINITY
+        {
+            return None;
+        }
+        Some(usize::try_from(limit.rlim_cur).unwrap_or(usize::MAX))
+    }
+
+    #[cfg(windows)]
+    {
+        None
+    }
+}
+
+/// Locks the pages of the given memory into RAM, returns whether it succeeded
+///
+/// Locking is skipped without a system call if the memory exceeds [`memlock_limit`].
+fn lock(ptr: *const u8, len: usize) -> bool {
+    if len == 0 || memlock_limit().is_some_and(|limit| len > limit) {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        unsafe { libc::mlock(ptr.cast(), len) == 0 }
+    }
+
+    #[cfg(windows)]
+    {
+        unsafe { windows_sys::Win32::System::Memory::VirtualLock(ptr.cast(), len) != 0 }
+    }
+}
+
+fn unlock(ptr: *const u8, len: usize) {
+    #[cfg(unix)]
+    unsafe {
+        libc::munlock(ptr.cast(), len);
+    }
+
+    #[cfg(windows)]
+    unsafe {
+        windows_sys::Win32::System::Memory::VirtualUnlock(ptr.cast(), len);
+    }
+}
+
+/// Allocates working memory that is locked into RAM so it is never written to swap
+///
+/// If the memory cannot be locked, because it exceeds [`memlock_limit`] or the system refuses,
+/// the hash goes ahead with unlocked memory. [`LockedAllocator::is_locked`] tells whether the
+/// last allocation was locked.
+///
+/// On Linux the memory is also excluded from core dumps and wiped in forked children like with
+/// [`HardenedAllocator`](super::HardenedAllocator).
+#[derive(Debug, Default)]
+pub struct LockedAllocator {
+    locked: bool,
+}
+
+impl LockedAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the memory of the last hash was locked into RAM
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl MemoryAllocator for LockedAllocator {
+    fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+        let memory = Block::try_alloc(blocks)?;
+        self.locked = lock(memory.as_ptr().cast(), blocks * BLOCK_SIZE);
+        #[cfg(target_os = "linux")]
+        super::harden::advise(memory.as_ptr().cast(), blocks * BLOCK_SIZE, true);
+        Some(memory)
+    }
+
+    fn free(&mut self, memory: Box<[Block]>) {
+        // The C implementation has already wiped the memory
+        #[cfg(target_os = "linux")]
+        super::harden::advise(memory.as_ptr().cast(), memory.len() * BLOCK_SIZE, false);
+        if self.locked {
+            unlock(memory.as_ptr().cast(), memory.len() * BLOCK_SIZE);
+        }
+    }
+}
+
+/// A secret kept in memory that is locked into RAM, wiped and unlocked when dropped
+///
+/// Holds peppers and derived keys for their whole lifetime. Locking works on whole pages, the
+/// secret is kept in a buffer of its own, aligned to and sized in pages of the running system,
+/// so that unlocking it never unlocks other memory. Locking falls back the same way as
+/// [`LockedAllocator`]. On Linux the buffer is also excluded from core dumps and wiped in forked
+/// children.
+///
+/// It dereferences to bytes wherever a secret is passed as a slice, and peppers keep it through
+/// [`Pepper::locked`](crate::pepper::Pepper::locked).
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, allocator::LockedSecret, pepper::{KeyedArgon2, Pepper}};
+///
+/// let secret = LockedSecret::from(b"pepper".to_vec());
+/// assert_eq!(format!("{secret:?}"), "LockedSecret([REDACTED])");
+///
+/// let keyed = KeyedArgon2::new(Argon2::new(1024, 2, 1), Pepper::locked("v1", secret));
+/// let stored = keyed
+///     .hash_password_encoded("password", b"somesaltsomesalt")
+///     .unwrap();
+/// assert!(keyed.verify_encoded("password", &stored).unwrap());
+/// ```
+pub struct LockedSecret {
+    buffer: NonNull<u8>,
+    layout: Layout,
+    len: usize,
+    locked: bool,
+}
+
+// The buffer is owned and only accessed through `&self` or `&mut self`
+unsafe impl Send for LockedSecret {}
+unsafe impl Sync for LockedSecret {}
+
+/// The derived key returned by [`Argon2::hash_password_locked`]
+pub type LockedBytes = LockedSecret;
+
+impl LockedSecret {
+    /// Copies the secret into locked memory
+    ///
+    /// The caller's copy is left as it is, see the `From<Vec<u8>>` implementation to wipe it.
+    pub fn new(secret: &[u8]) -> Self {
+        let mut locked = Self::zeroed(secret.len());
+        locked.as_mut_bytes().copy_from_slice(secret);
+        locked
+    }
+
+    /// Allocates whole pages of the running system, at least one, aligned to a page
+    fn zeroed(len: usize) -> Self {
+        let page = page_size();
+        let layout = len
+            .max(1)
+            .checked_next_multiple_of(page)
+            .and_then(|size| Layout::from_size_align(size, page).ok())
+            .expect("secret size overflows the address space");
+        let buffer = NonNull::new(unsafe { std::alloc::alloc_zeroed(layout) })
+            .unwrap_or_else(|| std::alloc::handle_alloc_error(layout));
+
+        let locked = lock(buffer.as_ptr(), layout.size());
+        #[cfg(target_os = "linux")]
+        super::harden::advise(buffer.as_ptr(), layout.size(), true);
+        Self {
+            buffer,
+            layout,
+            len,
+            locked,
+        }
+    }
+
+    fn as_mut_bytes(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.buffer.as_ptr(), self.len) }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.buffer.as_ptr(), self.len) }
+    }
+
+    /// Whether the secret is locked into RAM
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl Clone for LockedSecret {
+    fn clone(&self) -> Self {
+        Self::new(self.as_bytes())
+    }
+}
+
+impl From<Vec<u8>> for LockedSecret {
+    /// Moves the secret into locked memory and wipes the vector
+    fn from(mut secret: Vec<u8>) -> Self {
+        let locked = Self::new(&secret);
+        wipe(&mut secret);
+        locked
+    }
+}
+
+impl std::ops::Deref for LockedSecret {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<[u8]> for LockedSecret {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl std::fmt::Debug for LockedSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LockedSecret([REDACTED])")
+    }
+}
+
+impl Drop for LockedSecret {
+    fn drop(&mut self) {
+        wipe(self.as_mut_bytes());
+        #[cfg(target_os = "linux")]
+        super::harden::advise(self.buffer.as_ptr(), self.layout.size(), false);
+        if self.locked {
+            unlock(self.buffer.as_ptr(), self.layout.size());
+        }
+        unsafe { std::alloc::dealloc(self.buffer.as_ptr(), self.layout) };
+    }
+}
+
+impl Argon2 {
+    /// Hashes the given password with the working memory and the hash locked into RAM
+    ///
+    /// Neither the intermediate state nor the derived key is ever written to swap, unless
+    /// locking fails and falls back as described on [`LockedAllocator`].
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 1).with_hash_length(32);
+    /// let key = argon2.hash_password_locked("password", b"somesaltsomesalt").unwrap();
+    /// assert_eq!(key.len(), 32);
+    /// ```
+    pub fn hash_password_locked(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<LockedBytes, Error> {
+        let mut hash = LockedBytes::zeroed(self.hash_len()?);
+        let mut allocator = LockedAllocator::new();
+        backend::hash_with_allocator(
+            self,
+            password.as_ref(),
+            salt,
+            &[],
+            hash.as_mut_bytes(),
+            &mut allocator,
+        )?;
+        Ok(hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_password_locked() {
+        let argon2 = Argon2::new(32, 2, 1).with_hash_length(32);
+        let salt = b"somesaltsomesalt";
+
+        let key = argon2.hash_password_locked("password", salt).unwrap();
+        assert_eq!(
+            key.as_bytes(),
+            argon2.hash_password("password", salt).unwrap()
+        );
+        assert_eq!(format!("{key:?}"), "LockedSecret([REDACTED])");
+
+        let mut allocator = LockedAllocator::new();
+        argon2
+            .hash_password_with_allocator("password", salt, &mut allocator)
+            .unwrap();
+
+        // 32 KiB of working memory fits in the usual default limits
+        if memlock_limit().is_none_or(|limit| limit >= 64 * 1024) {
+            assert!(key.is_locked());
+            assert!(allocator.is_locked());
+        }
+    }
+
+    #[test]
+    fn test_locked_secret() {
+        use crate::pepper::{KeyedArgon2, Pepper};
+
+        let secret = LockedSecret::from(b"secret".to_vec());
+        assert_eq!(secret.as_bytes(), b"secret");
+        assert_eq!(secret.clone().as_bytes(), b"secret");
+        assert_eq!(LockedSecret::new(&[]).as_bytes(), b"");
+
+        // The buffer takes whole pages of its own
+        let page = page_size();
+        for len in [0, 6, page, page + 1] {
+            let secret = LockedSecret::new(&vec![1; len]);
+            assert_eq!(secret.buffer.as_ptr() as usize % page, 0);
+            assert_eq!(secret.layout.size(), len.max(1).next_multiple_of(page));
+        }
+
+        let argon2 = Argon2::new(32, 2, 1);
+        let salt = b"somesaltsomesalt";
+        let locked = KeyedArgon2::new(argon2.clone(), Pepper::locked("v1", secret));
+        let heap = KeyedArgon2::new(argon2, Pepper::new("v1", "secret"));
+        let stored = locked.hash_password_encoded("password", salt).unwrap();
+        assert_eq!(
+            stored,
+            heap.hash_password_encoded("password", salt).unwrap()
+        );
+        assert!(heap.verify_encoded("password", &stored).unwrap());
+    }
+
+    #[test]
+    fn test_lock_falls_back_over_limit() {
+        let Some(limit) = memlock_limit() else {
+            return;
+        };
+
+        let blocks = limit / BLOCK_SIZE + 1;
+        let mut allocator = LockedAllocator::new();
+        let memory = allocator.allocate(blocks).unwrap();
+        assert!(!allocator.is_locked());
+        allocator.free(memory);
+    }
+}
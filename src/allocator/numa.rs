@@ -0,0 +1,224 @@
+use super::{BLOCK_SIZE, Block, MemoryAllocator, whole_pages};
+use crate::Error;
+
+/// Where [`NumaAllocator`] places the working memory
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NumaPolicy {
+    /// Spreads the pages round-robin over all online nodes, so every lane thread sees the same
+    /// mix of local and remote memory
+    Interleave,
+    /// Places the pages on the given node and runs the lane threads on its CPUs
+    Bind(usize),
+}
+
+/// Places the working memory on NUMA nodes
+///
+/// At high memory and parallelism costs the traffic between nodes dominates the runtime. The
+/// memory policy is set with `mbind` before the pages are first touched. With
+/// [`NumaPolicy::Bind`] the hashing thread is pinned to the CPUs of the node between allocating
+/// and freeing the memory, the lane threads of the C implementation inherit the pinning. Its
+/// previous affinity is restored afterwards.
+///
+/// Failing system calls, e.g. in containers without `CAP_SYS_NICE`, leave the memory and threads
+/// where the kernel puts them by default.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, allocator::{NumaAllocator, NumaPolicy}};
+///
+/// let argon2 = Argon2::new(4096, 1, 4);
+/// let mut allocator = NumaAllocator::new(NumaPolicy::Interleave).unwrap();
+///
+/// let hash = argon2
+///     .hash_password_with_allocator("password", b"somesaltsomesalt", &mut allocator)
+///     .unwrap();
+/// assert_eq!(hash, argon2.hash_password("password", b"somesaltsomesalt").unwrap());
+/// ```
+pub struct NumaAllocator {
+    policy: NumaPolicy,
+    nodes: Vec<usize>,
+    cpus: Vec<usize>,
+    previous_affinity: Option<libc::cpu_set_t>,
+}
+
+const NODE_DIR: &str = "/sys/devices/system/node";
+
+impl NumaAllocator {
+    /// Returns [`Error::Memory`] if the system has no NUMA information or the node of
+    /// [`NumaPolicy::Bind`] does not exist
+    pub fn new(policy: NumaPolicy) -> Result<Self, Error> {
+        let (nodes, cpus) = match policy {
+            NumaPolicy::Interleave => (read_list(&format!("{NODE_DIR}/online"))?, Vec::new()),
+            NumaPolicy::Bind(node) => (
+                vec![node],
+                read_list(&format!("{NODE_DIR}/node{node}/cpulist"))
+                    .map_err(|_| Error::Memory(format!("NUMA node {node} not found")))?,
+            ),
+        };
+
+        Ok(Self {
+            policy,
+            nodes,
+            cpus,
+            previous_affinity: None,
+        })
+    }
+
+    pub fn policy(&self) -> NumaPolicy {
+        self.policy
+    }
+
+    /// The nodes the memory is placed on
+    pub fn nodes(&self) -> &[usize] {
+        &self.nodes
+    }
+
+    fn pin_thread(&mut self) {
+        let mut previous: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        let size = std::mem::size_of::<libc::cpu_set_t>();
+        if unsafe { libc::sched_getaffinity(0, size, &mut previous) } != 0 {
+            return;
+        }
+
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for &cpu in &self.cpus {
+            if cpu < libc::CPU_SETSIZE as usize {
+                unsafe { libc::CPU_SET(cpu, &mut set) };
+            }
+        }
+
+        if unsafe { libc::sched_setaffinity(0, size, &set) } == 0 {
+            self.previous_affinity = Some(previous);
+        }
+    }
+
+    fn unpin_thread(&mut self) {
+        if let Some(previous) = self.previous_affinity.take() {
+            let size = std::mem::size_of::<libc::cpu_set_t>();
+            unsafe { libc::sched_setaffinity(0, size, &previous) };
+        }
+    }
+}
+
+impl MemoryAllocator for NumaAllocator {
+    fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+        let memory = Block::try_alloc(blocks)?;
+
+        let mode = match self.policy {
+            NumaPolicy::Interleave => libc::MPOL_INTERLEAVE,
+            NumaPolicy::Bind(_) => libc::MPOL_BIND,
+        };
+        mbind(&memory, mode, &self.nodes);
+
+        if matches!(self.policy, NumaPolicy::Bind(_)) {
+            self.pin_thread();
+        }
+        Some(memory)
+    }
+
+    fn free(&mut self, memory: Box<[Block]>) {
+        self.unpin_thread();
+        // The policy sticks to the pages, the heap may hand them out again
+        mbind(&memory, libc::MPOL_DEFAULT, &[]);
+    }
+}
+
+impl Drop for NumaAllocator {
+    fn drop(&mut self) {
+        self.unpin_thread();
+    }
+}
+
+fn mbind(memory: &[Block], mode: i32, nodes: &[usize]) {
+    let Some((ptr, len)) = whole_pages(memory.as_ptr().cast(), memory.len() * BLOCK_SIZE) else {
+        return;
+    };
+
+    let bits = libc::c_ulong::BITS as usize;
+    let max_node = nodes.iter().max().map_or(0, |&node| node + 1);
+    let mut mask = vec![0 as libc::c_ulong; max_node.div_ceil(bits).max(1)];
+    for &node in nodes {
+        mask[node / bits] |= 1 << (node % bits);
+    }
+
+    // The kernel ignores the last bit of `maxnode`
+    let max_node = if nodes.is_empty() {
+        0
+    } else {
+        mask.len() * bits + 1
+    };
+    let mask = if nodes.is_empty() {
+        std::ptr::null()
+    } else {
+        mask.as_ptr()
+    };
+
+    unsafe {
+        libc::syscall(libc::SYS_mbind, ptr, len, mode, mask, max_node, 0);
+    }
+}
+
+fn read_list(path: &str) -> Result<Vec<usize>, Error> {
+    let list = std::fs::read_to_string(path).map_err(|e| Error::Memory(format!("{path}: {e}")))?;
+    parse_list(&list).ok_or_else(|| Error::Memory(format!("{path}: invalid list {list:?}")))
+}
+
+/// Parses a kernel list such as `0-3,8-11`
+fn parse_list(list: &str) -> Option<Vec<usize>> {
+    let list = list.trim();
+    if list.is_empty() {
+        return Some(Vec::new());
+    }
+
+    let mut items = Vec::new();
+    for range in list.split(',') {
+        match range.split_once('-') {
+            Some((start, end)) => items.extend(start.parse::<usize>().ok()?..=end.parse().ok()?),
+            None => items.push(range.parse().ok()?),
+        }
+    }
+    Some(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Argon2;
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list("0\n"), Some(vec![0]));
+        assert_eq!(parse_list("0-3,8-9"), Some(vec![0, 1, 2, 3, 8, 9]));
+        assert_eq!(parse_list(""), Some(vec![]));
+        assert_eq!(parse_list("0-a"), None);
+    }
+
+    #[test]
+    fn test_numa_allocator() {
+        if !std::path::Path::new(NODE_DIR).exists() {
+            return;
+        }
+
+        let argon2 = Argon2::new(4096, 1, 4);
+        let salt = b"somesaltsomesalt";
+        let expected = argon2.hash_password("password", salt).unwrap();
+
+        for policy in [NumaPolicy::Interleave, NumaPolicy::Bind(0)] {
+            let mut allocator = NumaAllocator::new(policy).unwrap();
+            assert!(allocator.nodes().contains(&0));
+            assert_eq!(
+                argon2
+                    .hash_password_with_allocator("password", salt, &mut allocator)
+                    .unwrap(),
+                expected
+            );
+            assert!(allocator.previous_affinity.is_none());
+        }
+
+        assert!(matches!(
+            NumaAllocator::new(NumaPolicy::Bind(usize::MAX)),
+            Err(Error::Memory(_))
+        ));
+    }
+}
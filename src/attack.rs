@@ -0,0 +1,126 @@
+use super::Argon2;
+
+/// The hardware an attacker is assumed to crack hashes with
+///
+/// Argon2 is memory-hard, on GPUs the guess rate is bound by how fast the memory can be moved
+/// rather than by compute, so the model only needs the memory bandwidth and the price of the
+/// hardware per hour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AttackModel {
+    /// Memory bandwidth in bytes per second
+    pub bandwidth: f64,
+    /// The memory of one device in bytes, hashes that need more cannot run on it at all
+    pub memory: f64,
+    /// The price of renting one device for an hour, in dollars
+    pub dollars_per_hour: f64,
+}
+
+impl AttackModel {
+    /// A rented high-end consumer GPU, about 1 TB/s of bandwidth and 24 GB of memory for $0.50 an
+    /// hour
+    pub fn consumer_gpu() -> Self {
+        Self {
+            bandwidth: 1.0e12,
+            memory: 24.0e9,
+            dollars_per_hour: 0.5,
+        }
+    }
+
+    /// A rented datacenter GPU, about 3.3 TB/s of bandwidth and 80 GB of memory for $2.50 an hour
+    pub fn datacenter_gpu() -> Self {
+        Self {
+            bandwidth: 3.3e12,
+            memory: 80.0e9,
+            dollars_per_hour: 2.5,
+        }
+    }
+}
+
+/// The estimated cost of guessing passwords hashed with given parameters
+///
+/// See [`Argon2::estimate_attack_cost`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AttackCost {
+    /// Guesses per second one device can make
+    pub hashes_per_second: f64,
+    /// Guesses one dollar buys
+    pub hashes_per_dollar: f64,
+}
+
+impl AttackCost {
+    /// The dollars needed to try the given number of guesses, e.g. `2f64.powi(40)` for a password
+    /// with 40 bits of entropy
+    ///
+    /// Infinite if the hash does not fit in the memory of the device.
+    pub fn dollars_for(&self, guesses: f64) -> f64 {
+        guesses / self.hashes_per_dollar
+    }
+}
+
+impl Argon2 {
+    /// Approximates what cracking hashes with these parameters costs on the given hardware
+    ///
+    /// Each pass reads two blocks and writes one for every block of the working memory, so a guess
+    /// moves 3 times the working memory per pass. The estimate is a lower bound in favor of the
+    /// attacker, it ignores the compute, the lanes that cannot fill the device and the
+    /// time-memory tradeoffs that make Argon2i and Argon2d cheaper to attack. Use it to compare
+    /// parameters, not as a price quote.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, attack::AttackModel};
+    ///
+    /// let model = AttackModel::consumer_gpu();
+    /// let weak = Argon2::new(8 * 1024, 1, 1).estimate_attack_cost(&model);
+    /// let strong = Argon2::rfc9106_low_memory().estimate_attack_cost(&model);
+    ///
+    /// // Guessing a password with 40 bits of entropy
+    /// let guesses = 2f64.powi(40);
+    /// assert!(strong.dollars_for(guesses) > 20.0 * weak.dollars_for(guesses));
+    /// ```
+    pub fn estimate_attack_cost(&self, model: &AttackModel) -> AttackCost {
+        let memory = self.working_memory_bytes() as f64;
+        let hashes_per_second = if memory > model.memory {
+            0.0
+        } else {
+            model.bandwidth / (3.0 * memory * self.t_cost as f64)
+        };
+
+        AttackCost {
+            hashes_per_second,
+            hashes_per_dollar: hashes_per_second * 3600.0 / model.dollars_per_hour,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_attack_cost() {
+        let model = AttackModel {
+            bandwidth: 3.0 * 1024.0 * 1024.0 * 1024.0,
+            memory: 1e12,
+            dollars_per_hour: 3600.0,
+        };
+
+        // 3 GiB/s moves a 1 GiB working memory once per second
+        let cost = Argon2::new(1024 * 1024, 1, 4).estimate_attack_cost(&model);
+        assert_eq!(cost.hashes_per_second, 1.0);
+        assert_eq!(cost.hashes_per_dollar, 1.0);
+        assert_eq!(cost.dollars_for(1000.0), 1000.0);
+
+        let cost = Argon2::new(1024 * 1024, 4, 4).estimate_attack_cost(&model);
+        assert_eq!(cost.hashes_per_second, 0.25);
+
+        let too_large = AttackModel {
+            memory: 1024.0,
+            ..model
+        };
+        let cost = Argon2::new(1024 * 1024, 1, 4).estimate_attack_cost(&too_large);
+        assert_eq!(cost.hashes_per_second, 0.0);
+        assert!(cost.dollars_for(1.0).is_infinite());
+    }
+}
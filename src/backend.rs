@@ -0,0 +1,658 @@
+use super::{
+    Argon2, Error, check_hash_length, instrument::Operation, limit, output::HashBytes, policy, salt,
+};
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+use super::{
+    allocator::{BLOCK_SIZE, Block, MemoryAllocator},
+    prealloc::LanePool,
+};
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+mod segments;
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub(crate) use segments::{Control, Timings};
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub(crate) use argon2_sys::{
+    ARGON2_DEFAULT_FLAGS, ARGON2_FLAG_CLEAR_PASSWORD, ARGON2_FLAG_CLEAR_SECRET, ARGON2_MAX_OUTLEN,
+    ARGON2_MIN_OUTLEN,
+};
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("the C implementation cannot be built for wasm32, enable the `wasm` feature");
+
+#[cfg(all(
+    not(target_arch = "wasm32"),
+    not(feature = "c"),
+    not(feature = "pure-rust")
+))]
+compile_error!("enable the `c` feature for the C implementation or `pure-rust` for the Rust one");
+
+// Same values as the C implementation so flags and limits behave the same on every target
+#[cfg(not(all(feature = "c", not(target_arch = "wasm32"))))]
+pub(crate) const ARGON2_DEFAULT_FLAGS: u32 = 0;
+#[cfg(not(all(feature = "c", not(target_arch = "wasm32"))))]
+pub(crate) const ARGON2_FLAG_CLEAR_PASSWORD: u32 = 1;
+#[cfg(not(all(feature = "c", not(target_arch = "wasm32"))))]
+pub(crate) const ARGON2_FLAG_CLEAR_SECRET: u32 = 2;
+#[cfg(not(all(feature = "c", not(target_arch = "wasm32"))))]
+pub(crate) const ARGON2_MIN_OUTLEN: u64 = 4;
+#[cfg(not(all(feature = "c", not(target_arch = "wasm32"))))]
+pub(crate) const ARGON2_MAX_OUTLEN: u64 = 0xFFFF_FFFF;
+
+/// Rejects inputs either implementation would reject or silently truncate
+///
+/// Every length is passed to the implementation as a `u32`, checking them up front keeps a
+/// 4 GiB password from being cut short.
+fn check_inputs(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &[u8],
+) -> Result<(), Error> {
+    use super::error::Argon2Error;
+
+    policy::check_algorithm(argon2.algorithm)?;
+    limit::check_password_length(password.len())?;
+    salt::check(salt)?;
+    check_hash_length(out.len() as u64)?;
+
+    let too_long = [
+        (password.len(), Argon2Error::PasswordTooLong),
+        (salt.len(), Argon2Error::SaltTooLong),
+        (secret.len(), Argon2Error::SecretTooLong),
+        (argon2.ad.len(), Argon2Error::AdTooLong),
+    ];
+    for (len, error) in too_long {
+        if u32::try_from(len).is_err() {
+            return Err(error.into());
+        }
+    }
+    Ok(())
+}
+
+/// The password the implementation is given instead, normalized and pre-hashed as the
+/// parameters say
+pub(crate) fn prepare_password(
+    argon2: &Argon2,
+    password: &[u8],
+) -> Result<Option<HashBytes>, Error> {
+    let normalized = argon2.normalized(password)?;
+    let digest = argon2.prehashed(normalized.as_deref().unwrap_or(password));
+    Ok(digest.or(normalized))
+}
+
+/// Wipes the output buffer when dropped unless the hash succeeded
+///
+/// An error from the implementation or a panic unwinding mid-hash would otherwise leave a partial
+/// hash in a buffer the caller frees or reuses.
+struct OutputGuard<'a> {
+    out: &'a mut [u8],
+    done: bool,
+}
+
+impl<'a> OutputGuard<'a> {
+    fn new(out: &'a mut [u8]) -> Self {
+        Self { out, done: false }
+    }
+
+    fn finish(mut self, result: Result<(), Error>) -> Result<(), Error> {
+        self.done = result.is_ok();
+        result
+    }
+}
+
+impl Drop for OutputGuard<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            super::output::wipe(self.out);
+        }
+    }
+}
+
+/// Hashes with the C implementation, or the RustCrypto one with the `pure-rust` feature or on
+/// wasm32
+pub(crate) fn hash(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    #[cfg(any(feature = "pure-rust", target_arch = "wasm32"))]
+    {
+        hash_rust(argon2, password, salt, secret, out)
+    }
+
+    #[cfg(not(any(feature = "pure-rust", target_arch = "wasm32")))]
+    {
+        hash_c(argon2, password, salt, secret, out)
+    }
+}
+
+/// Hashes with the C implementation
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub(crate) fn hash_c(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    hash_in(argon2, password, salt, secret, out, None, None)
+}
+
+/// Hashes with the C implementation in working memory from the given allocator
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub(crate) fn hash_with_allocator(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+    allocator: &mut dyn MemoryAllocator,
+) -> Result<(), Error> {
+    let memory = WorkingMemory::Allocator(allocator);
+    hash_in(argon2, password, salt, secret, out, Some(memory), None)
+}
+
+/// Hashes with the C implementation in working memory from the given allocator, filling the
+/// lanes on the threads of the given pool
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub(crate) fn hash_with_pool(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+    allocator: &mut dyn MemoryAllocator,
+    pool: &LanePool,
+) -> Result<(), Error> {
+    let memory = WorkingMemory::Allocator(allocator);
+    let control = Control {
+        pool: Some(pool),
+        ..Default::default()
+    };
+    hash_in(
+        argon2,
+        password,
+        salt,
+        secret,
+        out,
+        Some(memory),
+        Some(&control),
+    )
+}
+
+/// Hashes with the C implementation, filling the segments as `control` says
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub(crate) fn hash_with_control(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+    control: &Control,
+) -> Result<(), Error> {
+    hash_in(argon2, password, salt, secret, out, None, Some(control))
+}
+
+/// Hashes with the C implementation in the given working memory
+///
+/// `memory` must be at least [`Argon2::memory_blocks`] blocks for `argon2`, the C implementation
+/// fails with `ARGON2_MEMORY_ALLOCATION_ERROR` otherwise.
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub(crate) fn hash_in_memory(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+    memory: &mut [Block],
+) -> Result<(), Error> {
+    hash_in(
+        argon2,
+        password,
+        salt,
+        secret,
+        out,
+        Some(WorkingMemory::Slice(memory)),
+        None,
+    )
+}
+
+/// Where the working memory of the C implementation comes from, if not from `malloc`
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+enum WorkingMemory<'a> {
+    Allocator(&'a mut dyn MemoryAllocator),
+    Slice(&'a mut [Block]),
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+fn hash_in(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+    memory: Option<WorkingMemory>,
+    control: Option<&Control>,
+) -> Result<(), Error> {
+    let operation = Operation::hash(argon2);
+    let guard = OutputGuard::new(out);
+    let result = hash_in_guarded(argon2, password, salt, secret, guard.out, memory, control);
+    operation.finish_hash(&result);
+    guard.finish(result)
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+fn hash_in_guarded(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+    memory: Option<WorkingMemory>,
+    control: Option<&Control>,
+) -> Result<(), Error> {
+    use super::{Flags, error::Argon2Error};
+    use argon2_sys::{argon2_context, argon2_ctx};
+
+    check_inputs(argon2, password, salt, secret, out)?;
+    let prepared = prepare_password(argon2, password)?;
+    let password = prepared.as_deref().unwrap_or(password);
+    // The callbacks and allocators size the memory in bytes as a `usize`
+    argon2.working_memory_len()?;
+    let _permit = limit::acquire()?;
+
+    let mut password_input = CInput::new(password, argon2.flags, Flags::CLEAR_PASSWORD);
+    let mut secret_input = CInput::new(secret, argon2.flags, Flags::CLEAR_SECRET);
+
+    let mut context = argon2_context {
+        out: out.as_mut_ptr(),
+        outlen: out.len() as u32,
+        pwd: password_input.as_mut_ptr(),
+        pwdlen: password.len() as u32,
+        // The salt is only ever read by the C implementation
+        salt: salt.as_ptr() as *mut u8,
+        saltlen: salt.len() as u32,
+        secret: secret_input.as_mut_ptr(),
+        secretlen: secret.len() as u32,
+        // The associated data is only ever read by the C implementation
+        ad: argon2.ad.as_ptr() as *mut u8,
+        adlen: argon2.ad.len() as u32,
+        t_cost: argon2.t_cost,
+        m_cost: argon2.m_cost,
+        lanes: argon2.p_cost,
+        threads: argon2.threads(),
+        version: argon2.version as u32,
+        allocate_cbk: None,
+        free_cbk: None,
+        flags: argon2.flags.bits(),
+    };
+
+    // Only memory the caller sized can be too small, any other allocation failure is the
+    // allocator running out of memory
+    let caller_sized = matches!(memory, Some(WorkingMemory::Slice(_)));
+
+    // The callbacks take no user data, the memory is handed over through a thread local.
+    // The C implementation allocates and frees on the calling thread, not on its own threads.
+    let _lease = memory.map(|memory| {
+        context.allocate_cbk = Some(allocate_callback);
+        context.free_cbk = Some(free_callback);
+        MemoryLease::new(memory)
+    });
+
+    let ty = argon2.algorithm as u32;
+    let result = match control {
+        Some(control) => unsafe { segments::hash(&mut context, ty, control) },
+        None => match unsafe { argon2_ctx(&mut context, ty) } {
+            0 => Ok(()),
+            code => Err(Error::Argon2(Argon2Error::from_code(code))),
+        },
+    };
+    if let Some(payload) = PANIC.with(|cell| cell.borrow_mut().take()) {
+        std::panic::resume_unwind(payload);
+    }
+
+    match result {
+        Err(Error::Argon2(Argon2Error::MemoryAllocationError)) if !caller_sized => {
+            Err(Error::OutOfMemory)
+        }
+        result => result,
+    }
+}
+
+/// The buffer of the password or the secret handed to the C implementation as `*mut u8`
+///
+/// The C implementation writes to them only when their clear flag is set, it then gets a copy
+/// that is wiped when dropped. Otherwise it gets the caller's buffer, which it only reads.
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+enum CInput<'a> {
+    Borrowed(&'a [u8]),
+    Copied(Vec<u8>),
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+impl<'a> CInput<'a> {
+    fn new(input: &'a [u8], flags: super::Flags, clear: super::Flags) -> Self {
+        if flags.contains(clear) {
+            Self::Copied(input.to_vec())
+        } else {
+            Self::Borrowed(input)
+        }
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        match self {
+            Self::Borrowed(input) => input.as_ptr().cast_mut(),
+            Self::Copied(copy) => copy.as_mut_ptr(),
+        }
+    }
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+impl Drop for CInput<'_> {
+    fn drop(&mut self) {
+        // The C implementation skips the wipe when it fails before pre-hashing
+        if let Self::Copied(copy) = self {
+            super::output::wipe(copy);
+        }
+    }
+}
+
+/// [`WorkingMemory`] without its lifetime, to be stored in a thread local
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+#[derive(Clone, Copy)]
+enum Source {
+    Allocator(*mut (dyn MemoryAllocator + 'static)),
+    Slice(*mut Block, usize),
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+thread_local! {
+    static SOURCE: std::cell::Cell<Option<Source>> = const { std::cell::Cell::new(None) };
+    /// The memory handed to the C implementation and not yet freed by it
+    static IN_USE: std::cell::Cell<Option<(*mut u8, usize)>> = const { std::cell::Cell::new(None) };
+    static ALLOCATED: std::cell::RefCell<Option<Box<[Block]>>> =
+        const { std::cell::RefCell::new(None) };
+    /// A panic of the allocator, resumed once the C implementation has returned
+    static PANIC: std::cell::RefCell<Option<Box<dyn std::any::Any + Send>>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Makes the memory available to the callbacks of the current thread until dropped
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+struct MemoryLease {
+    previous: Option<Source>,
+    previous_in_use: Option<(*mut u8, usize)>,
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+impl MemoryLease {
+    fn new(memory: WorkingMemory) -> Self {
+        // The lease outlives no borrow of the memory, it is cleared before `hash_in` returns
+        let source = match memory {
+            WorkingMemory::Allocator(allocator) => Source::Allocator(unsafe {
+                std::mem::transmute::<*mut dyn MemoryAllocator, *mut (dyn MemoryAllocator + 'static)>(
+                    allocator,
+                )
+            }),
+            WorkingMemory::Slice(slice) => Source::Slice(slice.as_mut_ptr(), slice.len()),
+        };
+        // An allocator may itself hash with another allocator, which must not clobber this one
+        let previous = SOURCE.with(|cell| cell.replace(Some(source)));
+        let previous_in_use = IN_USE.with(|cell| cell.take());
+        Self {
+            previous,
+            previous_in_use,
+        }
+    }
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+impl Drop for MemoryLease {
+    fn drop(&mut self) {
+        // The C implementation neither frees nor wipes the memory on every error path, and not
+        // at all when a panic unwinds through it
+        if let Some((ptr, len)) = IN_USE.with(|cell| cell.take()) {
+            super::output::wipe(unsafe { std::slice::from_raw_parts_mut(ptr, len) });
+        }
+        free_allocated();
+        SOURCE.with(|cell| cell.set(self.previous));
+        IN_USE.with(|cell| cell.set(self.previous_in_use));
+    }
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+fn with_allocator<R>(f: impl FnOnce(&mut dyn MemoryAllocator) -> R) -> Option<R> {
+    match SOURCE.with(|cell| cell.get())? {
+        // Only the callbacks and the lease dereference the pointer, never at the same time
+        Source::Allocator(ptr) => Some(f(unsafe { &mut *ptr })),
+        Source::Slice(..) => None,
+    }
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+fn free_allocated() {
+    if let Some(memory) = ALLOCATED.with(|cell| cell.borrow_mut().take()) {
+        with_allocator(|allocator| allocator.free(memory));
+    }
+}
+
+/// Runs the allocator in a callback, a panic cannot unwind through the C frames
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+fn catch_panic<R>(f: impl FnOnce() -> R) -> Option<R> {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(result) => Some(result),
+        Err(payload) => {
+            PANIC.with(|cell| *cell.borrow_mut() = Some(payload));
+            None
+        }
+    }
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+unsafe extern "C" fn allocate_callback(memory: *mut *mut u8, bytes: usize) -> i32 {
+    let blocks = bytes.div_ceil(BLOCK_SIZE);
+
+    // A null pointer makes the C implementation fail with `ARGON2_MEMORY_ALLOCATION_ERROR`
+    let ptr = match SOURCE.with(|cell| cell.get()) {
+        Some(Source::Slice(ptr, len)) if len >= blocks => ptr as *mut u8,
+        Some(Source::Allocator(_)) => {
+            let allocated = catch_panic(|| {
+                with_allocator(|allocator| {
+                    let block = allocator.allocate(blocks)?;
+                    if block.len() < blocks {
+                        allocator.free(block);
+                        return None;
+                    }
+                    Some(block)
+                })
+            })
+            .flatten()
+            .flatten();
+
+            match allocated {
+                Some(mut block) => {
+                    let ptr = block.as_mut_ptr() as *mut u8;
+                    ALLOCATED.with(|cell| *cell.borrow_mut() = Some(block));
+                    ptr
+                }
+                None => std::ptr::null_mut(),
+            }
+        }
+        _ => std::ptr::null_mut(),
+    };
+    if !ptr.is_null() {
+        IN_USE.with(|cell| cell.set(Some((ptr, blocks * BLOCK_SIZE))));
+    }
+    unsafe { *memory = ptr };
+    0
+}
+
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+unsafe extern "C" fn free_callback(_memory: *mut u8, _bytes: usize) {
+    // Caller-supplied memory is wiped by the C implementation and stays with the caller
+    IN_USE.with(|cell| cell.set(None));
+    catch_panic(free_allocated);
+}
+
+/// Hashes with the RustCrypto implementation
+///
+/// The lanes are computed one after the other. The flags have no effect, the RustCrypto
+/// implementation never writes to the password or the secret.
+#[cfg(any(feature = "pure-rust", all(target_arch = "wasm32", feature = "wasm")))]
+pub(crate) fn hash_rust(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    let operation = Operation::hash(argon2);
+    let guard = OutputGuard::new(out);
+    let result = hash_rust_guarded(argon2, password, salt, secret, guard.out);
+    operation.finish_hash(&result);
+    guard.finish(result)
+}
+
+#[cfg(any(feature = "pure-rust", all(target_arch = "wasm32", feature = "wasm")))]
+fn hash_rust_guarded(
+    argon2: &Argon2,
+    password: &[u8],
+    salt: &[u8],
+    secret: &[u8],
+    out: &mut [u8],
+) -> Result<(), Error> {
+    use super::error::Argon2Error;
+
+    check_inputs(argon2, password, salt, secret, out)?;
+    let prepared = prepare_password(argon2, password)?;
+    let password = prepared.as_deref().unwrap_or(password);
+    argon2.working_memory_len()?;
+    let _permit = limit::acquire()?;
+
+    let map_error = |error: argon2::Error| {
+        let error = match error {
+            argon2::Error::AdTooLong => Argon2Error::AdTooLong,
+            argon2::Error::AlgorithmInvalid => Argon2Error::IncorrectType,
+            argon2::Error::MemoryTooLittle => Argon2Error::MemoryTooLittle,
+            argon2::Error::MemoryTooMuch => Argon2Error::MemoryTooMuch,
+            argon2::Error::OutputTooShort => Argon2Error::OutputTooShort,
+            argon2::Error::OutputTooLong => Argon2Error::OutputTooLong,
+            argon2::Error::PwdTooLong => Argon2Error::PasswordTooLong,
+            argon2::Error::SaltTooShort => Argon2Error::SaltTooShort,
+            argon2::Error::SaltTooLong => Argon2Error::SaltTooLong,
+            argon2::Error::SecretTooLong => Argon2Error::SecretTooLong,
+            argon2::Error::ThreadsTooFew => Argon2Error::LanesTooFew,
+            argon2::Error::ThreadsTooMany => Argon2Error::LanesTooMany,
+            argon2::Error::TimeTooSmall => Argon2Error::TimeTooSmall,
+            _ => Argon2Error::IncorrectParameter,
+        };
+        Error::Argon2(error)
+    };
+
+    let params = argon2::ParamsBuilder::new()
+        .m_cost(argon2.m_cost)
+        .t_cost(argon2.t_cost)
+        .p_cost(argon2.p_cost)
+        .output_len(out.len())
+        .data(argon2::AssociatedData::new(&argon2.ad).map_err(map_error)?)
+        .build()
+        .map_err(map_error)?;
+    let blocks = params.block_count();
+
+    let context = argon2::Argon2::new_with_secret(
+        secret,
+        argon2.algorithm.into(),
+        argon2.version.into(),
+        params,
+    )
+    .map_err(map_error)?;
+
+    // Allocated here instead of by `hash_password_into`, which aborts when it fails
+    let mut memory = Vec::new();
+    memory
+        .try_reserve_exact(blocks)
+        .map_err(|_| Error::OutOfMemory)?;
+    memory.resize(blocks, argon2::Block::default());
+    let mut memory = RustMemory(memory);
+
+    context
+        .hash_password_into_with_memory(password, salt, out, &mut memory.0)
+        .map_err(map_error)
+}
+
+/// The working memory of the RustCrypto implementation, wiped when dropped as the C
+/// implementation wipes its own
+#[cfg(any(feature = "pure-rust", all(target_arch = "wasm32", feature = "wasm")))]
+struct RustMemory(Vec<argon2::Block>);
+
+#[cfg(any(feature = "pure-rust", all(target_arch = "wasm32", feature = "wasm")))]
+impl Drop for RustMemory {
+    fn drop(&mut self) {
+        let bytes = std::mem::size_of_val(self.0.as_slice());
+        // A block is plain 64-bit words, any byte pattern is valid
+        let bytes =
+            unsafe { std::slice::from_raw_parts_mut(self.0.as_mut_ptr().cast::<u8>(), bytes) };
+        super::output::wipe(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_wiped_on_error() {
+        let mut out = [0xff; 32];
+        let result =
+            Argon2::new(1024, 0, 1).hash_password_into("password", b"somesaltsomesalt", &mut out);
+        assert!(result.is_err());
+        assert_eq!(out, [0; 32]);
+    }
+
+    #[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_memory_wiped_on_panic() {
+        struct Keep(Option<Box<[Block]>>);
+
+        impl MemoryAllocator for Keep {
+            fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+                Some(Block::alloc(blocks))
+            }
+
+            fn free(&mut self, memory: Box<[Block]>) {
+                self.0 = Some(memory);
+            }
+        }
+
+        // A panic after the first pass leaves the memory filled
+        let progress = |_, _| panic!("progress");
+        let control = Control {
+            progress: Some(&progress),
+            ..Default::default()
+        };
+        let mut allocator = Keep(None);
+        let mut out = [0xff; 32];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            hash_in(
+                &Argon2::new(1024, 2, 2),
+                b"password",
+                b"somesaltsomesalt",
+                &[],
+                &mut out,
+                Some(WorkingMemory::Allocator(&mut allocator)),
+                Some(&control),
+            )
+        }));
+
+        assert!(result.is_err());
+        assert_eq!(out, [0; 32]);
+        let memory = allocator.0.unwrap();
+        assert!(memory.iter().all(|block| *block == Block::ZERO));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    Error, allocator::Block, cancel::CancellationToken, error::Argon2Error, prealloc::LanePool,
+};
+use argon2_sys::{ARGON2_OK, argon2_context, argon2_type};
+use std::cell::Cell;
+use std::mem::offset_of;
+use std::time::{Duration, Instant};
+
+/// Same layout as `argon2_instance_t` of the C implementation
+#[repr(C)]
+struct Instance {
+    memory: *mut Block,
+    version: u32,
+    passes: u32,
+    memory_blocks: u32,
+    segment_length: u32,
+    lane_length: u32,
+    lanes: u32,
+    threads: u32,
+    ty: argon2_type,
+    print_internals: std::ffi::c_int,
+    context_ptr: *mut argon2_context,
+}
+
+/// Same layout as `argon2_position_t` of the C implementation
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Position {
+    pass: u32,
+    lane: u32,
+    slice: u8,
+    index: u32,
+}
+
+// The layouts of core.h, the `argon2-sys` dependency is pinned to the version they were taken from
+const _: () = {
+    const POINTER: usize = size_of::<*mut u8>();
+    assert!(offset_of!(Instance, memory) == 0);
+    assert!(offset_of!(Instance, version) == POINTER);
+    assert!(offset_of!(Instance, passes) == POINTER + 4);
+    assert!(offset_of!(Instance, memory_blocks) == POINTER + 8);
+    assert!(offset_of!(Instance, segment_length) == POINTER + 12);
+    assert!(offset_of!(Instance, lane_length) == POINTER + 16);
+    assert!(offset_of!(Instance, lanes) == POINTER + 20);
+    assert!(offset_of!(Instance, threads) == POINTER + 24);
+    assert!(offset_of!(Instance, ty) == POINTER + 28);
+    assert!(offset_of!(Instance, print_internals) == POINTER + 32);
+    assert!(offset_of!(Instance, context_ptr) == (POINTER + 36).next_multiple_of(POINTER));
+    assert!(size_of::<Instance>() == (POINTER + 36).next_multiple_of(POINTER) + POINTER);
+
+    assert!(offset_of!(Position, pass) == 0);
+    assert!(offset_of!(Position, lane) == 4);
+    assert!(offset_of!(Position, slice) == 8);
+    assert!(offset_of!(Position, index) == 12);
+    assert!(size_of::<Position>() == 16);
+};
+
+// Internal functions of the C implementation, `argon2_ctx` is made of them
+unsafe extern "C" {
+    fn validate_inputs(context: *const argon2_context) -> std::ffi::c_int;
+    fn initialize(instance: *mut Instance, context: *mut argon2_context) -> std::ffi::c_int;
+    fn fill_segment(instance: *const Instance, position: Position);
+    fn finalize(context: *const argon2_context, instance: *mut Instance);
+    fn free_memory(context: *const argon2_context, memory: *mut u8, num: usize, size: usize);
+}
+
+const SYNC_POINTS: u32 = 4;
+
+/// How the segments are filled when the hash is driven from Rust instead of `argon2_ctx`
+#[derive(Default)]
+pub(crate) struct Control<'a> {
+    /// Fills the lanes on these threads instead of spawning them for every slice
+    pub(crate) pool: Option<&'a LanePool>,
+    /// Checked before every slice
+    pub(crate) cancel: Option<&'a CancellationToken>,
+    /// Checked before every slice
+    pub(crate) deadline: Option<Instant>,
+    /// Called with the number of completed passes and the total after every pass
+    pub(crate) progress: Option<&'a dyn Fn(u32, u32)>,
+    /// Called on every lane thread before it fills its segments, the lanes are then never filled
+    /// on the calling thread. Not called on the threads of `pool`.
+    pub(crate) thread_init: Option<&'a (dyn Fn() + Sync)>,
+    /// Receives how long each phase of the hash took
+    pub(crate) timings: Option<&'a Timings>,
+}
+
+/// How long the phases of a hash took
+#[derive(Default)]
+pub(crate) struct Timings {
+    /// Allocating the working memory and filling the first blocks of every lane
+    pub(crate) alloc: Cell<Duration>,
+    /// Filling the segments of every pass
+    pub(crate) fill: Cell<Duration>,
+    /// Hashing the last blocks into the output and wiping the memory
+    pub(crate) finalize: Cell<Duration>,
+}
+
+/// Wipes and frees the memory of an instance that is dropped before it is finalized, when the hash
+/// is cancelled or a callback of [`Control`] panics
+struct Memory<'a> {
+    context: &'a argon2_context,
+    instance: &'a Instance,
+}
+
+impl Drop for Memory<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            free_memory(
+                self.context,
+                self.instance.memory.cast(),
+                self.instance.memory_blocks as usize,
+                size_of::<Block>(),
+            )
+        };
+    }
+}
+
+impl Control<'_> {
+    fn check(&self) -> Result<(), Error> {
+        if self.cancel.is_some_and(CancellationToken::is_cancelled) {
+            return Err(Error::Cancelled);
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(Error::DeadlineExceeded);
+        }
+        Ok(())
+    }
+}
+
+/// The instance is only read by the threads filling the segments of a slice, each writes to the
+/// blocks of its own segment
+#[derive(Clone, Copy)]
+struct Shared(*const Instance);
+
+unsafe impl Send for Shared {}
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    // A method captures the whole wrapper in closures, not just the pointer
+    fn instance(self) -> *const Instance {
+        self.0
+    }
+}
+
+/// Does what `argon2_ctx` does, filling the segments one slice at a time as `control` says
+///
+/// ## Safety
+///
+/// `context` must be valid as for `argon2_ctx`
+pub(crate) unsafe fn hash(
+    context: &mut argon2_context,
+    ty: argon2_type,
+    control: &Control,
+) -> Result<(), Error> {
+    let code = unsafe { validate_inputs(context) };
+    if code != ARGON2_OK {
+        return Err(Error::Argon2(Argon2Error::from_code(code)));
+    }
+    control.check()?;
+
+    // Same rounding as `argon2_ctx`
+    let lanes = context.lanes;
+    let segment_length = context.m_cost.max(2 * SYNC_POINTS * lanes) / (lanes * SYNC_POINTS);
+    let threads = match control.pool {
+        Some(pool) => pool.threads() as u32,
+        None => context.threads,
+    };
+    let mut instance = Instance {
+        memory: std::ptr::null_mut(),
+        version: context.version,
+        passes: context.t_cost,
+        memory_blocks: segment_length * lanes * SYNC_POINTS,
+        segment_length,
+        lane_length: segment_length * SYNC_POINTS,
+        lanes,
+        threads: threads.clamp(1, lanes),
+        ty,
+        print_internals: 0,
+        context_ptr: std::ptr::null_mut(),
+    };
+
+    let started = Instant::now();
+    let code = unsafe { initialize(&mut instance, context) };
+    if code != ARGON2_OK {
+        return Err(Error::Argon2(Argon2Error::from_code(code)));
+    }
+    let filling = Instant::now();
+
+    let memory = Memory {
+        context,
+        instance: &instance,
+    };
+    for pass in 0..instance.passes {
+        for slice in 0..SYNC_POINTS {
+            control.check()?;
+            fill_slice(memory.instance, pass, slice as u8, control);
+        }
+
+        if let Some(progress) = control.progress {
+            progress(pass + 1, instance.passes);
+        }
+    }
+    // `finalize` wipes and frees the memory itself
+    std::mem::forget(memory);
+
+    let finalizing = Instant::now();
+    unsafe { finalize(context, &mut instance) };
+
+    if let Some(timings) = control.timings {
+        timings.alloc.set(filling - started);
+        timings.fill.set(finalizing - filling);
+        timings.finalize.set(finalizing.elapsed());
+    }
+    Ok(())
+}
+
+fn fill_slice(instance: &Instance, pass: u32, slice: u8, control: &Control) {
+    let shared = Shared(instance);
+    let fill = move |lane| {
+        let position = Position {
+            pass,
+            lane,
+            slice,
+            index: 0,
+        };
+        unsafe { fill_segment(shared.instance(), position) };
+    };
+
+    let (lanes, threads) = (instance.lanes, instance.threads);
+    match (control.pool, control.thread_init) {
+        (Some(pool), _) => pool.run(lanes, &fill),
+        (None, None) if threads == 1 => (0..lanes).for_each(fill),
+        (None, init) => std::thread::scope(|scope| {
+            for thread in 0..threads {
+                scope.spawn(move || {
+                    if let Some(init) = init {
+                        init();
+                    }
+                    (thread..lanes).step_by(threads as usize).for_each(fill)
+                });
+            }
+        }),
+    }
+}
@@ -0,0 +1,174 @@
+use super::{Argon2, Error};
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+/// The pools of the batches, one per concurrency cap, built on first use and kept for the
+/// lifetime of the process
+static POOLS: Mutex<Vec<(usize, Arc<rayon::ThreadPool>)>> = Mutex::new(Vec::new());
+
+/// The pool running at most `max_concurrent` hashes at the same time
+fn pool(max_concurrent: usize) -> Result<Arc<rayon::ThreadPool>, Error> {
+    let threads = max_concurrent.max(1);
+    let mut pools = POOLS.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some((_, pool)) = pools.iter().find(|(cap, _)| *cap == threads) {
+        return Ok(Arc::clone(pool));
+    }
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| Error::ThreadPool(e.to_string()))?;
+    let pool = Arc::new(pool);
+    pools.push((threads, Arc::clone(&pool)));
+    Ok(pool)
+}
+
+impl Argon2 {
+    /// Hashes a batch of passwords in parallel
+    ///
+    /// At most `max_concurrent` hashes are computed at the same time, so the peak memory usage is
+    /// bounded to roughly `max_concurrent * m_cost` kibibytes. The threads are kept for the next
+    /// batch with the same `max_concurrent`.
+    ///
+    /// ## Arguments
+    ///
+    /// - `items` - The passwords to hash along with their salts
+    /// - `max_concurrent` - The maximum number of hashes computed at the same time
+    ///
+    /// ## Returns
+    ///
+    /// The result of each hash in the same order as `items`
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 1);
+    /// let items = vec![("alice", b"somesaltsomesalt"), ("bob", b"othersaltothersa")];
+    /// let hashes = argon2.hash_many(&items, 2).unwrap();
+    /// assert_eq!(hashes.len(), 2);
+    /// ```
+    pub fn hash_many<P, S>(
+        &self,
+        items: &[(P, S)],
+        max_concurrent: usize,
+    ) -> Result<Vec<Result<Vec<u8>, Error>>, Error>
+    where
+        P: AsRef<[u8]> + Sync,
+        S: AsRef<[u8]> + Sync,
+    {
+        let pool = pool(max_concurrent)?;
+
+        Ok(pool.install(|| {
+            items
+                .par_iter()
+                .map(|(password, salt)| self.hash_password(password, salt.as_ref()))
+                .collect()
+        }))
+    }
+
+    /// Verifies a stream of passwords against PHC strings in parallel
+    ///
+    /// Items are pulled from the iterator as workers become free, so the input does not have to be
+    /// collected up front. At most `max_concurrent` verifications run at the same time.
+    ///
+    /// ## Arguments
+    ///
+    /// - `items` - The passwords along with the hashes to verify them against
+    /// - `max_concurrent` - The maximum number of verifications running at the same time
+    ///
+    /// ## Returns
+    ///
+    /// The result of each verification in the same order as `items`
+    pub fn verify_many<I, P, E>(
+        items: I,
+        max_concurrent: usize,
+    ) -> Result<Vec<Result<bool, Error>>, Error>
+    where
+        I: IntoIterator<Item = (P, E)>,
+        I::IntoIter: Send,
+        P: AsRef<[u8]> + Send,
+        E: AsRef<str> + Send,
+    {
+        let pool = pool(max_concurrent)?;
+
+        let items = items.into_iter().enumerate();
+
+        let mut results: Vec<(usize, Result<bool, Error>)> = pool.install(|| {
+            items
+                .par_bridge()
+                .map(|(i, (password, encoded))| {
+                    (i, Self::verify_encoded(password, encoded.as_ref()))
+                })
+                .collect()
+        });
+
+        results.sort_unstable_by_key(|(i, _)| *i);
+        Ok(results.into_iter().map(|(_, result)| result).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_many() {
+        let argon2 = Argon2::new(1024, 1, 1);
+        let salt = b"somesaltsomesalt";
+        let items: Vec<(String, &[u8])> = (0..8)
+            .map(|i| (format!("password{}", i), &salt[..]))
+            .chain(std::iter::once((String::from("password"), &salt[..4])))
+            .collect();
+
+        let hashes = argon2.hash_many(&items, 3).unwrap();
+        assert_eq!(hashes.len(), items.len());
+
+        for ((password, salt), hash) in items.iter().zip(&hashes).take(8) {
+            let expected = argon2.hash_password(password, salt).unwrap();
+            assert_eq!(hash.as_ref().unwrap(), &expected);
+        }
+
+        assert!(hashes[8].is_err());
+
+        // Batches with the same cap share a pool
+        assert!(Arc::ptr_eq(&pool(3).unwrap(), &pool(3).unwrap()));
+        assert!(!Arc::ptr_eq(&pool(3).unwrap(), &pool(2).unwrap()));
+    }
+
+    #[test]
+    fn test_verify_many() {
+        let argon2 = Argon2::new(1024, 1, 1);
+        let salt = b"somesaltsomesalt";
+        let encoded: Vec<String> = (0..6)
+            .map(|i| {
+                argon2
+                    .hash_password_encoded(format!("password{}", i), salt)
+                    .unwrap()
+            })
+            .collect();
+
+        let items = encoded
+            .iter()
+            .enumerate()
+            .map(|(i, encoded)| (format!("password{}", i % 3), encoded.as_str()))
+            .chain(std::iter::once((String::new(), "invalid")));
+
+        let results = Argon2::verify_many(items, 2).unwrap();
+        let results: Vec<Option<bool>> = results.into_iter().map(|r| r.ok()).collect();
+        assert_eq!(
+            results,
+            vec![
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false),
+                None
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use super::{Argon2, Error, backend::Control, backend::Timings};
+use std::time::Duration;
+
+/// The average time each phase of a hash took over a number of runs
+///
+/// Compare hosts or presets by running the same parameters on each of them, the phases show
+/// whether a slow hash is spent in the memory allocator or in the memory bandwidth.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchReport {
+    /// The number of hashes the times are averaged over
+    pub runs: u32,
+    /// Allocating the working memory and filling the first blocks of every lane
+    pub alloc_time: Duration,
+    /// Filling the working memory over every pass
+    pub fill_time: Duration,
+    /// Hashing the last blocks into the output and wiping the working memory
+    pub finalize_time: Duration,
+    /// The working memory filled per second over all passes, in MiB/s
+    pub throughput_mib_s: f64,
+}
+
+impl BenchReport {
+    /// The average time of a whole hash
+    pub fn total_time(&self) -> Duration {
+        self.alloc_time + self.fill_time + self.finalize_time
+    }
+}
+
+impl Argon2 {
+    /// Hashes a throwaway password `runs` times with these parameters and reports how long the
+    /// phases of a hash took on average
+    ///
+    /// ## Arguments
+    ///
+    /// - `runs` - The number of hashes to average over, at least 1
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::InvalidConfig`] if `runs` is 0, the error of the hash if the parameters are
+    /// invalid
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let presets = [("fast", Argon2::new(4096, 1, 1)), ("wide", Argon2::new(8192, 2, 4))];
+    /// for (name, argon2) in presets {
+    ///     let report = argon2.bench(3).unwrap();
+    ///     println!("{name}: {:?}, {:.0} MiB/s", report.total_time(), report.throughput_mib_s);
+    /// }
+    /// ```
+    pub fn bench(&self, runs: u32) -> Result<BenchReport, Error> {
+        if runs == 0 {
+            return Err(Error::InvalidConfig("runs must be at least 1".to_string()));
+        }
+
+        let salt = [0u8; 16];
+        let timings = Timings::default();
+        let control = Control {
+            timings: Some(&timings),
+            ..Default::default()
+        };
+
+        let (mut alloc_time, mut fill_time, mut finalize_time) =
+            (Duration::ZERO, Duration::ZERO, Duration::ZERO);
+        for _ in 0..runs {
+            self.hash_controlled(b"password", &salt, &control)?;
+            alloc_time += timings.alloc.get();
+            fill_time += timings.fill.get();
+            finalize_time += timings.finalize.get();
+        }
+
+        let filled = self.working_memory_bytes() as f64 * self.t_cost as f64 * runs as f64;
+        let throughput_mib_s =
+            filled / (1024.0 * 1024.0) / fill_time.as_secs_f64().max(f64::MIN_POSITIVE);
+
+        Ok(BenchReport {
+            runs,
+            alloc_time: alloc_time / runs,
+            fill_time: fill_time / runs,
+            finalize_time: finalize_time / runs,
+            throughput_mib_s,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench() {
+        let argon2 = Argon2::new(4096, 2, 2);
+        let report = argon2.bench(3).unwrap();
+
+        assert_eq!(report.runs, 3);
+        assert!(report.fill_time > Duration::ZERO);
+        assert!(report.throughput_mib_s > 0.0);
+        assert_eq!(
+            report.total_time(),
+            report.alloc_time + report.fill_time + report.finalize_time
+        );
+
+        assert!(matches!(argon2.bench(0), Err(Error::InvalidConfig(_))));
+        assert!(Argon2::new(1024, 0, 1).bench(1).is_err());
+    }
+}
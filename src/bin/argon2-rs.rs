@@ -0,0 +1,236 @@
+use argon2_rs::{Algorithm, Argon2, salt::Salt};
+use std::io::Read;
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: argon2-rs <command> [options]
+
+The password is read from stdin, a single trailing newline is removed.
+
+Commands:
+  hash        Hash the password and print the PHC string
+  verify PHC  Verify the password against a PHC string, exits with 1 on mismatch
+  bench       Time the hashing with the given parameters
+  calibrate   Find the parameters that take about the target time
+
+Options:
+  -m KIB      Memory cost in kibibytes, the largest one for calibrate (default 65536)
+  -t N        Iterations (default 3)
+  -p N        Parallelism (default 1)
+  -l N        Hash length in bytes (default 32)
+  -a NAME     argon2d, argon2i or argon2id (default argon2id)
+  -s SALT     Salt to use instead of a random one (hash only)
+  -r          Print the raw hash in hex instead of the PHC string (hash only)
+  -n N        Number of runs (bench only, default 5)
+  --target MS Target time in milliseconds (calibrate only, default 500)
+  -h, --help  Print this help";
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Hash,
+    Verify(String),
+    Bench,
+    Calibrate,
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    command: Command,
+    argon2: Argon2,
+    salt: Option<String>,
+    raw: bool,
+    runs: u32,
+    target: Duration,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut args = args.into_iter();
+
+    let command = match args.next().as_deref() {
+        Some("hash") => Command::Hash,
+        Some("verify") => Command::Verify(args.next().ok_or("verify requires a PHC string")?),
+        Some("bench") => Command::Bench,
+        Some("calibrate") => Command::Calibrate,
+        Some(other) => return Err(format!("unknown command: {other}")),
+        None => return Err("missing command".to_string()),
+    };
+
+    let mut options = Options {
+        command,
+        argon2: Argon2::new(65536, 3, 1).with_hash_length(32),
+        salt: None,
+        raw: false,
+        runs: 5,
+        target: Duration::from_millis(500),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("{arg} requires a value"));
+
+        match arg.as_str() {
+            "-m" => options.argon2.m_cost = number(&arg, &value()?)?,
+            "-t" => options.argon2.t_cost = number(&arg, &value()?)?,
+            "-p" => options.argon2.p_cost = number(&arg, &value()?)?,
+            "-l" => options.argon2.hash_length = number(&arg, &value()?)?,
+            "-a" => {
+                let name = value()?;
+                options.argon2.algorithm = name
+                    .parse::<Algorithm>()
+                    .map_err(|_| format!("unknown algorithm: {name}"))?;
+            }
+            "-s" => options.salt = Some(value()?),
+            "-r" => options.raw = true,
+            "-n" => options.runs = number(&arg, &value()?)?,
+            "--target" => options.target = Duration::from_millis(number(&arg, &value()?)?),
+            other => return Err(format!("unknown option: {other}")),
+        }
+    }
+
+    Ok(options)
+}
+
+fn number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{option} expects a number, got {value:?}"))
+}
+
+/// Reads the password from stdin without its trailing newline
+fn read_password(mut input: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut password = Vec::new();
+    input.read_to_end(&mut password)?;
+
+    if password.ends_with(b"\n") {
+        password.pop();
+        if password.ends_with(b"\r") {
+            password.pop();
+        }
+    }
+
+    Ok(password)
+}
+
+fn run(options: Options) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let argon2 = &options.argon2;
+
+    match &options.command {
+        Command::Hash => {
+            let password = read_password(std::io::stdin())?;
+            let salt = match &options.salt {
+                Some(salt) => Salt::new(salt.as_bytes())?,
+                None => Salt::generate()?,
+            };
+
+            if options.raw {
+                println!("{}", argon2.hash_password_bytes(&password, &salt)?.to_hex());
+            } else {
+                println!("{}", argon2.hash_password_encoded(&password, &salt)?);
+            }
+        }
+        Command::Verify(encoded) => {
+            let password = read_password(std::io::stdin())?;
+            if !Argon2::verify_encoded(&password, encoded)? {
+                eprintln!("Mismatch");
+                return Ok(ExitCode::from(1));
+            }
+            println!("OK");
+        }
+        Command::Bench => {
+            let report = argon2.bench(options.runs)?;
+            println!(
+                "m={},t={},p={} ({}): {:.3}s per hash (alloc {:.3}s, fill {:.3}s, finalize {:.3}s), {:.0} MiB/s over {} runs",
+                argon2.m_cost,
+                argon2.t_cost,
+                argon2.p_cost,
+                argon2_rs::simd::backend(),
+                report.total_time().as_secs_f64(),
+                report.alloc_time.as_secs_f64(),
+                report.fill_time.as_secs_f64(),
+                report.finalize_time.as_secs_f64(),
+                report.throughput_mib_s,
+                report.runs
+            );
+        }
+        Command::Calibrate => {
+            let argon2 = Argon2::calibrate(options.target, argon2.m_cost)?;
+            println!(
+                "m={},t={},p={} takes {:.3}s",
+                argon2.m_cost,
+                argon2.t_cost,
+                argon2.p_cost,
+                argon2.bench(1)?.total_time().as_secs_f64()
+            );
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(options) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Result<Options, String> {
+        parse_args(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = args("hash -m 1024 -t 2 -p 4 -l 16 -a argon2i -s somesalt -r").unwrap();
+        assert_eq!(options.command, Command::Hash);
+        assert_eq!(
+            options.argon2,
+            Argon2::new(1024, 2, 4)
+                .with_hash_length(16)
+                .with_algorithm(Algorithm::Argon2i)
+        );
+        assert_eq!(options.salt.as_deref(), Some("somesalt"));
+        assert!(options.raw);
+
+        let options = args("verify $argon2id$v=19$m=1024,t=2,p=1$c2FsdA$aGFzaA").unwrap();
+        assert!(matches!(options.command, Command::Verify(_)));
+
+        assert!(args("verify").is_err());
+        assert!(args("hash -m").is_err());
+        assert!(args("hash -m lots").is_err());
+        assert!(args("hash -a argon3").is_err());
+        assert!(args("crack").is_err());
+    }
+
+    #[test]
+    fn test_read_password() {
+        assert_eq!(read_password(&b"password\n"[..]).unwrap(), b"password");
+        assert_eq!(read_password(&b"password\r\n"[..]).unwrap(), b"password");
+        assert_eq!(
+            read_password(&b"pass word\n\n"[..]).unwrap(),
+            b"pass word\n"
+        );
+        assert_eq!(read_password(&b"password"[..]).unwrap(), b"password");
+    }
+}
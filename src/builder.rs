@@ -0,0 +1,265 @@
+use super::{
+    Algorithm, Argon2, Error, Flags, Version, config::MAX_LANES, error::Argon2Error,
+    normalize::Normalization, policy,
+};
+
+/// Builds an [`Argon2`] whose parameters are checked against the limits of RFC 9106
+///
+/// Unlike [`Argon2::new`] and the `with_*` methods, [`Argon2Builder::build`] rejects parameters
+/// the implementation would only reject when hashing. Unset parameters take the values of
+/// [`Argon2::default`].
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, Algorithm, error::{Argon2Error, Error}};
+///
+/// let argon2 = Argon2::builder()
+///     .m_cost(19 * 1024)
+///     .t_cost(2)
+///     .p_cost(1)
+///     .algorithm(Algorithm::Argon2id)
+///     .build()
+///     .unwrap();
+/// assert_eq!(argon2, Argon2::new(19 * 1024, 2, 1));
+///
+/// let too_little = Argon2::builder().m_cost(16).t_cost(2).p_cost(4).build();
+/// assert!(matches!(too_little, Err(Error::Argon2(Argon2Error::MemoryTooLittle))));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Argon2Builder {
+    argon2: Argon2,
+}
+
+impl Argon2Builder {
+    /// The memory cost in kibibytes, at least 8 times `p_cost`
+    pub fn m_cost(mut self, m_cost: u32) -> Self {
+        self.argon2.m_cost = m_cost;
+        self
+    }
+
+    /// The number of passes over the memory, at least 1
+    pub fn t_cost(mut self, t_cost: u32) -> Self {
+        self.argon2.t_cost = t_cost;
+        self
+    }
+
+    /// The number of lanes, between 1 and 2^24 - 1
+    pub fn p_cost(mut self, p_cost: u32) -> Self {
+        self.argon2.p_cost = p_cost;
+        self
+    }
+
+    /// The length of the hash in bytes, between [`MIN_HASH_LENGTH`](crate::MIN_HASH_LENGTH) and
+    /// [`MAX_HASH_LENGTH`](crate::MAX_HASH_LENGTH)
+    pub fn hash_length(mut self, hash_length: u64) -> Self {
+        self.argon2.hash_length = hash_length;
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.argon2.algorithm = algorithm;
+        self
+    }
+
+    pub fn version(mut self, version: Version) -> Self {
+        self.argon2.version = version;
+        self
+    }
+
+    /// Associated data the hash is bound to
+    pub fn ad(mut self, ad: &[u8]) -> Self {
+        self.argon2.ad = ad.to_vec();
+        self
+    }
+
+    pub fn flags(mut self, flags: Flags) -> Self {
+        self.argon2.flags = flags;
+        self
+    }
+
+    /// The number of threads to compute the lanes with, at least 1
+    pub fn threads(mut self, threads: u32) -> Self {
+        self.argon2.threads = Some(threads);
+        self
+    }
+
+    /// Pre-hashes passwords longer than `threshold` bytes, see [`Argon2::with_prehash`]
+    pub fn prehash(mut self, threshold: u32) -> Self {
+        self.argon2.prehash = Some(threshold);
+        self
+    }
+
+    /// Normalizes passwords to the given Unicode form, see [`Argon2::with_normalization`]
+    pub fn normalization(mut self, normalization: Normalization) -> Self {
+        self.argon2.normalization = Some(normalization);
+        self
+    }
+
+    /// Validates the parameters, see [`Argon2::validate`]
+    pub fn build(self) -> Result<Argon2, Error> {
+        self.argon2.validate()?;
+        Ok(self.argon2)
+    }
+}
+
+impl Argon2 {
+    /// Starts building an instance whose parameters are validated, see [`Argon2Builder`]
+    pub fn builder() -> Argon2Builder {
+        Argon2Builder {
+            argon2: Argon2::default(),
+        }
+    }
+
+    /// Create a new Argon2 instance like [`Argon2::new`], failing if the parameters are invalid
+    ///
+    /// ## Arguments
+    ///
+    /// - `m_cost` - The memory cost in kibibytes, at least 8 times `p_cost`
+    /// - `t_cost` - Iteration cost, at least 1
+    /// - `p_cost` - Parallelization, between 1 and 2^24 - 1
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, error::{Argon2Error, Error}};
+    ///
+    /// assert!(Argon2::try_new(1024, 2, 1).is_ok());
+    /// assert!(matches!(
+    ///     Argon2::try_new(1024, 0, 1),
+    ///     Err(Error::Argon2(Argon2Error::TimeTooSmall))
+    /// ));
+    /// ```
+    pub fn try_new(m_cost: u32, t_cost: u32, p_cost: u32) -> Result<Self, Error> {
+        let argon2 = Self::new(m_cost, t_cost, p_cost);
+        argon2.validate()?;
+        Ok(argon2)
+    }
+
+    /// Checks the parameters against the limits of RFC 9106
+    ///
+    /// ## Returns
+    ///
+    /// The [`Error::Argon2`] the implementation would fail the hash with,
+    /// [`Error::InvalidHashLength`] if the `hash_length` is out of range,
+    /// [`Error::MemoryTooLargeForPlatform`] if the working memory does not fit in the address
+    /// space or [`Error::PolicyViolation`] if the algorithm is not allowed, see
+    /// [`policy::set_allowed_algorithms`]
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.t_cost < 1 {
+            return Err(Argon2Error::TimeTooSmall.into());
+        }
+
+        if self.p_cost < 1 {
+            return Err(Argon2Error::LanesTooFew.into());
+        }
+
+        if self.p_cost > MAX_LANES {
+            return Err(Argon2Error::LanesTooMany.into());
+        }
+
+        if (self.m_cost as u64) < 8 * self.p_cost as u64 {
+            return Err(Argon2Error::MemoryTooLittle.into());
+        }
+
+        if self.threads == Some(0) {
+            return Err(Argon2Error::ThreadsTooFew.into());
+        }
+
+        self.hash_len()?;
+        self.working_memory_len()?;
+        policy::check_algorithm(self.algorithm)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MAX_HASH_LENGTH, MIN_HASH_LENGTH};
+
+    #[test]
+    fn test_validate() {
+        let argon2 = Argon2::new(1024, 2, 4);
+        assert!(argon2.validate().is_ok());
+
+        let invalid = [
+            (Argon2::new(1024, 0, 1), Argon2Error::TimeTooSmall),
+            (Argon2::new(1024, 2, 0), Argon2Error::LanesTooFew),
+            (
+                Argon2::new(u32::MAX, 2, MAX_LANES + 1),
+                Argon2Error::LanesTooMany,
+            ),
+            (Argon2::new(31, 2, 4), Argon2Error::MemoryTooLittle),
+            (argon2.clone().with_threads(0), Argon2Error::ThreadsTooFew),
+        ];
+        for (argon2, expected) in invalid {
+            assert!(matches!(argon2.validate(), Err(Error::Argon2(e)) if e == expected));
+            // The C implementation fails the hash with the same error
+            #[cfg(not(feature = "pure-rust"))]
+            if expected != Argon2Error::LanesTooMany {
+                assert!(matches!(
+                    argon2.hash_password("password", b"somesaltsomesalt"),
+                    Err(Error::Argon2(e)) if e == expected
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_length() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let salt = b"somesaltsomesalt";
+
+        for hash_length in [0, MIN_HASH_LENGTH - 1, MAX_HASH_LENGTH + 1, u64::MAX] {
+            let argon2 = argon2.clone().with_hash_length(hash_length);
+            assert!(matches!(
+                argon2.validate(),
+                Err(Error::InvalidHashLength(length)) if length == hash_length
+            ));
+            assert!(matches!(
+                argon2.hash_password("password", salt),
+                Err(Error::InvalidHashLength(length)) if length == hash_length
+            ));
+        }
+
+        assert!(matches!(
+            argon2.hash_password_into("password", salt, &mut [0; 3]),
+            Err(Error::InvalidHashLength(3))
+        ));
+        assert!(argon2.with_hash_length(MIN_HASH_LENGTH).validate().is_ok());
+    }
+
+    #[cfg(not(feature = "argon2id-only"))]
+    #[test]
+    fn test_builder() {
+        let built = Argon2::builder()
+            .m_cost(2048)
+            .t_cost(3)
+            .p_cost(2)
+            .hash_length(32)
+            .algorithm(Algorithm::Argon2i)
+            .version(Version::V0x10)
+            .ad(b"user1")
+            .threads(1)
+            .prehash(1024)
+            .normalization(Normalization::Nfkc)
+            .build()
+            .unwrap();
+        assert_eq!(
+            built,
+            Argon2::new(2048, 3, 2)
+                .with_hash_length(32)
+                .with_algorithm(Algorithm::Argon2i)
+                .with_version(Version::V0x10)
+                .with_ad(b"user1")
+                .with_threads(1)
+                .with_prehash(1024)
+                .with_normalization(Normalization::Nfkc)
+        );
+
+        assert_eq!(Argon2::builder().build().unwrap(), Argon2::default());
+        assert!(Argon2::try_new(1024, 2, 1).is_ok());
+        assert!(Argon2::try_new(1024, 2, 1024).is_err());
+    }
+}
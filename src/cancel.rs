@@ -0,0 +1,234 @@
+use super::{Argon2, Error, backend, backend::Control, constant_time_eq, output_buffer, phc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Aborts a hash in progress from another thread or task
+///
+/// The hash checks the token before each of the four slices of every pass, so it stops within a
+/// quarter of a pass after [`CancellationToken::cancel`] is called. The partially filled working
+/// memory is wiped before the hash returns [`Error::Cancelled`].
+///
+/// Clones share the same state, cancelling one cancels all of them.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, cancel::CancellationToken, error::Error};
+///
+/// let token = CancellationToken::new();
+/// let argon2 = Argon2::new(1024, 2, 1);
+///
+/// token.cancel();
+/// let result = argon2.hash_password_cancellable("password", b"somesaltsomesalt", &token);
+/// assert!(matches!(result, Err(Error::Cancelled)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every hash checking this token, now or in the future
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Argon2 {
+    /// Hashes the given password unless the token is cancelled before the hash completes
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `token` - Aborts the hash with [`Error::Cancelled`] when cancelled
+    pub fn hash_password_cancellable(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        token: &CancellationToken,
+    ) -> Result<Vec<u8>, Error> {
+        let control = Control {
+            cancel: Some(token),
+            ..Default::default()
+        };
+        self.hash_controlled(password.as_ref(), salt, &control)
+    }
+
+    /// Verifies the given password against a PHC string unless the token is cancelled before the
+    /// verification completes, see [`Argon2::verify_encoded`]
+    pub fn verify_encoded_cancellable(
+        password: impl AsRef<[u8]>,
+        encoded: &str,
+        token: &CancellationToken,
+    ) -> Result<bool, Error> {
+        let control = Control {
+            cancel: Some(token),
+            ..Default::default()
+        };
+        Self::verify_controlled(password.as_ref(), encoded, &control)
+    }
+
+    /// Hashes the given password, aborting if it takes longer than `budget`
+    ///
+    /// The elapsed time is checked before each of the four slices of every pass, so the hash may
+    /// overrun the budget by up to a quarter of a pass. Use it to bound the worst-case latency
+    /// when the parameters or the hardware drift from what they were tuned for.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `budget` - Aborts the hash with [`Error::DeadlineExceeded`] once exceeded
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, error::Error};
+    /// use std::time::Duration;
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 1);
+    /// let salt = b"somesaltsomesalt";
+    ///
+    /// let hash = argon2.hash_password_with_deadline("password", salt, Duration::from_secs(10));
+    /// assert!(hash.is_ok());
+    ///
+    /// let hash = argon2.hash_password_with_deadline("password", salt, Duration::ZERO);
+    /// assert!(matches!(hash, Err(Error::DeadlineExceeded)));
+    /// ```
+    pub fn hash_password_with_deadline(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        budget: Duration,
+    ) -> Result<Vec<u8>, Error> {
+        let control = Control {
+            deadline: Some(Instant::now() + budget),
+            ..Default::default()
+        };
+        self.hash_controlled(password.as_ref(), salt, &control)
+    }
+
+    /// Verifies the given password against a PHC string, aborting if it takes longer than
+    /// `budget`, see [`Argon2::hash_password_with_deadline`]
+    pub fn verify_encoded_with_deadline(
+        password: impl AsRef<[u8]>,
+        encoded: &str,
+        budget: Duration,
+    ) -> Result<bool, Error> {
+        let control = Control {
+            deadline: Some(Instant::now() + budget),
+            ..Default::default()
+        };
+        Self::verify_controlled(password.as_ref(), encoded, &control)
+    }
+
+    pub(crate) fn hash_controlled(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        control: &Control,
+    ) -> Result<Vec<u8>, Error> {
+        let mut hash = output_buffer(self.hash_len()?)?;
+        backend::hash_with_control(self, password, salt, &[], &mut hash, control)?;
+        Ok(hash)
+    }
+
+    pub(crate) fn verify_controlled(
+        password: &[u8],
+        encoded: &str,
+        control: &Control,
+    ) -> Result<bool, Error> {
+        let decoded = phc::decode(encoded)?;
+
+        if decoded.keyid.is_some() {
+            return Err(Error::PepperRequired);
+        }
+
+        let mut hash = output_buffer(decoded.hash.len())?;
+        backend::hash_with_control(
+            &decoded.argon2,
+            password,
+            &decoded.salt,
+            &[],
+            &mut hash,
+            control,
+        )?;
+
+        Ok(constant_time_eq(&hash, &decoded.hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellable_matches_uncancelled() {
+        let token = CancellationToken::new();
+        let salt = b"somesaltsomesalt";
+
+        for argon2 in [Argon2::new(1024, 2, 1), Argon2::new(2048, 1, 4)] {
+            let hash = argon2
+                .hash_password_cancellable("password", salt, &token)
+                .unwrap();
+            assert_eq!(hash, argon2.hash_password("password", salt).unwrap());
+
+            let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+            assert!(Argon2::verify_encoded_cancellable("password", &encoded, &token).unwrap());
+            assert!(!Argon2::verify_encoded_cancellable("wrong", &encoded, &token).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_cancel_in_progress() {
+        let token = CancellationToken::new();
+        let argon2 = Argon2::new(64 * 1024, 1000, 2);
+
+        let cancel = token.clone();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            cancel.cancel();
+        });
+
+        let result = argon2.hash_password_cancellable("password", b"somesaltsomesalt", &token);
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(token.is_cancelled());
+        canceller.join().unwrap();
+    }
+
+    #[test]
+    fn test_deadline() {
+        let argon2 = Argon2::new(1024, 2, 2);
+        let salt = b"somesaltsomesalt";
+        let budget = Duration::from_secs(60);
+
+        let hash = argon2
+            .hash_password_with_deadline("password", salt, budget)
+            .unwrap();
+        assert_eq!(hash, argon2.hash_password("password", salt).unwrap());
+
+        let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+        assert!(Argon2::verify_encoded_with_deadline("password", &encoded, budget).unwrap());
+
+        let slow = Argon2::new(64 * 1024, 1000, 1);
+        let started = Instant::now();
+        let result = slow.hash_password_with_deadline("password", salt, Duration::from_millis(50));
+        assert!(matches!(result, Err(Error::DeadlineExceeded)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        assert!(matches!(
+            Argon2::verify_encoded_with_deadline("password", &encoded, Duration::ZERO),
+            Err(Error::DeadlineExceeded)
+        ));
+    }
+}
@@ -0,0 +1,351 @@
+use super::{Algorithm, Argon2, Error, error::Argon2Error};
+use std::ffi::{CStr, c_char, c_int};
+
+/// The call succeeded
+pub const ARGON2RS_OK: c_int = 0;
+
+/// The hash is keyed with a pepper and cannot be verified through the C API
+pub const ARGON2RS_PEPPER_REQUIRED: c_int = -100;
+
+/// Any other error of this crate
+pub const ARGON2RS_ERROR: c_int = -101;
+
+/// Maps an error to the code returned by the C API
+///
+/// Errors of the C implementation keep the codes of libargon2 so existing error handling keeps
+/// working, malformed PHC strings are reported as `ARGON2_DECODING_FAIL`.
+fn error_code(error: &Error) -> c_int {
+    match error {
+        Error::PepperRequired => ARGON2RS_PEPPER_REQUIRED,
+        error => error.code().unwrap_or(ARGON2RS_ERROR),
+    }
+}
+
+fn algorithm(type_: u32) -> Option<Algorithm> {
+    match type_ {
+        0 => Some(Algorithm::Argon2d),
+        1 => Some(Algorithm::Argon2i),
+        2 => Some(Algorithm::Argon2id),
+        _ => None,
+    }
+}
+
+/// Builds a byte slice from a C pointer and length, a null pointer is only accepted for `len == 0`
+///
+/// # Safety
+///
+/// If `ptr` is not null it must be valid for reads of `len` bytes.
+unsafe fn slice<'a>(ptr: *const u8, len: usize, mismatch: Argon2Error) -> Result<&'a [u8], c_int> {
+    if ptr.is_null() {
+        return if len == 0 {
+            Ok(&[])
+        } else {
+            Err(mismatch.code())
+        };
+    }
+
+    Ok(unsafe { std::slice::from_raw_parts(ptr, len) })
+}
+
+/// Hashes a password into a raw hash of `hashlen` bytes
+///
+/// `type_` follows `argon2_type` of libargon2: `0` = Argon2d, `1` = Argon2i, `2` = Argon2id.
+/// The version is always `0x13`.
+///
+/// Returns [`ARGON2RS_OK`] or a negative error code.
+///
+/// # Safety
+///
+/// `pwd` and `salt` must be valid for reads of `pwdlen` and `saltlen` bytes and `hash` must be
+/// valid for writes of `hashlen` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn argon2rs_hash(
+    t_cost: u32,
+    m_cost: u32,
+    parallelism: u32,
+    pwd: *const u8,
+    pwdlen: usize,
+    salt: *const u8,
+    saltlen: usize,
+    hash: *mut u8,
+    hashlen: usize,
+    type_: u32,
+) -> c_int {
+    let Some(algorithm) = algorithm(type_) else {
+        return Argon2Error::IncorrectType.code();
+    };
+
+    if hash.is_null() {
+        return Argon2Error::OutputPtrNull.code();
+    }
+
+    let (pwd, salt) = unsafe {
+        match (
+            slice(pwd, pwdlen, Argon2Error::PwdPtrMismatch),
+            slice(salt, saltlen, Argon2Error::SaltPtrMismatch),
+        ) {
+            (Ok(pwd), Ok(salt)) => (pwd, salt),
+            (Err(code), _) | (_, Err(code)) => return code,
+        }
+    };
+
+    let out = unsafe { std::slice::from_raw_parts_mut(hash, hashlen) };
+    let argon2 = Argon2::new(m_cost, t_cost, parallelism).with_algorithm(algorithm);
+
+    match argon2.hash_password_into(pwd, salt, out) {
+        Ok(()) => ARGON2RS_OK,
+        Err(e) => error_code(&e),
+    }
+}
+
+/// Hashes a password and writes the NUL-terminated PHC string into `encoded`
+///
+/// Returns [`ARGON2RS_OK`] or a negative error code, `ARGON2_ENCODING_FAIL` if `encodedlen` is too
+/// small to hold the string and its terminator.
+///
+/// # Safety
+///
+/// `pwd` and `salt` must be valid for reads of `pwdlen` and `saltlen` bytes and `encoded` must be
+/// valid for writes of `encodedlen` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn argon2rs_hash_encoded(
+    t_cost: u32,
+    m_cost: u32,
+    parallelism: u32,
+    pwd: *const u8,
+    pwdlen: usize,
+    salt: *const u8,
+    saltlen: usize,
+    hashlen: usize,
+    encoded: *mut c_char,
+    encodedlen: usize,
+    type_: u32,
+) -> c_int {
+    let Some(algorithm) = algorithm(type_) else {
+        return Argon2Error::IncorrectType.code();
+    };
+
+    if encoded.is_null() {
+        return Argon2Error::OutputPtrNull.code();
+    }
+
+    let (pwd, salt) = unsafe {
+        match (
+            slice(pwd, pwdlen, Argon2Error::PwdPtrMismatch),
+            slice(salt, saltlen, Argon2Error::SaltPtrMismatch),
+        ) {
+            (Ok(pwd), Ok(salt)) => (pwd, salt),
+            (Err(code), _) | (_, Err(code)) => return code,
+        }
+    };
+
+    let argon2 = Argon2::new(m_cost, t_cost, parallelism)
+        .with_algorithm(algorithm)
+        .with_hash_length(hashlen as u64);
+
+    let string = match argon2.hash_password_encoded(pwd, salt) {
+        Ok(string) => string,
+        Err(e) => return error_code(&e),
+    };
+
+    unsafe { write_str(&string, encoded, encodedlen) }
+}
+
+/// Copies `string` and a NUL terminator into `out`
+///
+/// # Safety
+///
+/// `out` must be valid for writes of `outlen` bytes.
+unsafe fn write_str(string: &str, out: *mut c_char, outlen: usize) -> c_int {
+    if string.len() >= outlen {
+        return Argon2Error::EncodingFail.code();
+    }
+
+    unsafe {
+        std::ptr::copy_nonoverlapping(string.as_ptr(), out.cast::<u8>(), string.len());
+        *out.add(string.len()) = 0;
+    }
+
+    ARGON2RS_OK
+}
+
+/// crypt(3)-compatible entry point, see [`crate::crypt::crypt`]
+///
+/// Writes the NUL-terminated hash into `output`. Returns [`ARGON2RS_OK`] or a negative error code,
+/// `ARGON2_ENCODING_FAIL` if `outputlen` is too small to hold the hash and its terminator.
+///
+/// # Safety
+///
+/// `phrase` and `setting` must point to NUL-terminated strings and `output` must be valid for
+/// writes of `outputlen` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn argon2rs_crypt(
+    phrase: *const c_char,
+    setting: *const c_char,
+    output: *mut c_char,
+    outputlen: usize,
+) -> c_int {
+    if phrase.is_null() {
+        return Argon2Error::PwdPtrMismatch.code();
+    }
+
+    if setting.is_null() {
+        return Argon2Error::DecodingFail.code();
+    }
+
+    if output.is_null() {
+        return Argon2Error::OutputPtrNull.code();
+    }
+
+    let phrase = unsafe { CStr::from_ptr(phrase) }.to_bytes();
+    let Ok(setting) = unsafe { CStr::from_ptr(setting) }.to_str() else {
+        return Argon2Error::DecodingFail.code();
+    };
+
+    match crate::crypt::crypt(phrase, setting) {
+        Ok(hash) => unsafe { write_str(&hash, output, outputlen) },
+        Err(e) => error_code(&e),
+    }
+}
+
+/// Verifies a password against a NUL-terminated PHC string
+///
+/// Returns [`ARGON2RS_OK`] if the password matches, `ARGON2_VERIFY_MISMATCH` if it does not or a
+/// negative error code.
+///
+/// # Safety
+///
+/// `encoded` must point to a NUL-terminated string and `pwd` must be valid for reads of `pwdlen`
+/// bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn argon2rs_verify(
+    encoded: *const c_char,
+    pwd: *const u8,
+    pwdlen: usize,
+) -> c_int {
+    if encoded.is_null() {
+        return Argon2Error::DecodingFail.code();
+    }
+
+    let pwd = match unsafe { slice(pwd, pwdlen, Argon2Error::PwdPtrMismatch) } {
+        Ok(pwd) => pwd,
+        Err(code) => return code,
+    };
+
+    let Ok(encoded) = unsafe { CStr::from_ptr(encoded) }.to_str() else {
+        return Argon2Error::DecodingFail.code();
+    };
+
+    match Argon2::verify_encoded(pwd, encoded) {
+        Ok(true) => ARGON2RS_OK,
+        Ok(false) => Argon2Error::VerifyMismatch.code(),
+        Err(e) => error_code(&e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capi() {
+        let pwd = b"password";
+        let salt = b"somesaltsomesalt";
+
+        let mut hash = [0u8; 32];
+        let code = unsafe {
+            argon2rs_hash(
+                2,
+                1024,
+                1,
+                pwd.as_ptr(),
+                pwd.len(),
+                salt.as_ptr(),
+                salt.len(),
+                hash.as_mut_ptr(),
+                hash.len(),
+                2,
+            )
+        };
+        assert_eq!(code, ARGON2RS_OK);
+
+        let expected = Argon2::new(1024, 2, 1).with_hash_length(32);
+        assert_eq!(hash.to_vec(), expected.hash_password(pwd, salt).unwrap());
+
+        let mut encoded = [0 as c_char; 128];
+        let code = unsafe {
+            argon2rs_hash_encoded(
+                2,
+                1024,
+                1,
+                pwd.as_ptr(),
+                pwd.len(),
+                salt.as_ptr(),
+                salt.len(),
+                32,
+                encoded.as_mut_ptr(),
+                encoded.len(),
+                2,
+            )
+        };
+        assert_eq!(code, ARGON2RS_OK);
+
+        let string = unsafe { CStr::from_ptr(encoded.as_ptr()) };
+        assert_eq!(
+            string.to_str().unwrap(),
+            expected.hash_password_encoded(pwd, salt).unwrap()
+        );
+
+        let verify =
+            |pwd: &[u8]| unsafe { argon2rs_verify(encoded.as_ptr(), pwd.as_ptr(), pwd.len()) };
+        assert_eq!(verify(b"password"), ARGON2RS_OK);
+        assert_eq!(verify(b"wrong"), Argon2Error::VerifyMismatch.code());
+
+        // Too small for the string and its terminator
+        let mut small = [0 as c_char; 16];
+        let code = unsafe {
+            argon2rs_hash_encoded(
+                2,
+                1024,
+                1,
+                pwd.as_ptr(),
+                pwd.len(),
+                salt.as_ptr(),
+                salt.len(),
+                32,
+                small.as_mut_ptr(),
+                small.len(),
+                2,
+            )
+        };
+        assert_eq!(code, Argon2Error::EncodingFail.code());
+
+        let code = unsafe {
+            argon2rs_hash(
+                2,
+                1024,
+                1,
+                std::ptr::null(),
+                8,
+                salt.as_ptr(),
+                16,
+                hash.as_mut_ptr(),
+                32,
+                2,
+            )
+        };
+        assert_eq!(code, Argon2Error::PwdPtrMismatch.code());
+
+        let mut crypted = [0 as c_char; 128];
+        let code = unsafe {
+            argon2rs_crypt(
+                c"password".as_ptr(),
+                encoded.as_ptr(),
+                crypted.as_mut_ptr(),
+                crypted.len(),
+            )
+        };
+        assert_eq!(code, ARGON2RS_OK);
+        assert_eq!(unsafe { CStr::from_ptr(crypted.as_ptr()) }, string);
+    }
+}
@@ -0,0 +1,165 @@
+use super::{Argon2, Error};
+
+/// Limits at or above this are how cgroup v1 reports no limit
+const UNLIMITED: u64 = 1 << 62;
+
+/// The memory limit of the container or cgroup this process runs in, in bytes
+///
+/// Reads `memory.max` of cgroup v2 along the path of the process up to the root, or
+/// `memory.limit_in_bytes` of cgroup v1, and returns the lowest limit. `None` if there is no
+/// limit or it cannot be read, always on other systems than Linux.
+///
+/// A pod with a 512 MiB limit is OOM-killed by a hash with 1 GiB of working memory, while
+/// `/proc/meminfo` shows the memory of the whole host.
+pub fn memory_limit() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let cgroups = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+        let (v2, v1) = parse_cgroups(&cgroups);
+        let read = |path: String| std::fs::read_to_string(path).ok();
+
+        if let Some(path) = v2 {
+            let limit = ancestors(path)
+                .filter_map(|dir| read(format!("/sys/fs/cgroup{dir}/memory.max")))
+                .filter_map(|limit| parse_limit(&limit))
+                .min();
+            if limit.is_some() {
+                return limit;
+            }
+        }
+
+        if let Some(path) = v1 {
+            // Inside a container the path is usually not visible, its limit is at the root
+            return read(format!("/sys/fs/cgroup/memory{path}/memory.limit_in_bytes"))
+                .or_else(|| read("/sys/fs/cgroup/memory/memory.limit_in_bytes".to_string()))
+                .and_then(|limit| parse_limit(&limit));
+        }
+
+        None
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// The cgroup v2 path and the cgroup v1 memory path of `/proc/self/cgroup`
+fn parse_cgroups(cgroups: &str) -> (Option<&str>, Option<&str>) {
+    let (mut v2, mut v1) = (None, None);
+
+    for line in cgroups.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+
+        if id == "0" && controllers.is_empty() {
+            v2 = Some(path);
+        } else if controllers
+            .split(',')
+            .any(|controller| controller == "memory")
+        {
+            v1 = Some(path);
+        }
+    }
+
+    (v2, v1)
+}
+
+/// `/a/b`, `/a` and the root of a cgroup path
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    let path = path.trim_end_matches('/');
+    std::iter::successors(Some(path), |dir| {
+        (!dir.is_empty()).then(|| dir.rsplit_once('/').map_or("", |(parent, _)| parent))
+    })
+}
+
+/// A limit in bytes, `None` for `max` and the huge values of cgroup v1
+fn parse_limit(limit: &str) -> Option<u64> {
+    limit
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|&limit| limit < UNLIMITED)
+}
+
+impl Argon2 {
+    /// Checks that a hash fits in the memory limit of the container, see [`memory_limit`]
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::MemoryLimitExceeded`] with the required bytes and the limit if
+    /// [`Argon2::memory_usage_bytes`] exceeds it
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = match Argon2::balanced().check_memory_limit() {
+    ///     Ok(()) => Argon2::balanced(),
+    ///     Err(_) => Argon2::very_fast(),
+    /// };
+    /// ```
+    pub fn check_memory_limit(&self) -> Result<(), Error> {
+        let required = self.memory_usage_bytes();
+        match memory_limit() {
+            Some(limit) if required > limit => Err(Error::MemoryLimitExceeded(required, limit)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cgroups() {
+        assert_eq!(
+            parse_cgroups("0::/kubepods/pod1/abc\n"),
+            (Some("/kubepods/pod1/abc"), None)
+        );
+
+        let v1 = "12:pids:/\n4:memory:/docker/abc\n3:cpu,cpuacct:/\n";
+        assert_eq!(parse_cgroups(v1), (None, Some("/docker/abc")));
+
+        let hybrid = "1:memory,cpu:/job\n0::/user.slice\n";
+        assert_eq!(parse_cgroups(hybrid), (Some("/user.slice"), Some("/job")));
+    }
+
+    #[test]
+    fn test_ancestors() {
+        assert_eq!(ancestors("/a/b").collect::<Vec<_>>(), ["/a/b", "/a", ""]);
+        assert_eq!(ancestors("/").collect::<Vec<_>>(), [""]);
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("536870912\n"), Some(512 * 1024 * 1024));
+        assert_eq!(parse_limit("max\n"), None);
+        assert_eq!(parse_limit("9223372036854771712\n"), None);
+    }
+
+    #[test]
+    fn test_check_memory_limit() {
+        let argon2 = Argon2::new(1024, 1, 1);
+        match memory_limit() {
+            Some(limit) if argon2.memory_usage_bytes() > limit => {
+                assert!(argon2.check_memory_limit().is_err())
+            }
+            _ => assert!(argon2.check_memory_limit().is_ok()),
+        }
+
+        let huge = Argon2::new(u32::MAX, 1, 1);
+        if let Some(limit) = memory_limit().filter(|&l| l < huge.memory_usage_bytes()) {
+            assert!(matches!(
+                huge.check_memory_limit(),
+                Err(Error::MemoryLimitExceeded(_, l)) if l == limit
+            ));
+        }
+    }
+}
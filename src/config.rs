@@ -0,0 +1,230 @@
+use super::{Argon2, Error, phc};
+
+/// The largest number of lanes supported by the C implementation
+pub(crate) const MAX_LANES: u32 = 0x00FF_FFFF;
+
+impl Argon2 {
+    /// Create a new Argon2 instance from environment variables
+    ///
+    /// `ARGON2_M_COST`, `ARGON2_T_COST` and `ARGON2_P_COST` are required. `ARGON2_HASH_LENGTH`,
+    /// `ARGON2_ALGORITHM` (e.g. `argon2id`), `ARGON2_VERSION` (e.g. `0x13`), `ARGON2_AD` (B64
+    /// encoded), `ARGON2_THREADS`, `ARGON2_PREHASH` (the threshold in bytes) and
+    /// `ARGON2_NORMALIZATION` (`nfc` or `nfkc`) are optional and default to the values of
+    /// [`Argon2::new`].
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::InvalidConfig`] if a variable is missing, malformed or out of range
+    pub fn from_env() -> Result<Self, Error> {
+        from_vars(|name| match std::env::var(name) {
+            Ok(value) => Ok(Some(value)),
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(std::env::VarError::NotUnicode(_)) => {
+                Err(Error::InvalidConfig(format!("{name} is not valid unicode")))
+            }
+        })
+    }
+
+    /// Create a new Argon2 instance from a TOML config
+    ///
+    /// The keys are the same as the fields of [`Argon2`], only `m_cost`, `t_cost` and `p_cost`
+    /// are required:
+    ///
+    /// ```toml
+    /// m_cost = 65536
+    /// t_cost = 3
+    /// p_cost = 4
+    /// algorithm = "argon2id"
+    /// version = "0x13"
+    /// ```
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::InvalidConfig`] if the config is malformed or a value is out of range
+    #[cfg(feature = "toml")]
+    pub fn from_toml(config: &str) -> Result<Self, Error> {
+        let argon2: Argon2 =
+            toml::from_str(config).map_err(|e| Error::InvalidConfig(e.to_string()))?;
+        check(argon2)
+    }
+}
+
+fn from_vars(var: impl Fn(&str) -> Result<Option<String>, Error>) -> Result<Argon2, Error> {
+    let required =
+        |name: &str| var(name)?.ok_or_else(|| Error::InvalidConfig(format!("{name} is not set")));
+
+    let mut argon2 = Argon2::new(
+        parse("ARGON2_M_COST", &required("ARGON2_M_COST")?)?,
+        parse("ARGON2_T_COST", &required("ARGON2_T_COST")?)?,
+        parse("ARGON2_P_COST", &required("ARGON2_P_COST")?)?,
+    );
+
+    if let Some(value) = var("ARGON2_HASH_LENGTH")? {
+        argon2.hash_length = parse("ARGON2_HASH_LENGTH", &value)?;
+    }
+
+    if let Some(value) = var("ARGON2_ALGORITHM")? {
+        argon2.algorithm = parse("ARGON2_ALGORITHM", &value)?;
+    }
+
+    if let Some(value) = var("ARGON2_VERSION")? {
+        argon2.version = parse("ARGON2_VERSION", &value)?;
+    }
+
+    if let Some(value) = var("ARGON2_AD")? {
+        argon2.ad = phc::b64_decode(value.trim())
+            .ok_or_else(|| Error::InvalidConfig("ARGON2_AD is not valid B64".to_string()))?;
+    }
+
+    if let Some(value) = var("ARGON2_THREADS")? {
+        argon2.threads = Some(parse("ARGON2_THREADS", &value)?);
+    }
+
+    if let Some(value) = var("ARGON2_PREHASH")? {
+        argon2.prehash = Some(parse("ARGON2_PREHASH", &value)?);
+    }
+
+    if let Some(value) = var("ARGON2_NORMALIZATION")? {
+        argon2.normalization = Some(parse("ARGON2_NORMALIZATION", &value)?);
+    }
+
+    check(argon2)
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, Error> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::InvalidConfig(format!("{name} has an invalid value: {value:?}")))
+}
+
+/// Rejects parameters the implementation would only reject when hashing, see
+/// [`Argon2::validate`]
+pub(crate) fn check(argon2: Argon2) -> Result<Argon2, Error> {
+    argon2
+        .validate()
+        .map_err(|e| Error::InvalidConfig(e.to_string()))?;
+    Ok(argon2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Algorithm, RECOMMENDED_HASH_LENGTH, Version, normalize::Normalization};
+    use std::collections::HashMap;
+
+    fn from_map(vars: &[(&str, &str)]) -> Result<Argon2, Error> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        from_vars(|name| Ok(vars.get(name).map(|v| v.to_string())))
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let argon2 = from_map(&[
+            ("ARGON2_M_COST", "65536"),
+            ("ARGON2_T_COST", "3"),
+            ("ARGON2_P_COST", " 4 "),
+            ("ARGON2_HASH_LENGTH", "32"),
+            ("ARGON2_ALGORITHM", "argon2id"),
+            ("ARGON2_VERSION", "0x10"),
+            ("ARGON2_AD", "dXNlcjE"),
+            ("ARGON2_THREADS", "2"),
+            ("ARGON2_PREHASH", "1024"),
+            ("ARGON2_NORMALIZATION", "nfkc"),
+        ])
+        .unwrap();
+
+        let expected = Argon2::new(65536, 3, 4)
+            .with_hash_length(32)
+            .with_algorithm(Algorithm::Argon2id)
+            .with_version(Version::V0x10)
+            .with_ad(b"user1")
+            .with_threads(2)
+            .with_prehash(1024)
+            .with_normalization(Normalization::Nfkc);
+        assert_eq!(argon2, expected);
+
+        let defaults = from_map(&[
+            ("ARGON2_M_COST", "1024"),
+            ("ARGON2_T_COST", "2"),
+            ("ARGON2_P_COST", "1"),
+        ]);
+        assert_eq!(defaults.unwrap().hash_length, RECOMMENDED_HASH_LENGTH);
+
+        let invalid = [
+            &[("ARGON2_M_COST", "1024"), ("ARGON2_T_COST", "2")][..],
+            &[
+                ("ARGON2_M_COST", "1k"),
+                ("ARGON2_T_COST", "2"),
+                ("ARGON2_P_COST", "1"),
+            ],
+            &[
+                ("ARGON2_M_COST", "1024"),
+                ("ARGON2_T_COST", "0"),
+                ("ARGON2_P_COST", "1"),
+            ],
+            &[
+                ("ARGON2_M_COST", "8"),
+                ("ARGON2_T_COST", "2"),
+                ("ARGON2_P_COST", "2"),
+            ],
+            &[
+                ("ARGON2_M_COST", "1024"),
+                ("ARGON2_T_COST", "2"),
+                ("ARGON2_P_COST", "1"),
+                ("ARGON2_ALGORITHM", "argon3"),
+            ],
+            &[
+                ("ARGON2_M_COST", "1024"),
+                ("ARGON2_T_COST", "2"),
+                ("ARGON2_P_COST", "1"),
+                ("ARGON2_NORMALIZATION", "nfd"),
+            ],
+            &[
+                ("ARGON2_M_COST", "1024"),
+                ("ARGON2_T_COST", "2"),
+                ("ARGON2_P_COST", "1"),
+                ("ARGON2_THREADS", "0"),
+            ],
+        ];
+
+        for vars in invalid {
+            assert!(matches!(from_map(vars), Err(Error::InvalidConfig(_))));
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_toml() {
+        let argon2 = Argon2::from_toml(
+            r#"
+            m_cost = 65536
+            t_cost = 3
+            p_cost = 4
+            algorithm = "argon2id"
+            version = "0x13"
+            threads = 2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            argon2,
+            Argon2::new(65536, 3, 4)
+                .with_algorithm(Algorithm::Argon2id)
+                .with_threads(2)
+        );
+
+        assert!(matches!(
+            Argon2::from_toml("m_cost = 1024\nt_cost = 0\np_cost = 1"),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            Argon2::from_toml("m_cost = 1024"),
+            Err(Error::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            Argon2::from_toml("m_cost = 1024\nt_cost = 2\np_cost = 1\nthreads = 0"),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+use super::{Argon2, Error, phc};
+
+/// The hash length used when the setting does not carry a hash, same as the libargon2 CLI
+pub const DEFAULT_HASH_LENGTH: u64 = 32;
+
+/// Creates a crypt(3) setting for the given parameters and salt
+///
+/// The setting is a PHC string without the hash segment, e.g. `$argon2id$v=19$m=65536,t=2,p=1$<salt>`.
+/// Pass it to [`crypt`] to hash a password, the hash length is [`DEFAULT_HASH_LENGTH`].
+///
+/// ## Arguments
+///
+/// - `argon2` - The parameters to hash with
+/// - `salt` - The salt to use for hashing
+pub fn gensalt(argon2: &Argon2, salt: &[u8]) -> String {
+    let mut encoded = phc::encode(argon2, None, salt, &[]);
+    encoded.pop();
+    encoded
+}
+
+/// Hashes a password the way crypt(3) does
+///
+/// `setting` is either a setting created by [`gensalt`] or a complete hash, in which case the
+/// parameters, the salt and the hash length are taken from it. As with crypt(3), a password
+/// matches a stored hash if `crypt(password, hash) == hash`, use [`verify`] to compare in constant
+/// time.
+///
+/// The result only uses characters allowed in `/etc/shadow` and contains no `:`.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, crypt};
+///
+/// let setting = crypt::gensalt(&Argon2::new(1024, 2, 1), b"somesaltsomesalt");
+/// let hash = crypt::crypt("password", &setting).unwrap();
+///
+/// assert_eq!(crypt::crypt("password", &hash).unwrap(), hash);
+/// assert!(crypt::verify("password", &hash).unwrap());
+/// ```
+pub fn crypt(password: impl AsRef<[u8]>, setting: &str) -> Result<String, Error> {
+    let decoded = match phc::decode(setting) {
+        Ok(decoded) => decoded,
+        // A setting without the hash segment
+        Err(_) => phc::decode(&format!("{setting}$"))?,
+    };
+
+    if decoded.keyid.is_some() {
+        return Err(Error::PepperRequired);
+    }
+
+    let mut argon2 = decoded.argon2;
+
+    if argon2.hash_length == 0 {
+        argon2.hash_length = DEFAULT_HASH_LENGTH;
+    }
+
+    argon2.hash_password_encoded(password, &decoded.salt)
+}
+
+/// Verifies a password against a hash created by [`crypt`]
+pub fn verify(password: impl AsRef<[u8]>, hash: &str) -> Result<bool, Error> {
+    Argon2::verify_encoded(password, hash)
+}
+
+// The tests hash with Argon2i and Argon2d, which the `argon2id-only` feature rejects
+#[cfg(all(test, not(feature = "argon2id-only")))]
+mod tests {
+    use super::*;
+    use crate::Algorithm;
+
+    #[test]
+    fn test_crypt() {
+        let argon2 = Argon2::new(1024, 2, 1).with_algorithm(Algorithm::Argon2i);
+        let setting = gensalt(&argon2, b"somesaltsomesalt");
+        assert_eq!(
+            setting,
+            "$argon2i$v=19$m=1024,t=2,p=1$c29tZXNhbHRzb21lc2FsdA"
+        );
+
+        let hash = crypt("password", &setting).unwrap();
+        assert!(hash.starts_with(&format!("{setting}$")));
+        assert!(!hash.contains(':'));
+
+        let expected = argon2.with_hash_length(DEFAULT_HASH_LENGTH);
+        assert_eq!(
+            hash,
+            expected
+                .hash_password_encoded("password", b"somesaltsomesalt")
+                .unwrap()
+        );
+
+        assert_eq!(crypt("password", &hash).unwrap(), hash);
+        assert_ne!(crypt("wrong", &hash).unwrap(), hash);
+        assert!(verify("password", &hash).unwrap());
+        assert!(!verify("wrong", &hash).unwrap());
+
+        assert!(crypt("password", "$6$rounds=5000$salt").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use super::{Error, stored::StoredHash};
+
+/// The algorithm prefix Django's `Argon2PasswordHasher` puts in front of the PHC string
+pub const PREFIX: &str = "argon2";
+
+/// Parses a hash created by Django or passlib into a [`StoredHash`]
+///
+/// Accepts:
+///
+/// - Django's `argon2$argon2id$v=19$...` format, the PHC string prefixed with the hasher name
+/// - Django hashes created with argon2-cffi before 16.0, which omit the `v=` segment
+/// - passlib's `argon2` handler, which emits plain PHC strings for the `argon2i`, `argon2d` and
+///   `argon2id` idents, with or without the `v=` segment
+///
+/// ## Example
+///
+/// ```
+/// # #[cfg(not(feature = "argon2id-only"))]
+/// # {
+/// use argon2_rs::django;
+///
+/// let encoded = "argon2$argon2i$m=8,t=1,p=1$c29tZXNhbHQ$gwQOXSNhxiOxPOA0+PY10P9QFO4NAYysnqRt1GSQLE55m+2GYDt9FEjPMHhP2Cuf0nOEXXMocVrsJAtNSsKyfg";
+/// let stored = django::parse(encoded).unwrap();
+///
+/// assert!(stored.verify("secret").unwrap());
+/// assert_eq!(django::encode(&stored), encoded.replace("$m=", "$v=16$m="));
+/// # }
+/// ```
+pub fn parse(encoded: &str) -> Result<StoredHash, Error> {
+    let phc = encoded
+        .strip_prefix(PREFIX)
+        .filter(|rest| rest.starts_with('$'))
+        .unwrap_or(encoded);
+
+    phc.parse()
+}
+
+/// Verifies the given password against a hash created by Django or passlib
+///
+/// See [`parse`] for the accepted formats.
+pub fn verify(password: impl AsRef<[u8]>, encoded: &str) -> Result<bool, Error> {
+    parse(encoded)?.verify(password)
+}
+
+/// Encodes the hash in Django's format so it can be written back to a Django user table
+pub fn encode(stored: &StoredHash) -> String {
+    format!("{PREFIX}{stored}")
+}
+
+// The tests hash with Argon2i and Argon2d, which the `argon2id-only` feature rejects
+#[cfg(all(test, not(feature = "argon2id-only")))]
+mod tests {
+    use super::*;
+    use crate::{Algorithm, Argon2, Version};
+
+    /// A hash from Django's test suite, created by argon2-cffi before the `v=` segment was added
+    const DJANGO_LEGACY: &str = "argon2$argon2i$m=8,t=1,p=1$c29tZXNhbHQ$gwQOXSNhxiOxPOA0+PY10P9QFO4NAYysnqRt1GSQLE55m+2GYDt9FEjPMHhP2Cuf0nOEXXMocVrsJAtNSsKyfg";
+
+    #[test]
+    fn test_parse() {
+        let stored = parse(DJANGO_LEGACY).unwrap();
+        assert_eq!(stored.argon2.algorithm, Algorithm::Argon2i);
+        assert_eq!(stored.argon2.version, Version::V0x10);
+        assert_eq!(stored.salt, b"somesalt");
+        assert!(verify("secret", DJANGO_LEGACY).unwrap());
+        assert!(!verify("wrong", DJANGO_LEGACY).unwrap());
+
+        let argon2 = Argon2::new(1024, 2, 8).with_hash_length(32);
+        let stored = argon2
+            .hash_password_stored("password", b"somesaltsomesalt")
+            .unwrap();
+        let django = encode(&stored);
+        assert!(django.starts_with("argon2$argon2id$v=19$m=1024,t=2,p=8$"));
+        assert_eq!(parse(&django).unwrap(), stored);
+
+        // passlib emits plain PHC strings
+        assert_eq!(parse(&stored.to_string()).unwrap(), stored);
+
+        assert!(parse("argon2argon2id$v=19$m=8,t=1,p=1$c29tZXNhbHQ$aGFzaA").is_err());
+        assert!(parse("pbkdf2_sha256$600000$salt$hash").is_err());
+    }
+}
@@ -1,124 +1,433 @@
 use thiserror::Error as ThisError;
 
 #[derive(ThisError, Debug)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
 pub enum Error {
     #[error("Argon2 error: {0}")]
     Argon2(#[from] Argon2Error),
+    #[error("Invalid PHC string: {0}")]
+    InvalidPhcString(&'static str),
+    #[error("Invalid binary record: {0}")]
+    InvalidRecord(&'static str),
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid keystore: {0}")]
+    InvalidKeystore(String),
+    #[error("Wrong password or the keystore has been modified")]
+    KeystoreMacMismatch,
+    #[error("Invalid legacy hash: {0}")]
+    InvalidLegacyHash(String),
+    #[error("The hash does not belong to any registered scheme")]
+    UnknownScheme,
+    #[error("Hash policy violation: {0}")]
+    PolicyViolation(String),
+    #[error("Salt is too short: {0} bytes, at least {min} are required", min = crate::salt::MIN_SALT_LENGTH)]
+    SaltTooShort(usize),
+    #[error("Password is too long: {0} bytes, the limit is {1} bytes")]
+    PasswordTooLong(usize, usize),
+    #[error(
+        "Invalid hash length: {0} bytes, it must be between {min} and {max}",
+        min = crate::MIN_HASH_LENGTH,
+        max = crate::MAX_HASH_LENGTH
+    )]
+    InvalidHashLength(u64),
+    #[error("Failed to generate random bytes: {0}")]
+    Random(String),
+    #[error("Heapless limit exceeded: {0}")]
+    HeaplessLimit(&'static str),
+    #[error("Failed to map working memory: {0}")]
+    Memory(String),
+    #[error("Out of memory")]
+    OutOfMemory,
+    #[error("Too many concurrent hashes, {0} are already running")]
+    TooManyHashes(usize),
+    #[error("{0} bytes of memory do not fit in the address space of this platform")]
+    MemoryTooLargeForPlatform(u64),
+    #[error("The hash needs {0} bytes of memory, the memory limit is {1} bytes")]
+    MemoryLimitExceeded(u64, u64),
+    #[error("Failed to build thread pool: {0}")]
+    ThreadPool(String),
+    #[error("The hash was cancelled")]
+    Cancelled,
+    #[error("The hash exceeded its time budget")]
+    DeadlineExceeded,
+    #[error("The hash queue is full")]
+    QueueFull,
+    #[error("The blocking hash task failed: {0}")]
+    Task(String),
+    #[error("Sandboxed hash failed: {0}")]
+    Sandbox(String),
+    #[error("The hash is keyed with a pepper, use `KeyedArgon2` to verify it")]
+    PepperRequired,
+    #[error("Failed to resolve the pepper: {0}")]
+    SecretProvider(String),
 }
 
 #[derive(ThisError, Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Argon2Error {
     #[error("Output pointer is null")]
     OutputPtrNull,
-    #[error("Output is too short")]
+    #[error("Output is too short, the hash must be at least 4 bytes")]
     OutputTooShort,
-    #[error("Output is too long")]
+    #[error("Output is too long, the hash must be at most 2^32 - 1 bytes")]
     OutputTooLong,
     #[error("Password is too short")]
     PasswordTooShort,
-    #[error("Password is too long")]
+    #[error("Password is too long, it must be at most 2^32 - 1 bytes")]
     PasswordTooLong,
-    #[error("Salt is too short")]
+    #[error("Salt is too short, it must be at least 8 bytes")]
     SaltTooShort,
-    #[error("Salt is too long")]
+    #[error("Salt is too long, it must be at most 2^32 - 1 bytes")]
     SaltTooLong,
     #[error("Associated data is too short")]
     AdTooShort,
-    #[error("Associated data is too long")]
+    #[error("Associated data is too long, it must be at most 2^32 - 1 bytes")]
     AdTooLong,
     #[error("Secret is too short")]
     SecretTooShort,
-    #[error("Secret is too long")]
+    #[error("Secret is too long, it must be at most 2^32 - 1 bytes")]
     SecretTooLong,
-    #[error("Time cost is too small")]
+    #[error("Time cost is too small, `t_cost` must be at least 1")]
     TimeTooSmall,
-    #[error("Time cost is too large")]
+    #[error("Time cost is too large, `t_cost` must be at most 2^32 - 1")]
     TimeTooLarge,
-    #[error("Memory cost is too little")]
+    #[error("Memory cost is too little, `m_cost` must be at least 8 KiB per lane")]
     MemoryTooLittle,
-    #[error("Memory cost is too much")]
+    #[error("Memory cost is too much for this platform, lower `m_cost`")]
     MemoryTooMuch,
-    #[error("Number of lanes is too few")]
+    #[error("Number of lanes is too few, `p_cost` must be at least 1")]
     LanesTooFew,
-    #[error("Number of lanes is too many")]
+    #[error("Number of lanes is too many, `p_cost` must be at most 2^24 - 1")]
     LanesTooMany,
-    #[error("Password pointer mismatch")]
+    #[error("Password pointer is null but its length is not 0")]
     PwdPtrMismatch,
-    #[error("Salt pointer mismatch")]
+    #[error("Salt pointer is null but its length is not 0")]
     SaltPtrMismatch,
-    #[error("Secret pointer mismatch")]
+    #[error("Secret pointer is null but its length is not 0")]
     SecretPtrMismatch,
-    #[error("Associated data pointer mismatch")]
+    #[error("Associated data pointer is null but its length is not 0")]
     AdPtrMismatch,
-    #[error("Memory allocation error")]
+    #[error("Failed to allocate the working memory, lower `m_cost` or free up memory")]
     MemoryAllocationError,
-    #[error("Free memory callback is null")]
+    #[error("Free memory callback is null but the allocate callback is set")]
     FreeMemoryCbkNull,
-    #[error("Allocate memory callback is null")]
+    #[error("Allocate memory callback is null but the free callback is set")]
     AllocateMemoryCbkNull,
-    #[error("Incorrect parameter")]
+    #[error("Incorrect parameter, the context is null")]
     IncorrectParameter,
-    #[error("Incorrect Argon2 type")]
+    #[error("Incorrect Argon2 type, use Argon2d, Argon2i or Argon2id")]
     IncorrectType,
-    #[error("Output pointer mismatch")]
+    #[error("Output pointer is null but its length is not 0")]
     OutPtrMismatch,
-    #[error("Number of threads is too few")]
+    #[error("Number of threads is too few, it must be at least 1")]
     ThreadsTooFew,
-    #[error("Number of threads is too many")]
+    #[error("Number of threads is too many, it must be at most 2^24 - 1")]
     ThreadsTooMany,
     #[error("Missing arguments")]
     MissingArgs,
-    #[error("Encoding failed")]
+    #[error("Encoding failed, the output buffer is too small for the encoded hash")]
     EncodingFail,
-    #[error("Decoding failed")]
+    #[error("Decoding failed, the encoded hash is malformed")]
     DecodingFail,
-    #[error("Thread failed")]
+    #[error("Failed to create or join a hashing thread")]
     ThreadFail,
-    #[error("Decoding length failed")]
+    #[error("Decoding failed, the salt or the hash has an invalid length")]
     DecodingLengthFail,
-    #[error("Verification mismatch")]
+    #[error("The password does not match the hash")]
     VerifyMismatch,
     #[error("Unknown argon2 error with code: {0}")]
     Unknown(i32),
 }
 
-pub(crate) fn map_argon2_error(code: i32) -> Argon2Error {
-    match code {
-        -1 => Argon2Error::OutputPtrNull,
-        -2 => Argon2Error::OutputTooShort,
-        -3 => Argon2Error::OutputTooLong,
-        -4 => Argon2Error::PasswordTooShort,
-        -5 => Argon2Error::PasswordTooLong,
-        -6 => Argon2Error::SaltTooShort,
-        -7 => Argon2Error::SaltTooLong,
-        -8 => Argon2Error::AdTooShort,
-        -9 => Argon2Error::AdTooLong,
-        -10 => Argon2Error::SecretTooShort,
-        -11 => Argon2Error::SecretTooLong,
-        -12 => Argon2Error::TimeTooSmall,
-        -13 => Argon2Error::TimeTooLarge,
-        -14 => Argon2Error::MemoryTooLittle,
-        -15 => Argon2Error::MemoryTooMuch,
-        -16 => Argon2Error::LanesTooFew,
-        -17 => Argon2Error::LanesTooMany,
-        -18 => Argon2Error::PwdPtrMismatch,
-        -19 => Argon2Error::SaltPtrMismatch,
-        -20 => Argon2Error::SecretPtrMismatch,
-        -21 => Argon2Error::AdPtrMismatch,
-        -22 => Argon2Error::MemoryAllocationError,
-        -23 => Argon2Error::FreeMemoryCbkNull,
-        -24 => Argon2Error::AllocateMemoryCbkNull,
-        -25 => Argon2Error::IncorrectParameter,
-        -26 => Argon2Error::IncorrectType,
-        -27 => Argon2Error::OutPtrMismatch,
-        -28 => Argon2Error::ThreadsTooFew,
-        -29 => Argon2Error::ThreadsTooMany,
-        -30 => Argon2Error::MissingArgs,
-        -31 => Argon2Error::EncodingFail,
-        -32 => Argon2Error::DecodingFail,
-        -33 => Argon2Error::ThreadFail,
-        -34 => Argon2Error::DecodingLengthFail,
-        -35 => Argon2Error::VerifyMismatch,
-        _ => Argon2Error::Unknown(code),
+impl Error {
+    /// The libargon2 error code of this error, `None` for errors that have none
+    ///
+    /// Errors this crate detects before calling the implementation get the code libargon2 would
+    /// have failed with, malformed PHC strings get `ARGON2_DECODING_FAIL`.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, error::Argon2Error};
+    ///
+    /// let error = Argon2::new(1024, 0, 1)
+    ///     .hash_password("password", b"somesaltsomesalt")
+    ///     .unwrap_err();
+    /// assert_eq!(error.code(), Some(Argon2Error::TimeTooSmall.code()));
+    /// ```
+    pub fn code(&self) -> Option<i32> {
+        let error = match self {
+            Error::Argon2(error) => *error,
+            Error::InvalidPhcString(_) => Argon2Error::DecodingFail,
+            Error::SaltTooShort(_) => Argon2Error::SaltTooShort,
+            Error::PasswordTooLong(..) => Argon2Error::PasswordTooLong,
+            Error::InvalidHashLength(length) if *length < crate::MIN_HASH_LENGTH => {
+                Argon2Error::OutputTooShort
+            }
+            Error::InvalidHashLength(_) => Argon2Error::OutputTooLong,
+            Error::OutOfMemory => Argon2Error::MemoryAllocationError,
+            _ => return None,
+        };
+        Some(error.code())
+    }
+
+    /// Whether the parameters or the inputs are invalid, retrying will fail the same way
+    ///
+    /// Covers the costs, lengths and algorithms rejected by the implementation, the configuration
+    /// and the hash policy. The fix is in the configuration or the caller.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let error = Argon2::new(1024, 2, 0)
+    ///     .hash_password("password", b"somesaltsomesalt")
+    ///     .unwrap_err();
+    /// assert!(error.is_parameter_error());
+    /// assert!(!error.is_memory_error());
+    /// ```
+    pub fn is_parameter_error(&self) -> bool {
+        match self {
+            Error::Argon2(error) => error.is_parameter_error(),
+            Error::InvalidConfig(_)
+            | Error::PolicyViolation(_)
+            | Error::SaltTooShort(_)
+            | Error::PasswordTooLong(..)
+            | Error::InvalidHashLength(_)
+            | Error::HeaplessLimit(_)
+            | Error::MemoryTooLargeForPlatform(_)
+            | Error::MemoryLimitExceeded(..) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the working memory could not be provided
+    ///
+    /// Either the memory ran out, which may pass once other hashes complete, or the memory cost
+    /// exceeds what this platform or the memory limit allows.
+    pub fn is_memory_error(&self) -> bool {
+        matches!(
+            self,
+            Error::Argon2(Argon2Error::MemoryAllocationError)
+                | Error::OutOfMemory
+                | Error::Memory(_)
+                | Error::MemoryTooLargeForPlatform(_)
+                | Error::MemoryLimitExceeded(..)
+        )
+    }
+
+    /// Whether the process is too busy to hash right now and the request should be shed or
+    /// retried later
+    ///
+    /// Covers the concurrency limit, a full queue, an exceeded time budget and running out of
+    /// memory.
+    pub fn is_overload_error(&self) -> bool {
+        matches!(
+            self,
+            Error::TooManyHashes(_)
+                | Error::QueueFull
+                | Error::DeadlineExceeded
+                | Error::OutOfMemory
+                | Error::Argon2(Argon2Error::MemoryAllocationError)
+        )
+    }
+
+    /// Whether the password is wrong
+    ///
+    /// The verification methods return `Ok(false)` for a wrong password, this only matches the
+    /// errors that mean the same, e.g. [`Error::KeystoreMacMismatch`].
+    pub fn is_verification_mismatch(&self) -> bool {
+        matches!(
+            self,
+            Error::Argon2(Argon2Error::VerifyMismatch) | Error::KeystoreMacMismatch
+        )
+    }
+
+    /// Whether a stored hash or record could not be parsed
+    pub fn is_invalid_hash(&self) -> bool {
+        matches!(
+            self,
+            Error::InvalidPhcString(_)
+                | Error::InvalidRecord(_)
+                | Error::InvalidLegacyHash(_)
+                | Error::InvalidKeystore(_)
+                | Error::UnknownScheme
+                | Error::Argon2(Argon2Error::DecodingFail | Argon2Error::DecodingLengthFail)
+        )
+    }
+}
+
+impl Argon2Error {
+    /// The error for the given error code of libargon2, [`Argon2Error::Unknown`] if there is none
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::error::Argon2Error;
+    ///
+    /// assert_eq!(Argon2Error::from_code(-35), Argon2Error::VerifyMismatch);
+    /// assert_eq!(Argon2Error::from_code(-99), Argon2Error::Unknown(-99));
+    /// ```
+    pub fn from_code(code: i32) -> Self {
+        match code {
+            -1 => Argon2Error::OutputPtrNull,
+            -2 => Argon2Error::OutputTooShort,
+            -3 => Argon2Error::OutputTooLong,
+            -4 => Argon2Error::PasswordTooShort,
+            -5 => Argon2Error::PasswordTooLong,
+            -6 => Argon2Error::SaltTooShort,
+            -7 => Argon2Error::SaltTooLong,
+            -8 => Argon2Error::AdTooShort,
+            -9 => Argon2Error::AdTooLong,
+            -10 => Argon2Error::SecretTooShort,
+            -11 => Argon2Error::SecretTooLong,
+            -12 => Argon2Error::TimeTooSmall,
+            -13 => Argon2Error::TimeTooLarge,
+            -14 => Argon2Error::MemoryTooLittle,
+            -15 => Argon2Error::MemoryTooMuch,
+            -16 => Argon2Error::LanesTooFew,
+            -17 => Argon2Error::LanesTooMany,
+            -18 => Argon2Error::PwdPtrMismatch,
+            -19 => Argon2Error::SaltPtrMismatch,
+            -20 => Argon2Error::SecretPtrMismatch,
+            -21 => Argon2Error::AdPtrMismatch,
+            -22 => Argon2Error::MemoryAllocationError,
+            -23 => Argon2Error::FreeMemoryCbkNull,
+            -24 => Argon2Error::AllocateMemoryCbkNull,
+            -25 => Argon2Error::IncorrectParameter,
+            -26 => Argon2Error::IncorrectType,
+            -27 => Argon2Error::OutPtrMismatch,
+            -28 => Argon2Error::ThreadsTooFew,
+            -29 => Argon2Error::ThreadsTooMany,
+            -30 => Argon2Error::MissingArgs,
+            -31 => Argon2Error::EncodingFail,
+            -32 => Argon2Error::DecodingFail,
+            -33 => Argon2Error::ThreadFail,
+            -34 => Argon2Error::DecodingLengthFail,
+            -35 => Argon2Error::VerifyMismatch,
+            _ => Argon2Error::Unknown(code),
+        }
+    }
+
+    /// Whether the parameters or the inputs given to the implementation are invalid
+    pub fn is_parameter_error(&self) -> bool {
+        !matches!(
+            self,
+            Argon2Error::MemoryAllocationError
+                | Argon2Error::EncodingFail
+                | Argon2Error::DecodingFail
+                | Argon2Error::DecodingLengthFail
+                | Argon2Error::ThreadFail
+                | Argon2Error::VerifyMismatch
+                | Argon2Error::Unknown(_)
+        )
+    }
+
+    /// The error code of the C implementation this error was created from
+    pub fn code(&self) -> i32 {
+        match self {
+            Argon2Error::OutputPtrNull => -1,
+            Argon2Error::OutputTooShort => -2,
+            Argon2Error::OutputTooLong => -3,
+            Argon2Error::PasswordTooShort => -4,
+            Argon2Error::PasswordTooLong => -5,
+            Argon2Error::SaltTooShort => -6,
+            Argon2Error::SaltTooLong => -7,
+            Argon2Error::AdTooShort => -8,
+            Argon2Error::AdTooLong => -9,
+            Argon2Error::SecretTooShort => -10,
+            Argon2Error::SecretTooLong => -11,
+            Argon2Error::TimeTooSmall => -12,
+            Argon2Error::TimeTooLarge => -13,
+            Argon2Error::MemoryTooLittle => -14,
+            Argon2Error::MemoryTooMuch => -15,
+            Argon2Error::LanesTooFew => -16,
+            Argon2Error::LanesTooMany => -17,
+            Argon2Error::PwdPtrMismatch => -18,
+            Argon2Error::SaltPtrMismatch => -19,
+            Argon2Error::SecretPtrMismatch => -20,
+            Argon2Error::AdPtrMismatch => -21,
+            Argon2Error::MemoryAllocationError => -22,
+            Argon2Error::FreeMemoryCbkNull => -23,
+            Argon2Error::AllocateMemoryCbkNull => -24,
+            Argon2Error::IncorrectParameter => -25,
+            Argon2Error::IncorrectType => -26,
+            Argon2Error::OutPtrMismatch => -27,
+            Argon2Error::ThreadsTooFew => -28,
+            Argon2Error::ThreadsTooMany => -29,
+            Argon2Error::MissingArgs => -30,
+            Argon2Error::EncodingFail => -31,
+            Argon2Error::DecodingFail => -32,
+            Argon2Error::ThreadFail => -33,
+            Argon2Error::DecodingLengthFail => -34,
+            Argon2Error::VerifyMismatch => -35,
+            Argon2Error::Unknown(code) => *code,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        for code in -35..=-1 {
+            let error = Argon2Error::from_code(code);
+            assert!(!matches!(error, Argon2Error::Unknown(_)), "{code}");
+            assert_eq!(error.code(), code);
+            assert_eq!(Error::Argon2(error).code(), Some(code));
+        }
+        assert_eq!(Argon2Error::from_code(-36), Argon2Error::Unknown(-36));
+
+        assert_eq!(
+            Error::InvalidHashLength(2).code(),
+            Some(Argon2Error::OutputTooShort.code())
+        );
+        assert_eq!(
+            Error::SaltTooShort(4).code(),
+            Some(Argon2Error::SaltTooShort.code())
+        );
+        assert_eq!(Error::Cancelled.code(), None);
+    }
+
+    #[test]
+    fn test_classification() {
+        let parameter = [
+            Error::Argon2(Argon2Error::TimeTooSmall),
+            Error::Argon2(Argon2Error::LanesTooMany),
+            Error::SaltTooShort(4),
+            Error::InvalidHashLength(2),
+            Error::PolicyViolation(String::new()),
+        ];
+        for error in &parameter {
+            assert!(error.is_parameter_error(), "{error}");
+            assert!(!error.is_overload_error(), "{error}");
+        }
+
+        let overload = [
+            Error::TooManyHashes(8),
+            Error::QueueFull,
+            Error::DeadlineExceeded,
+            Error::OutOfMemory,
+            Error::Argon2(Argon2Error::MemoryAllocationError),
+        ];
+        for error in &overload {
+            assert!(error.is_overload_error(), "{error}");
+            assert!(!error.is_parameter_error(), "{error}");
+        }
+
+        assert!(Error::OutOfMemory.is_memory_error());
+        assert!(Error::MemoryLimitExceeded(2, 1).is_memory_error());
+        assert!(!Error::QueueFull.is_memory_error());
+
+        assert!(Error::Argon2(Argon2Error::VerifyMismatch).is_verification_mismatch());
+        assert!(Error::KeystoreMacMismatch.is_verification_mismatch());
+        assert!(!Error::Cancelled.is_verification_mismatch());
+
+        assert!(Error::InvalidPhcString("missing salt").is_invalid_hash());
+        assert!(Error::Argon2(Argon2Error::DecodingFail).is_invalid_hash());
+        assert!(!Error::Argon2(Argon2Error::DecodingFail).is_parameter_error());
     }
 }
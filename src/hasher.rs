@@ -0,0 +1,210 @@
+use super::{Algorithm, Argon2, Error, Version, phc};
+use password_hash::{Decimal, Ident, Output, ParamsString, PasswordHash, PasswordHasher, Salt};
+
+impl Algorithm {
+    fn ident(&self) -> Ident<'static> {
+        Ident::new_unwrap(self.as_str())
+    }
+}
+
+impl TryFrom<Ident<'_>> for Algorithm {
+    type Error = password_hash::Error;
+
+    fn try_from(ident: Ident<'_>) -> password_hash::Result<Self> {
+        match ident.as_str() {
+            "argon2d" => Ok(Algorithm::Argon2d),
+            "argon2i" => Ok(Algorithm::Argon2i),
+            "argon2id" => Ok(Algorithm::Argon2id),
+            _ => Err(password_hash::Error::Algorithm),
+        }
+    }
+}
+
+impl TryFrom<Decimal> for Version {
+    type Error = password_hash::Error;
+
+    fn try_from(version: Decimal) -> password_hash::Result<Self> {
+        match version {
+            0x10 => Ok(Version::V0x10),
+            0x13 => Ok(Version::V0x13),
+            _ => Err(password_hash::Error::Version),
+        }
+    }
+}
+
+fn map_error(error: Error) -> password_hash::Error {
+    match error {
+        Error::InvalidPhcString(_) => password_hash::Error::PhcStringField,
+        _ => password_hash::Error::Crypto,
+    }
+}
+
+/// Reads the parameters back from a [`PasswordHash`]
+///
+/// Hashes keyed with a pepper are rejected, they can only be verified with
+/// [`KeyedArgon2`](crate::pepper::KeyedArgon2).
+impl<'a> TryFrom<&'a PasswordHash<'a>> for Argon2 {
+    type Error = password_hash::Error;
+
+    fn try_from(hash: &'a PasswordHash<'a>) -> password_hash::Result<Self> {
+        let decoded = phc::decode(&hash.to_string()).map_err(map_error)?;
+
+        if decoded.keyid.is_some() {
+            return Err(password_hash::Error::Crypto);
+        }
+
+        Ok(decoded.argon2)
+    }
+}
+
+impl TryFrom<&Argon2> for ParamsString {
+    type Error = password_hash::Error;
+
+    fn try_from(argon2: &Argon2) -> password_hash::Result<Self> {
+        let mut params = ParamsString::new();
+        params.add_decimal("m", argon2.m_cost)?;
+        params.add_decimal("t", argon2.t_cost)?;
+        params.add_decimal("p", argon2.p_cost)?;
+
+        if let Some(threshold) = argon2.prehash {
+            params.add_decimal("ph", threshold)?;
+        }
+
+        if let Some(normalization) = argon2.normalization {
+            params.add_str("norm", normalization.as_str())?;
+        }
+
+        if !argon2.ad.is_empty() {
+            params.add_b64_bytes("data", &argon2.ad)?;
+        }
+
+        Ok(params)
+    }
+}
+
+impl TryFrom<Argon2> for ParamsString {
+    type Error = password_hash::Error;
+
+    fn try_from(argon2: Argon2) -> password_hash::Result<Self> {
+        ParamsString::try_from(&argon2)
+    }
+}
+
+/// Implements the RustCrypto [`PasswordHasher`] trait
+///
+/// `PasswordVerifier` is provided by the blanket implementation of the `password_hash` crate.
+///
+/// The inherent [`Argon2::hash_password`] takes precedence in method call syntax, call the trait
+/// method as `PasswordHasher::hash_password(&argon2, ...)` to get a [`PasswordHash`].
+impl PasswordHasher for Argon2 {
+    type Params = Argon2;
+
+    fn hash_password<'a>(
+        &self,
+        password: &[u8],
+        salt: impl Into<Salt<'a>>,
+    ) -> password_hash::Result<PasswordHash<'a>> {
+        self.hash_password_customized(password, None, None, self.clone(), salt)
+    }
+
+    fn hash_password_customized<'a>(
+        &self,
+        password: &[u8],
+        algorithm: Option<Ident<'a>>,
+        version: Option<Decimal>,
+        params: Argon2,
+        salt: impl Into<Salt<'a>>,
+    ) -> password_hash::Result<PasswordHash<'a>> {
+        let mut argon2 = Argon2 {
+            flags: self.flags,
+            ..params
+        };
+
+        if let Some(algorithm) = algorithm {
+            argon2.algorithm = Algorithm::try_from(algorithm)?;
+        }
+
+        if let Some(version) = version {
+            argon2.version = Version::try_from(version)?;
+        }
+
+        let salt = salt.into();
+        let mut salt_buf = [0u8; Salt::MAX_LENGTH];
+        let salt_bytes = salt.decode_b64(&mut salt_buf)?;
+
+        let hash = argon2
+            .hash_password(password, salt_bytes)
+            .map_err(map_error)?;
+
+        Ok(PasswordHash {
+            algorithm: argon2.algorithm.ident(),
+            version: Some(argon2.version.as_u32()),
+            params: ParamsString::try_from(&argon2)?,
+            salt: Some(salt),
+            hash: Some(Output::new(&hash)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use password_hash::{PasswordVerifier, SaltString};
+
+    #[test]
+    fn test_password_hasher() {
+        let argon2 = Argon2::new(1024, 2, 1).with_ad(b"context");
+        let salt = SaltString::encode_b64(b"somesaltsomesalt").unwrap();
+
+        let hash = PasswordHasher::hash_password(&argon2, b"password", &salt).unwrap();
+        let encoded = argon2
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+        assert_eq!(hash.to_string(), encoded);
+
+        assert!(argon2.verify_password(b"password", &hash).is_ok());
+        assert!(argon2.verify_password(b"wrong", &hash).is_err());
+
+        // The parameters are read from the hash, not from the verifier
+        let parsed = PasswordHash::new(&encoded).unwrap();
+        assert!(
+            Argon2::default()
+                .verify_password(b"password", &parsed)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_password_hasher_prehash() {
+        let argon2 = Argon2::new(1024, 2, 1).with_prehash(1024);
+        let salt = SaltString::encode_b64(b"somesaltsomesalt").unwrap();
+        let password = vec![b'a'; 64 * 1024];
+
+        let hash = PasswordHasher::hash_password(&argon2, &password, &salt).unwrap();
+        let encoded = argon2
+            .hash_password_encoded(&password, b"somesaltsomesalt")
+            .unwrap();
+        assert_eq!(hash.to_string(), encoded);
+
+        assert!(argon2.verify_password(&password, &hash).is_ok());
+        assert!(Argon2::verify_encoded(&password, &hash.to_string()).unwrap());
+        assert!(argon2.verify_password(b"wrong", &hash).is_err());
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn test_password_hasher_normalization() {
+        let argon2 =
+            Argon2::new(1024, 2, 1).with_normalization(crate::normalize::Normalization::Nfc);
+        let salt = SaltString::encode_b64(b"somesaltsomesalt").unwrap();
+
+        let hash = PasswordHasher::hash_password(&argon2, "caf\u{e9}".as_bytes(), &salt).unwrap();
+        assert!(hash.to_string().contains(",norm=nfc"));
+        assert!(
+            argon2
+                .verify_password("cafe\u{301}".as_bytes(), &hash)
+                .is_ok()
+        );
+        assert!(Argon2::verify_encoded("cafe\u{301}", &hash.to_string()).unwrap());
+    }
+}
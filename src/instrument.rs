@@ -0,0 +1,340 @@
+use super::{Argon2, Error};
+
+/// Reports a hash or a verification to the enabled observability features
+///
+/// With the `tracing` feature every operation runs in a `argon2.hash` or `argon2.verify` span at
+/// the debug level, carrying the parameters and, once finished, the duration and the outcome.
+/// Passwords, salts, secrets and hashes are never recorded.
+///
+/// With the `metrics` feature every operation is published through the `metrics` facade:
+///
+/// - `argon2_hash_duration_seconds` - Histogram of the hash durations, labelled by `algorithm`
+/// - `argon2_verifications_total` - Counter of the verifications, labelled by `result`, one of
+///   `success`, `failure` or `error`
+/// - `argon2_allocation_failures_total` - Counter of the hashes that failed to allocate their
+///   working memory
+/// - `argon2_concurrent_hashes` - Gauge of the hashes running at the same time
+pub(crate) struct Operation {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+    #[cfg(any(feature = "tracing", feature = "metrics"))]
+    start: std::time::Instant,
+    #[cfg(feature = "metrics")]
+    algorithm: super::Algorithm,
+}
+
+impl Operation {
+    #[cfg_attr(
+        not(any(feature = "tracing", feature = "metrics")),
+        allow(unused_variables)
+    )]
+    pub(crate) fn hash(argon2: &Argon2) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(
+                "argon2.hash",
+                algorithm = %argon2.algorithm,
+                m_cost = argon2.m_cost,
+                t_cost = argon2.t_cost,
+                p_cost = argon2.p_cost,
+                duration_us = tracing::field::Empty,
+                error = tracing::field::Empty,
+            )
+            .entered(),
+            #[cfg(any(feature = "tracing", feature = "metrics"))]
+            start: std::time::Instant::now(),
+            #[cfg(feature = "metrics")]
+            algorithm: argon2.algorithm,
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "tracing", feature = "metrics")),
+        allow(unused_variables)
+    )]
+    pub(crate) fn verify(argon2: &Argon2) -> Self {
+        Self {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(
+                "argon2.verify",
+                algorithm = %argon2.algorithm,
+                m_cost = argon2.m_cost,
+                t_cost = argon2.t_cost,
+                p_cost = argon2.p_cost,
+                duration_us = tracing::field::Empty,
+                matched = tracing::field::Empty,
+                error = tracing::field::Empty,
+            )
+            .entered(),
+            #[cfg(any(feature = "tracing", feature = "metrics"))]
+            start: std::time::Instant::now(),
+            #[cfg(feature = "metrics")]
+            algorithm: argon2.algorithm,
+        }
+    }
+
+    pub(crate) fn finish_hash(self, result: &Result<(), Error>) {
+        #[cfg(feature = "metrics")]
+        {
+            metrics::histogram!(
+                "argon2_hash_duration_seconds",
+                "algorithm" => self.algorithm.as_str()
+            )
+            .record(self.start.elapsed());
+
+            if matches!(
+                result,
+                Err(Error::OutOfMemory
+                    | Error::Argon2(super::error::Argon2Error::MemoryAllocationError))
+            ) {
+                metrics::counter!("argon2_allocation_failures_total").increment(1);
+            }
+        }
+
+        self.finish(result);
+    }
+
+    pub(crate) fn finish_verify(self, result: &Result<bool, Error>) {
+        #[cfg(feature = "tracing")]
+        if let Ok(matched) = result {
+            self.span.record("matched", matched);
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            let outcome = match result {
+                Ok(true) => "success",
+                Ok(false) => "failure",
+                Err(_) => "error",
+            };
+            metrics::counter!("argon2_verifications_total", "result" => outcome).increment(1);
+        }
+
+        self.finish(result);
+    }
+
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    fn finish<T>(self, result: &Result<T, Error>) {
+        #[cfg(feature = "tracing")]
+        {
+            let duration = u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX);
+            self.span.record("duration_us", duration);
+            if let Err(error) = result {
+                self.span.record("error", tracing::field::display(error));
+            }
+        }
+    }
+}
+
+/// Publishes the number of hashes holding a permit of [`limit`](super::limit)
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn concurrent_hashes(running: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!("argon2_concurrent_hashes").set(running as f64);
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tracing::{
+        Event, Id, Metadata, Subscriber,
+        field::{Field, Visit},
+        span::{Attributes, Record},
+    };
+
+    /// Collects the name and the fields of every span as text
+    #[derive(Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<(String, String)>>>);
+
+    struct Fields<'a>(&'a mut String);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!("{}={value:?} ", field.name()));
+        }
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = String::new();
+            span.record(&mut Fields(&mut fields));
+            let mut spans = self.0.lock().unwrap();
+            spans.push((span.metadata().name().to_string(), fields));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+            values.record(&mut Fields(fields));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_spans() {
+        let collector = Collector::default();
+        let argon2 = Argon2::new(1024, 2, 1);
+        let encoded = tracing::subscriber::with_default(collector.clone(), || {
+            let encoded = argon2
+                .hash_password_encoded("hunter2", b"somesaltsomesalt")
+                .unwrap();
+            assert!(!Argon2::verify_encoded("wrong", &encoded).unwrap());
+            assert!(argon2.hash_password("hunter2", b"short").is_err());
+            encoded
+        });
+
+        let spans = collector.0.lock().unwrap();
+        let names: Vec<_> = spans.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["argon2.hash", "argon2.verify", "argon2.hash", "argon2.hash"]
+        );
+
+        let (_, hash) = &spans[0];
+        for field in [
+            "algorithm=argon2id",
+            "m_cost=1024",
+            "t_cost=2",
+            "p_cost=1",
+            "duration_us=",
+        ] {
+            assert!(hash.contains(field), "{hash}");
+        }
+        assert!(spans[1].1.contains("matched=false"), "{}", spans[1].1);
+        assert!(spans[3].1.contains("error="), "{}", spans[3].1);
+
+        let hash = encoded.rsplit('$').next().unwrap();
+        for (_, fields) in spans.iter() {
+            for secret in ["hunter2", "wrong", "somesalt", hash] {
+                assert!(!fields.contains(secret), "{fields}");
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod metrics_tests {
+    use super::*;
+    use metrics::{
+        Counter, CounterFn, Gauge, GaugeFn, Histogram, HistogramFn, Key, KeyName, Metadata,
+        Recorder, SharedString, Unit,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// Records every update as `name{label=value} update`
+    #[derive(Default)]
+    struct Updates(Arc<Mutex<Vec<String>>>);
+
+    struct Handle {
+        key: String,
+        updates: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Handle {
+        fn push(&self, update: String) {
+            self.updates
+                .lock()
+                .unwrap()
+                .push(format!("{} {update}", self.key));
+        }
+    }
+
+    impl CounterFn for Handle {
+        fn increment(&self, value: u64) {
+            self.push(format!("+{value}"));
+        }
+
+        fn absolute(&self, value: u64) {
+            self.push(format!("={value}"));
+        }
+    }
+
+    impl GaugeFn for Handle {
+        fn increment(&self, value: f64) {
+            self.push(format!("+{value}"));
+        }
+
+        fn decrement(&self, value: f64) {
+            self.push(format!("-{value}"));
+        }
+
+        fn set(&self, value: f64) {
+            self.push(format!("={value}"));
+        }
+    }
+
+    impl HistogramFn for Handle {
+        fn record(&self, _: f64) {
+            self.push("observed".to_string());
+        }
+    }
+
+    impl Updates {
+        fn handle(&self, key: &Key) -> Arc<Handle> {
+            let labels: Vec<_> = key
+                .labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            Arc::new(Handle {
+                key: format!("{}{{{}}}", key.name(), labels.join(",")),
+                updates: self.0.clone(),
+            })
+        }
+    }
+
+    impl Recorder for Updates {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.handle(key))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.handle(key))
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(self.handle(key))
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        let recorder = Updates::default();
+        let argon2 = Argon2::new(1024, 2, 1);
+        metrics::with_local_recorder(&recorder, || {
+            let encoded = argon2
+                .hash_password_encoded("password", b"somesaltsomesalt")
+                .unwrap();
+            assert!(Argon2::verify_encoded("password", &encoded).unwrap());
+            assert!(!Argon2::verify_encoded("wrong", &encoded).unwrap());
+            assert!(Argon2::verify_encoded("password", "$argon2id$").is_err());
+            assert!(argon2.verify_raw("password", b"short", &[0; 64]).is_err());
+        });
+
+        let updates = recorder.0.lock().unwrap();
+        let count = |update: &str| updates.iter().filter(|u| *u == update).count();
+        assert_eq!(
+            count("argon2_hash_duration_seconds{algorithm=argon2id} observed"),
+            4
+        );
+        assert_eq!(count("argon2_verifications_total{result=success} +1"), 1);
+        assert_eq!(count("argon2_verifications_total{result=failure} +1"), 1);
+        // A malformed PHC string fails before a verification starts
+        assert_eq!(count("argon2_verifications_total{result=error} +1"), 1);
+        assert_eq!(count("argon2_concurrent_hashes{} =1"), 3);
+        assert_eq!(count("argon2_concurrent_hashes{} =0"), 3);
+    }
+}
@@ -0,0 +1,308 @@
+use super::{Algorithm, Argon2, Error, Version, output::HashBytes, salt::RECOMMENDED_SALT_LENGTH};
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{Key, Tag, XChaCha20Poly1305, XNonce};
+use serde_json::{Value, json};
+
+/// The version of the keystore format
+pub const KEYSTORE_VERSION: u64 = 1;
+
+/// The only cipher supported
+pub const CIPHER: &str = "xchacha20-poly1305";
+
+/// The length of the key derived with Argon2
+const KEY_LENGTH: u64 = 32;
+
+const NONCE_LENGTH: usize = 24;
+
+/// A secret, such as a private key, encrypted with a key derived from a password
+///
+/// The key is derived with Argon2 and the secret is encrypted with XChaCha20-Poly1305. The
+/// Poly1305 tag is the MAC, decryption fails if the password is wrong or the keystore has been
+/// tampered with.
+///
+/// ## JSON format
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "crypto": {
+///     "kdf": "argon2id",
+///     "kdfparams": { "m_cost": 65536, "t_cost": 3, "p_cost": 1, "version": "0x13", "salt": "<hex>" },
+///     "cipher": "xchacha20-poly1305",
+///     "cipherparams": { "nonce": "<hex>" },
+///     "ciphertext": "<hex>",
+///     "mac": "<hex>"
+///   }
+/// }
+/// ```
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, keystore::Keystore};
+///
+/// let private_key = [0x42u8; 32];
+/// let keystore = Keystore::encrypt(&private_key, "password", &Argon2::new(1024, 2, 1)).unwrap();
+///
+/// let json = keystore.to_json();
+/// let keystore = Keystore::from_json(&json).unwrap();
+///
+/// assert_eq!(keystore.decrypt("password").unwrap().as_bytes(), private_key);
+/// assert!(keystore.decrypt("wrong").is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Keystore {
+    /// The parameters the key was derived with, the `hash_length` is always 32
+    pub argon2: Argon2,
+    pub salt: Vec<u8>,
+    pub nonce: [u8; NONCE_LENGTH],
+    pub ciphertext: Vec<u8>,
+    pub mac: [u8; 16],
+}
+
+impl Keystore {
+    /// Encrypts a secret with a random salt and nonce
+    ///
+    /// ## Arguments
+    ///
+    /// - `secret` - The secret to protect, e.g. a private key
+    /// - `password` - The password to derive the key from
+    /// - `argon2` - The parameters to derive the key with, only the algorithm, version and costs
+    ///   are used
+    pub fn encrypt(
+        secret: &[u8],
+        password: impl AsRef<[u8]>,
+        argon2: &Argon2,
+    ) -> Result<Self, Error> {
+        let mut salt = vec![0u8; RECOMMENDED_SALT_LENGTH];
+        let mut nonce = [0u8; NONCE_LENGTH];
+        getrandom::getrandom(&mut salt).map_err(|e| Error::Random(e.to_string()))?;
+        getrandom::getrandom(&mut nonce).map_err(|e| Error::Random(e.to_string()))?;
+        Self::encrypt_with(secret, password, argon2, salt, nonce)
+    }
+
+    /// Encrypts a secret with the given salt and nonce
+    ///
+    /// The nonce must never be reused with the same password and salt, prefer
+    /// [`Keystore::encrypt`] which generates both.
+    pub fn encrypt_with(
+        secret: &[u8],
+        password: impl AsRef<[u8]>,
+        argon2: &Argon2,
+        salt: impl Into<Vec<u8>>,
+        nonce: [u8; NONCE_LENGTH],
+    ) -> Result<Self, Error> {
+        let argon2 = Argon2::new(argon2.m_cost, argon2.t_cost, argon2.p_cost)
+            .with_algorithm(argon2.algorithm)
+            .with_version(argon2.version)
+            .with_hash_length(KEY_LENGTH);
+        let salt = salt.into();
+        let cipher = cipher(&argon2, password.as_ref(), &salt)?;
+
+        let mut ciphertext = secret.to_vec();
+        let mac = cipher
+            .encrypt_in_place_detached(XNonce::from_slice(&nonce), &[], &mut ciphertext)
+            .map_err(|_| Error::InvalidKeystore("encryption failed".to_string()))?;
+
+        Ok(Self {
+            argon2,
+            salt,
+            nonce,
+            ciphertext,
+            mac: mac.into(),
+        })
+    }
+
+    /// Decrypts the secret
+    ///
+    /// Returns [`Error::KeystoreMacMismatch`] if the password is wrong or the keystore has been
+    /// modified.
+    pub fn decrypt(&self, password: impl AsRef<[u8]>) -> Result<HashBytes, Error> {
+        let cipher = cipher(&self.argon2, password.as_ref(), &self.salt)?;
+
+        // The tag is checked before decrypting, the buffer is only ever plaintext on success
+        let mut secret = self.ciphertext.clone();
+        cipher
+            .decrypt_in_place_detached(
+                XNonce::from_slice(&self.nonce),
+                &[],
+                &mut secret,
+                Tag::from_slice(&self.mac),
+            )
+            .map_err(|_| Error::KeystoreMacMismatch)?;
+
+        Ok(HashBytes::from(secret))
+    }
+
+    /// Serializes the keystore to its JSON format
+    pub fn to_json(&self) -> String {
+        json!({
+            "version": KEYSTORE_VERSION,
+            "crypto": {
+                "kdf": self.argon2.algorithm.as_str(),
+                "kdfparams": {
+                    "m_cost": self.argon2.m_cost,
+                    "t_cost": self.argon2.t_cost,
+                    "p_cost": self.argon2.p_cost,
+                    "version": self.argon2.version.to_string(),
+                    "salt": hex_encode(&self.salt),
+                },
+                "cipher": CIPHER,
+                "cipherparams": { "nonce": hex_encode(&self.nonce) },
+                "ciphertext": hex_encode(&self.ciphertext),
+                "mac": hex_encode(&self.mac),
+            }
+        })
+        .to_string()
+    }
+
+    /// Parses a keystore from its JSON format
+    ///
+    /// Returns [`Error::InvalidKeystore`] if the JSON is malformed, a field is missing or the
+    /// version, KDF or cipher is not supported.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| Error::InvalidKeystore(e.to_string()))?;
+
+        let version = value["version"].as_u64();
+        if version != Some(KEYSTORE_VERSION) {
+            return Err(Error::InvalidKeystore(format!(
+                "unsupported version: {}",
+                value["version"]
+            )));
+        }
+
+        let crypto = &value["crypto"];
+        if crypto["cipher"].as_str() != Some(CIPHER) {
+            return Err(Error::InvalidKeystore(format!(
+                "unsupported cipher: {}",
+                crypto["cipher"]
+            )));
+        }
+
+        let algorithm: Algorithm = string(crypto, "kdf")?
+            .parse()
+            .map_err(|_| Error::InvalidKeystore(format!("unsupported kdf: {}", crypto["kdf"])))?;
+
+        let params = &crypto["kdfparams"];
+        let argon2_version: Version = string(params, "version")?
+            .parse()
+            .map_err(|_| Error::InvalidKeystore("invalid kdfparams.version".to_string()))?;
+
+        let argon2 = Argon2::new(
+            number(params, "m_cost")?,
+            number(params, "t_cost")?,
+            number(params, "p_cost")?,
+        )
+        .with_algorithm(algorithm)
+        .with_version(argon2_version)
+        .with_hash_length(KEY_LENGTH);
+
+        Ok(Self {
+            argon2,
+            salt: hex(params, "salt")?,
+            nonce: array(&crypto["cipherparams"], "nonce")?,
+            ciphertext: hex(crypto, "ciphertext")?,
+            mac: array(crypto, "mac")?,
+        })
+    }
+}
+
+fn cipher(argon2: &Argon2, password: &[u8], salt: &[u8]) -> Result<XChaCha20Poly1305, Error> {
+    let key = argon2
+        .clone()
+        .with_hash_length(KEY_LENGTH)
+        .hash_password_bytes(password, salt)?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn string<'a>(value: &'a Value, field: &str) -> Result<&'a str, Error> {
+    value[field]
+        .as_str()
+        .ok_or_else(|| Error::InvalidKeystore(format!("missing or invalid field: {field}")))
+}
+
+fn number(value: &Value, field: &str) -> Result<u32, Error> {
+    value[field]
+        .as_u64()
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or_else(|| Error::InvalidKeystore(format!("missing or invalid field: {field}")))
+}
+
+fn hex(value: &Value, field: &str) -> Result<Vec<u8>, Error> {
+    hex_decode(string(value, field)?)
+        .ok_or_else(|| Error::InvalidKeystore(format!("invalid hex in field: {field}")))
+}
+
+fn array<const N: usize>(value: &Value, field: &str) -> Result<[u8; N], Error> {
+    hex(value, field)?
+        .try_into()
+        .map_err(|_| Error::InvalidKeystore(format!("invalid length of field: {field}")))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "argon2id-only"))]
+    #[test]
+    fn test_keystore_roundtrip() {
+        let argon2 = Argon2::new(1024, 2, 1).with_algorithm(Algorithm::Argon2i);
+        let secret = [0x42u8; 32];
+        let keystore =
+            Keystore::encrypt_with(&secret, "password", &argon2, [7u8; 16], [9u8; 24]).unwrap();
+
+        let parsed = Keystore::from_json(&keystore.to_json()).unwrap();
+        assert_eq!(parsed, keystore);
+        assert_eq!(parsed.argon2.algorithm, Algorithm::Argon2i);
+        assert_eq!(parsed.decrypt("password").unwrap().as_bytes(), secret);
+
+        assert!(matches!(
+            parsed.decrypt("wrong"),
+            Err(Error::KeystoreMacMismatch)
+        ));
+
+        let mut tampered = parsed.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert!(matches!(
+            tampered.decrypt("password"),
+            Err(Error::KeystoreMacMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_keystore_invalid_json() {
+        let keystore = Keystore::encrypt(b"secret", "password", &Argon2::new(1024, 2, 1)).unwrap();
+        let json = keystore.to_json();
+
+        let invalid = [
+            "not json".to_string(),
+            json.replace("\"version\":1", "\"version\":2"),
+            json.replace(CIPHER, "aes-128-ctr"),
+            json.replace("argon2id", "scrypt"),
+            json.replace("\"m_cost\":1024,", ""),
+        ];
+
+        for json in invalid {
+            assert!(matches!(
+                Keystore::from_json(&json),
+                Err(Error::InvalidKeystore(_))
+            ));
+        }
+    }
+}
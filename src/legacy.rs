@@ -0,0 +1,350 @@
+use super::{Argon2, Error, phc, stored::StoredHash};
+
+/// The prefix of a wrapped legacy hash
+pub const WRAP_PREFIX: &str = "$wrap$";
+
+/// A legacy password hashing scheme whose hashes can be wrapped with Argon2
+pub trait LegacyScheme {
+    /// The name recorded in the wrapped hash, e.g. `bcrypt`
+    fn name(&self) -> &'static str;
+
+    /// Splits a legacy hash into its setting, everything needed to recompute it from a password
+    fn setting<'a>(&self, hash: &'a str) -> Result<&'a str, Error>;
+
+    /// Recomputes the full legacy hash of a password from its setting
+    fn hash(&self, password: &[u8], setting: &str) -> Result<String, Error>;
+}
+
+/// An Argon2 hash over a legacy hash
+///
+/// Wrapping strengthens existing bcrypt or scrypt hashes right away: the legacy hash is hashed
+/// again with Argon2 and only its setting (scheme, cost and salt) is kept, so the weak digest can
+/// be deleted without waiting for each user to log in.
+///
+/// To verify, the legacy hash is recomputed from the password and the setting and then checked
+/// against the Argon2 hash. Once the password is known, rehash it with plain Argon2.
+///
+/// ## Format
+///
+/// `$wrap$<scheme>$<setting in B64>` followed by the PHC string of the Argon2 hash, e.g.
+/// `$wrap$bcrypt$JDJiJDEyJE...$argon2id$v=19$m=65536,t=2,p=1$<salt>$<hash>`.
+///
+/// ## Example
+///
+/// ```
+/// # #[cfg(feature = "bcrypt")] {
+/// use argon2_rs::{Argon2, legacy::{Bcrypt, WrappedHash}};
+///
+/// let legacy = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+/// let wrapped = Argon2::new(1024, 2, 1)
+///     .wrap_legacy(&Bcrypt, legacy, b"somesaltsomesalt")
+///     .unwrap();
+///
+/// let encoded = wrapped.to_string();
+/// let parsed: WrappedHash = encoded.parse().unwrap();
+/// assert!(parsed.verify(&Bcrypt, "U*U").unwrap());
+/// # }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WrappedHash {
+    /// The name of the legacy scheme
+    pub scheme: String,
+    /// The setting of the legacy hash
+    pub setting: String,
+    /// The Argon2 hash over the legacy hash
+    pub inner: StoredHash,
+}
+
+impl WrappedHash {
+    /// Verifies the given password against this hash
+    ///
+    /// Returns [`Error::InvalidLegacyHash`] if `scheme` is not the scheme the hash was wrapped
+    /// with.
+    pub fn verify(
+        &self,
+        scheme: &impl LegacyScheme,
+        password: impl AsRef<[u8]>,
+    ) -> Result<bool, Error> {
+        if scheme.name() != self.scheme {
+            return Err(Error::InvalidLegacyHash(format!(
+                "wrapped with {}, not {}",
+                self.scheme,
+                scheme.name()
+            )));
+        }
+
+        let legacy = scheme.hash(password.as_ref(), &self.setting)?;
+        self.inner.verify(legacy)
+    }
+}
+
+impl Argon2 {
+    /// Wraps an existing legacy hash with Argon2
+    ///
+    /// ## Arguments
+    ///
+    /// - `scheme` - The scheme of the legacy hash
+    /// - `legacy_hash` - The legacy hash, e.g. `$2b$12$...`
+    /// - `salt` - The salt to use for the Argon2 hash
+    pub fn wrap_legacy(
+        &self,
+        scheme: &impl LegacyScheme,
+        legacy_hash: &str,
+        salt: &[u8],
+    ) -> Result<WrappedHash, Error> {
+        let setting = scheme.setting(legacy_hash)?;
+
+        Ok(WrappedHash {
+            scheme: scheme.name().to_string(),
+            setting: setting.to_string(),
+            inner: self.hash_password_stored(legacy_hash, salt)?,
+        })
+    }
+}
+
+impl std::fmt::Display for WrappedHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{WRAP_PREFIX}{}${}{}",
+            self.scheme,
+            phc::b64_encode(self.setting.as_bytes()),
+            self.inner
+        )
+    }
+}
+
+impl std::str::FromStr for WrappedHash {
+    type Err = Error;
+
+    fn from_str(encoded: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidLegacyHash("malformed wrapped hash".to_string());
+
+        let rest = encoded.strip_prefix(WRAP_PREFIX).ok_or_else(invalid)?;
+        let (scheme, rest) = rest.split_once('$').ok_or_else(invalid)?;
+        let (setting, inner) = rest.split_once('$').ok_or_else(invalid)?;
+
+        let setting = phc::b64_decode(setting)
+            .and_then(|setting| String::from_utf8(setting).ok())
+            .ok_or_else(invalid)?;
+
+        if scheme.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            scheme: scheme.to_string(),
+            setting,
+            inner: format!("${inner}").parse()?,
+        })
+    }
+}
+
+/// bcrypt hashes, `$2a$`, `$2b$`, `$2x$` and `$2y$`
+#[cfg(feature = "bcrypt")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bcrypt;
+
+#[cfg(feature = "bcrypt")]
+impl LegacyScheme for Bcrypt {
+    fn name(&self) -> &'static str {
+        "bcrypt"
+    }
+
+    /// The setting is `$2b$<cost>$<salt>`, the first 29 characters of the hash
+    fn setting<'a>(&self, hash: &'a str) -> Result<&'a str, Error> {
+        hash.parse::<bcrypt::HashParts>()
+            .map_err(|e| Error::InvalidLegacyHash(e.to_string()))?;
+        Ok(&hash[..29])
+    }
+
+    fn hash(&self, password: &[u8], setting: &str) -> Result<String, Error> {
+        use base64::Engine;
+
+        let invalid = || Error::InvalidLegacyHash(format!("invalid bcrypt setting: {setting}"));
+
+        let mut parts = setting.split('$');
+        let (Some(""), Some(prefix), Some(cost), Some(salt), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return Err(invalid());
+        };
+
+        let version = match prefix {
+            "2a" => bcrypt::Version::TwoA,
+            "2b" => bcrypt::Version::TwoB,
+            "2x" => bcrypt::Version::TwoX,
+            "2y" => bcrypt::Version::TwoY,
+            _ => return Err(invalid()),
+        };
+
+        let cost = cost.parse().map_err(|_| invalid())?;
+        let salt: [u8; 16] = bcrypt::BASE_64
+            .decode(salt)
+            .ok()
+            .and_then(|salt| salt.try_into().ok())
+            .ok_or_else(invalid)?;
+
+        let parts = bcrypt::hash_with_salt(password, cost, salt)
+            .map_err(|e| Error::InvalidLegacyHash(e.to_string()))?;
+        Ok(parts.format_for_version(version))
+    }
+}
+
+/// scrypt hashes in the PHC string format, `$scrypt$ln=17,r=8,p=1$<salt>$<hash>`
+///
+/// Only the default 32 byte output is supported.
+#[cfg(feature = "scrypt")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Scrypt;
+
+#[cfg(feature = "scrypt")]
+impl LegacyScheme for Scrypt {
+    fn name(&self) -> &'static str {
+        "scrypt"
+    }
+
+    /// The setting is the PHC string without the hash
+    fn setting<'a>(&self, hash: &'a str) -> Result<&'a str, Error> {
+        let parsed = scrypt::password_hash::PasswordHash::new(hash)
+            .map_err(|e| Error::InvalidLegacyHash(e.to_string()))?;
+
+        if parsed.algorithm != scrypt::ALG_ID {
+            return Err(Error::InvalidLegacyHash(format!(
+                "not a scrypt hash: {}",
+                parsed.algorithm
+            )));
+        }
+
+        match parsed.hash {
+            Some(output) if output.len() == scrypt::Params::RECOMMENDED_LEN => {}
+            _ => {
+                return Err(Error::InvalidLegacyHash(
+                    "only 32 byte scrypt hashes are supported".to_string(),
+                ));
+            }
+        }
+
+        let end = hash
+            .rfind('$')
+            .ok_or_else(|| Error::InvalidLegacyHash("malformed scrypt hash".to_string()))?;
+        Ok(&hash[..end])
+    }
+
+    fn hash(&self, password: &[u8], setting: &str) -> Result<String, Error> {
+        use scrypt::password_hash::{PasswordHash, PasswordHasher};
+
+        let invalid = |e: scrypt::password_hash::Error| Error::InvalidLegacyHash(e.to_string());
+
+        let parsed = PasswordHash::new(setting).map_err(invalid)?;
+        let salt = parsed
+            .salt
+            .ok_or_else(|| Error::InvalidLegacyHash("scrypt setting has no salt".to_string()))?;
+        let params = scrypt::Params::try_from(&parsed).map_err(invalid)?;
+
+        let hash = scrypt::Scrypt
+            .hash_password_customized(password, None, None, params, salt)
+            .map_err(invalid)?;
+        Ok(hash.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scheme whose hash is the setting followed by the password, only for testing the format
+    struct Plain;
+
+    impl LegacyScheme for Plain {
+        fn name(&self) -> &'static str {
+            "plain"
+        }
+
+        fn setting<'a>(&self, hash: &'a str) -> Result<&'a str, Error> {
+            Ok(&hash[..hash.find(':').unwrap() + 1])
+        }
+
+        fn hash(&self, password: &[u8], setting: &str) -> Result<String, Error> {
+            Ok(format!("{setting}{}", String::from_utf8_lossy(password)))
+        }
+    }
+
+    #[test]
+    fn test_wrapped_hash_format() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let wrapped = argon2
+            .wrap_legacy(&Plain, "$p$salt:password", b"somesaltsomesalt")
+            .unwrap();
+        assert_eq!(wrapped.setting, "$p$salt:");
+
+        let encoded = wrapped.to_string();
+        assert!(encoded.starts_with("$wrap$plain$"));
+        assert!(encoded.contains("$argon2id$v=19$m=1024,t=2,p=1$"));
+        assert!(!encoded.contains("password"));
+
+        let parsed: WrappedHash = encoded.parse().unwrap();
+        assert_eq!(parsed, wrapped);
+        assert!(parsed.verify(&Plain, "password").unwrap());
+        assert!(!parsed.verify(&Plain, "wrong").unwrap());
+
+        assert!(
+            "$argon2id$v=19$m=1024,t=2,p=1$c2FsdA$aGFzaA"
+                .parse::<WrappedHash>()
+                .is_err()
+        );
+        assert!("$wrap$plain$!!$argon2id".parse::<WrappedHash>().is_err());
+    }
+
+    #[cfg(feature = "bcrypt")]
+    #[test]
+    fn test_wrap_bcrypt() {
+        // From the OpenBSD / John the Ripper test vectors
+        let legacy = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+        assert_eq!(
+            Bcrypt
+                .hash(b"U*U", Bcrypt.setting(legacy).unwrap())
+                .unwrap(),
+            legacy
+        );
+
+        let wrapped = Argon2::new(1024, 2, 1)
+            .wrap_legacy(&Bcrypt, legacy, b"somesaltsomesalt")
+            .unwrap();
+        let parsed: WrappedHash = wrapped.to_string().parse().unwrap();
+        assert!(parsed.verify(&Bcrypt, "U*U").unwrap());
+        assert!(!parsed.verify(&Bcrypt, "U*V").unwrap());
+
+        assert!(Bcrypt.setting("$2a$05$short").is_err());
+    }
+
+    #[cfg(feature = "scrypt")]
+    #[test]
+    fn test_wrap_scrypt() {
+        use scrypt::password_hash::{PasswordHasher, SaltString};
+
+        let salt = SaltString::encode_b64(b"somesaltsomesalt").unwrap();
+        let params = scrypt::Params::new(10, 8, 1, 32).unwrap();
+        let legacy = scrypt::Scrypt
+            .hash_password_customized(b"password", None, None, params, &salt)
+            .unwrap()
+            .to_string();
+
+        let setting = Scrypt.setting(&legacy).unwrap();
+        assert_eq!(Scrypt.hash(b"password", setting).unwrap(), legacy);
+
+        let wrapped = Argon2::new(1024, 2, 1)
+            .wrap_legacy(&Scrypt, &legacy, b"somesaltsomesalt")
+            .unwrap();
+        let parsed: WrappedHash = wrapped.to_string().parse().unwrap();
+        assert!(parsed.verify(&Scrypt, "password").unwrap());
+        assert!(!parsed.verify(&Scrypt, "wrong").unwrap());
+
+        #[cfg(feature = "bcrypt")]
+        assert!(parsed.verify(&Bcrypt, "password").is_err());
+    }
+}
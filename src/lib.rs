@@ -1,15 +1,86 @@
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub mod allocator;
+pub mod attack;
+mod backend;
+#[cfg(feature = "rayon")]
+mod batch;
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub mod bench;
+pub mod builder;
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub mod cancel;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod cgroup;
+mod config;
+pub mod crypt;
+pub mod django;
 pub mod error;
+#[cfg(feature = "password-hash")]
+mod hasher;
+mod instrument;
+#[cfg(feature = "keystore")]
+pub mod keystore;
+pub mod legacy;
+pub mod limit;
+#[cfg(feature = "uniffi")]
+mod mobile;
+pub mod normalize;
+pub mod output;
+pub mod password;
+pub mod pepper;
+mod phc;
+pub mod php;
+pub mod policy;
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+pub mod prealloc;
+mod prehash;
+#[cfg(all(feature = "c", not(target_arch = "wasm32")))]
+mod progress;
+#[cfg(feature = "binary-record")]
+mod record;
+pub mod registry;
+pub mod runtime;
+#[cfg(feature = "rustcrypto")]
+mod rustcrypto;
+pub mod salt;
+#[cfg(all(feature = "sandbox", feature = "c", unix))]
+pub mod sandbox;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod simd;
+pub mod sodium;
+#[cfg(any(feature = "sqlx", feature = "diesel"))]
+mod sql;
+pub mod stored;
+pub mod strength;
+mod tune;
+#[cfg(feature = "vectors")]
+pub mod vectors;
 use error::*;
+use output::HashBytes;
+#[cfg(feature = "getrandom")]
+use salt::Salt;
 
-use argon2_sys::{ARGON2_DEFAULT_FLAGS, argon2_context, argon2_ctx};
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
 
-#[cfg(feature = "zeroize")]
-use zeroize::Zeroize;
+use backend::{
+    ARGON2_DEFAULT_FLAGS, ARGON2_FLAG_CLEAR_PASSWORD, ARGON2_FLAG_CLEAR_SECRET, ARGON2_MAX_OUTLEN,
+    ARGON2_MIN_OUTLEN,
+};
 
 pub const RECOMMENDED_HASH_LENGTH: u64 = 64;
 
+/// The shortest hash in bytes the implementation can derive
+pub const MIN_HASH_LENGTH: u64 = ARGON2_MIN_OUTLEN;
+
+/// The longest hash in bytes the implementation can derive
+pub const MAX_HASH_LENGTH: u64 = ARGON2_MAX_OUTLEN;
+
 /// Argon2 primitive type: variants of the algorithm.
 #[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Default, Ord)]
 pub enum Algorithm {
     /// Optimizes against GPU cracking attacks but vulnerable to side-channels.
@@ -38,6 +109,7 @@ pub enum Algorithm {
 
 /// Version of the algorithm.
 #[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum Version {
@@ -53,6 +125,61 @@ pub enum Version {
     V0x13 = 0x13,
 }
 
+/// Flags passed to the C implementation
+///
+/// Flags do not affect the resulting hash.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, Flags};
+///
+/// let argon2 = Argon2::new(1024, 2, 1).with_flags(Flags::CLEAR_PASSWORD | Flags::CLEAR_SECRET);
+/// ```
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Flags(u32);
+
+impl Flags {
+    /// No flags set (*default*)
+    pub const DEFAULT: Flags = Flags(ARGON2_DEFAULT_FLAGS);
+
+    /// Wipe the password as soon as it has been pre-hashed
+    ///
+    /// The C implementation wipes an internal copy of the password, the buffer passed by the caller
+    /// is never written to.
+    pub const CLEAR_PASSWORD: Flags = Flags(ARGON2_FLAG_CLEAR_PASSWORD);
+
+    /// Wipe the secret (pepper) as soon as it has been pre-hashed
+    ///
+    /// As with [`Flags::CLEAR_PASSWORD`] only an internal copy of the secret is wiped.
+    pub const CLEAR_SECRET: Flags = Flags(ARGON2_FLAG_CLEAR_SECRET);
+
+    /// The raw value of the flags as passed to the C implementation
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all the flags in `other` are set
+    pub fn contains(&self, other: Flags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for Flags {
+    type Output = Flags;
+
+    fn bitor(self, rhs: Flags) -> Flags {
+        Flags(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Flags {
+    fn bitor_assign(&mut self, rhs: Flags) {
+        self.0 |= rhs.0;
+    }
+}
+
 /// Argon2 instance
 ///
 /// # Parameters
@@ -63,6 +190,10 @@ pub enum Version {
 /// - `hash_length` - The length of the hash in bytes
 /// - `algorithm` - The algorithm to use
 /// - `version` - The version of the algorithm to use
+/// - `ad` - Optional associated data the hash is bound to
+/// - `flags` - Flags passed to the C implementation, they do not affect the hash
+/// - `prehash` - Optional length above which passwords are pre-hashed with BLAKE2b
+/// - `normalization` - Optional Unicode form passwords are normalized to
 ///
 /// By default it will use the `Argon2id` with a `64 byte` hash length (maximum).
 ///
@@ -88,7 +219,7 @@ pub enum Version {
 /// For example if the hash takes 10 seconds to compute with `t_cost` set to `8` and you increase it to `16` it will take roughly twice the time.
 ///
 /// ### `p_cost`
-/// 
+///
 /// For max security the `p_cost` should be set to `1`.
 ///
 /// Increasing the `p_cost` will decrease the time it takes to compute the hash linearly.
@@ -107,8 +238,14 @@ pub enum Version {
 /// - `Argon2::balanced()`
 /// - `Argon2::slow()`
 /// - `Argon2::very_slow()`
+/// - `Argon2::rfc9106_high_memory()`, `Argon2::rfc9106_low_memory()` - The options of RFC 9106
+/// - `Argon2::owasp()` - The minimum of the OWASP Password Storage Cheat Sheet
+/// - `Argon2::interactive()`, `Argon2::moderate()`, `Argon2::sensitive()` - The limits of libsodium
+///
+/// `Argon2::default()` is the second recommended option of RFC 9106, 64 MiB of memory, 3 passes
+/// and 4 lanes.
 #[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
-#[derive(Default, Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Argon2 {
     pub m_cost: u32,
     pub t_cost: u32,
@@ -118,6 +255,45 @@ pub struct Argon2 {
     pub algorithm: Algorithm,
     /// By default we use the version 0x13
     pub version: Version,
+    /// Associated data the hash is bound to, empty by default
+    pub ad: Vec<u8>,
+    /// By default no flags are set
+    pub flags: Flags,
+    /// The number of threads to compute the lanes with, one per lane (`p_cost`) by default
+    ///
+    /// The hash does not depend on it, a hash created with 4 lanes can be computed by fewer
+    /// threads on a constrained machine.
+    pub threads: Option<u32>,
+    /// Passwords longer than this many bytes are pre-hashed with BLAKE2b, `None` by default
+    ///
+    /// See [`Argon2::with_prehash`], it is stored in the `ph` parameter of PHC strings.
+    pub prehash: Option<u32>,
+    /// The Unicode form passwords are normalized to before hashing, `None` by default
+    ///
+    /// See [`Argon2::with_normalization`], it is stored in the `norm` parameter of PHC strings.
+    pub normalization: Option<normalize::Normalization>,
+}
+
+/// The second recommended option of RFC 9106 with a [`RECOMMENDED_HASH_LENGTH`] hash
+///
+/// Argon2id with 64 MiB of memory, 3 passes and 4 lanes, so struct updates with
+/// `..Default::default()` always hash.
+impl Default for Argon2 {
+    fn default() -> Self {
+        Self {
+            m_cost: 64 * 1024,
+            t_cost: 3,
+            p_cost: 4,
+            hash_length: RECOMMENDED_HASH_LENGTH,
+            algorithm: Algorithm::default(),
+            version: Version::default(),
+            ad: Vec::new(),
+            flags: Flags::default(),
+            threads: None,
+            prehash: None,
+            normalization: None,
+        }
+    }
 }
 
 impl Argon2 {
@@ -140,6 +316,17 @@ impl Argon2 {
         }
     }
 
+    /// Create a new Argon2 instance from the parameters of a PHC string
+    ///
+    /// The `hash_length` is taken from the length of the encoded hash.
+    ///
+    /// ## Arguments
+    ///
+    /// - `encoded` - The hash in the PHC string format
+    pub fn from_encoded(encoded: &str) -> Result<Self, Error> {
+        Ok(phc::decode(encoded)?.argon2)
+    }
+
     pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
         self.algorithm = algorithm;
         self
@@ -155,54 +342,502 @@ impl Argon2 {
         self
     }
 
+    /// Sets the flags passed to the C implementation
+    ///
+    /// No flag makes the C implementation write to the password or the secret of the caller, the
+    /// clear flags wipe an internal copy instead.
+    pub fn with_flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Computes the lanes with the given number of threads instead of one per lane
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 4);
+    /// let hash = argon2.hash_password("password", b"somesaltsomesalt").unwrap();
+    ///
+    /// let single = argon2.with_threads(1);
+    /// assert_eq!(single.hash_password("password", b"somesaltsomesalt").unwrap(), hash);
+    /// ```
+    pub fn with_threads(mut self, threads: u32) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// The number of threads the lanes are computed with, never more than `p_cost`
+    pub fn threads(&self) -> u32 {
+        self.threads
+            .map_or(self.p_cost, |threads| threads.min(self.p_cost))
+    }
+
+    /// Binds the hash to the given associated data (e.g. a user ID or protocol version)
+    ///
+    /// The same data must be provided to verify the hash, it is stored in the `data` parameter of PHC strings.
+    pub fn with_ad(mut self, ad: &[u8]) -> Self {
+        self.ad = ad.to_vec();
+        self
+    }
+
     /// Hashes the given password
     ///
     /// ## Arguments
     ///
-    /// - `password` - The password to hash
+    /// - `password` - The password to hash, either a string or raw bytes
     /// - `salt` - The salt to use for hashing
     ///
     ///
     /// ## Returns
     ///
     /// The hash of the password in its raw byte form
-    pub fn hash_password(&self, password: &str, mut salt: Vec<u8>) -> Result<Vec<u8>, Error> {
-        let mut hash_buffer = vec![0u8; self.hash_length as usize];
-
-        let mut context = argon2_context {
-            out: hash_buffer.as_mut_ptr(),
-            outlen: self.hash_length as u32,
-            pwd: password.as_bytes().as_ptr() as *mut u8,
-            pwdlen: password.len() as u32,
-            salt: salt.as_mut_ptr(),
-            saltlen: salt.len() as u32,
-            secret: std::ptr::null_mut(),
-            secretlen: 0,
-            ad: std::ptr::null_mut(),
-            adlen: 0,
-            t_cost: self.t_cost,
-            m_cost: self.m_cost,
-            lanes: self.p_cost,
-            threads: self.p_cost,
-            version: self.version as u32,
-            allocate_cbk: None,
-            free_cbk: None,
-            flags: ARGON2_DEFAULT_FLAGS,
+    pub fn hash_password(&self, password: impl AsRef<[u8]>, salt: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut hash_buffer = output_buffer(self.hash_len()?)?;
+        self.hash_password_into(password, salt, &mut hash_buffer)?;
+        Ok(hash_buffer)
+    }
+
+    /// Hashes the given password into a [`HashBytes`] that is wiped from memory when dropped
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    pub fn hash_password_bytes(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<HashBytes, Error> {
+        self.hash_password(password, salt).map(HashBytes::from)
+    }
+
+    /// Hashes the given password with a newly generated random salt
+    ///
+    /// The salt is [`salt::RECOMMENDED_SALT_LENGTH`] bytes long and generated with the OS CSPRNG.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    ///
+    /// ## Returns
+    ///
+    /// The generated salt and the hash of the password in its raw byte form
+    #[cfg(feature = "getrandom")]
+    pub fn hash_password_detached(
+        &self,
+        password: impl AsRef<[u8]>,
+    ) -> Result<(Salt, Vec<u8>), Error> {
+        let salt = Salt::generate()?;
+        let hash = self.hash_password(password, &salt)?;
+        Ok((salt, hash))
+    }
+
+    /// Hashes the given password into a caller-provided buffer
+    ///
+    /// The length of the hash is the length of `out`, the `hash_length` of this instance is ignored.
+    /// `out` is zeroed if hashing fails, it never holds a partial hash.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `out` - The buffer to write the hash into
+    pub fn hash_password_into(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), Error> {
+        self.hash_with_secret(password.as_ref(), salt, &[], out)
+    }
+
+    /// Hashes the given password keyed with a secret (pepper) into `out`
+    pub(crate) fn hash_with_secret(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+        secret: &[u8],
+        out: &mut [u8],
+    ) -> Result<(), Error> {
+        backend::hash(self, password, salt, secret, out)
+    }
+
+    /// Hashes the given password with both the C and the Rust implementation and compares them
+    ///
+    /// With the `pure-rust` feature hashes are computed by the RustCrypto implementation, this
+    /// checks it against the C implementation, e.g. in a startup self-test.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    #[cfg(all(feature = "c", feature = "pure-rust", not(target_arch = "wasm32")))]
+    pub fn cross_check(&self, password: impl AsRef<[u8]>, salt: &[u8]) -> Result<bool, Error> {
+        let mut c = output_buffer(self.hash_len()?)?;
+        let mut rust = output_buffer(self.hash_len()?)?;
+        backend::hash_c(self, password.as_ref(), salt, &[], &mut c)?;
+        backend::hash_rust(self, password.as_ref(), salt, &[], &mut rust)?;
+        Ok(c == rust)
+    }
+
+    /// Hashes the given password into a fixed-size array
+    ///
+    /// The length of the hash is `N`, the `hash_length` of this instance is ignored.
+    /// `N` is checked at compile time against the output length limits of Argon2.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 1);
+    /// let key: [u8; 32] = argon2.hash_password_array("password", b"somesaltsomesalt").unwrap();
+    /// ```
+    ///
+    /// Lengths outside of the supported range are rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// # use argon2_rs::Argon2;
+    /// # let argon2 = Argon2::new(1024, 2, 1);
+    /// let key: [u8; 2] = argon2.hash_password_array("password", b"somesaltsomesalt").unwrap();
+    /// ```
+    pub fn hash_password_array<const N: usize>(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<[u8; N], Error> {
+        const {
+            assert!(
+                N as u64 >= ARGON2_MIN_OUTLEN && N as u64 <= ARGON2_MAX_OUTLEN,
+                "hash length is out of the range supported by Argon2"
+            )
         };
 
-        let code = unsafe { argon2_ctx(&mut context, self.algorithm as u32) };
+        let mut out = [0u8; N];
+        self.hash_password_into(password, salt, &mut out)?;
+        Ok(out)
+    }
 
-        #[cfg(feature = "zeroize")]
-        salt.zeroize();
+    /// Hashes the given password and encodes the result as a PHC string
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash
+    /// - `salt` - The salt to use for hashing
+    ///
+    /// ## Returns
+    ///
+    /// The hash in the PHC string format `$argon2id$v=19$m=...,t=...,p=...$salt$hash`
+    pub fn hash_password_encoded(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<String, Error> {
+        let hash = self.hash_password(password, salt)?;
+        Ok(phc::encode(self, None, salt, &hash))
+    }
 
-        if code != 0 {
-            return Err(Error::Argon2(map_argon2_error(code)));
+    /// Checks whether a stored hash should be recomputed with the parameters of this instance
+    ///
+    /// Returns `true` if any of the parameters embedded in the PHC string (including the algorithm,
+    /// version and hash length) differ from this instance.
+    ///
+    /// ## Arguments
+    ///
+    /// - `encoded` - The hash in the PHC string format
+    pub fn needs_rehash(&self, encoded: &str) -> Result<bool, Error> {
+        let stored = Self::from_encoded(encoded)?;
+        Ok(!stored.same_parameters(self))
+    }
+
+    /// Whether both instances compute the same hash
+    ///
+    /// The flags and the number of threads are left out, they do not change the hash and are not
+    /// stored in PHC strings.
+    pub(crate) fn same_parameters(&self, other: &Argon2) -> bool {
+        self.m_cost == other.m_cost
+            && self.t_cost == other.t_cost
+            && self.p_cost == other.p_cost
+            && self.hash_length == other.hash_length
+            && self.algorithm == other.algorithm
+            && self.version == other.version
+            && self.ad == other.ad
+            && self.prehash == other.prehash
+            && self.normalization == other.normalization
+    }
+
+    /// Whether a hash with these parameters falls short of `target`
+    ///
+    /// That is a different algorithm or version, or less memory, fewer passes, fewer lanes or a
+    /// shorter output. Hashes stronger than `target` are left as they are.
+    #[cfg(feature = "getrandom")]
+    pub(crate) fn below(&self, target: &Argon2) -> bool {
+        self.algorithm != target.algorithm
+            || self.version != target.version
+            || self.m_cost < target.m_cost
+            || self.t_cost < target.t_cost
+            || self.p_cost < target.p_cost
+            || self.hash_length < target.hash_length
+    }
+
+    /// Verifies the given password against a raw hash
+    ///
+    /// The hash is recomputed with the parameters of this instance and compared in constant time.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to verify
+    /// - `salt` - The salt the hash was created with
+    /// - `expected_hash` - The hash in its raw byte form
+    ///
+    /// ## Returns
+    ///
+    /// `true` if the password matches the hash
+    pub fn verify_raw(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        expected_hash: &[u8],
+    ) -> Result<bool, Error> {
+        let operation = instrument::Operation::verify(self);
+        let result = self
+            .hash_password_bytes(password, salt)
+            .map(|hash| hash == *expected_hash);
+        operation.finish_verify(&result);
+        result
+    }
+
+    /// Verifies the given password against a PHC string
+    ///
+    /// The parameters and the salt are taken from the encoded string, so the hash can be verified
+    /// regardless of the parameters it was created with.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to verify
+    /// - `encoded` - The hash in the PHC string format
+    ///
+    /// ## Returns
+    ///
+    /// `true` if the password matches the hash
+    pub fn verify_encoded(password: impl AsRef<[u8]>, encoded: &str) -> Result<bool, Error> {
+        let decoded = phc::decode(encoded)?;
+
+        if decoded.keyid.is_some() {
+            return Err(Error::PepperRequired);
         }
 
-        Ok(hash_buffer)
+        decoded
+            .argon2
+            .verify_raw(password, &decoded.salt, &decoded.hash)
+    }
+
+    /// Performs a full-cost verification that always fails
+    ///
+    /// Call this when the user does not exist so that the response takes the same time as a real
+    /// verification, preventing user enumeration through timing.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password that was submitted
+    ///
+    /// ## Returns
+    ///
+    /// Always `false`, unless the parameters of this instance are invalid
+    pub fn verify_dummy(&self, password: impl AsRef<[u8]>) -> Result<bool, Error> {
+        let salt = [0u8; salt::RECOMMENDED_SALT_LENGTH];
+        let hash = self.hash_password_bytes(password, &salt)?;
+        std::hint::black_box(hash);
+        Ok(false)
+    }
+
+    /// Verifies the given password against a stored hash, or performs a dummy verification if there is none
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to verify
+    /// - `stored` - The hash in the PHC string format, `None` if the user does not exist
+    ///
+    /// ## Returns
+    ///
+    /// `true` if a hash is stored and the password matches it
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 1);
+    /// assert!(!argon2.verify_or_dummy("password", None).unwrap());
+    /// ```
+    pub fn verify_or_dummy(
+        &self,
+        password: impl AsRef<[u8]>,
+        stored: Option<&str>,
+    ) -> Result<bool, Error> {
+        match stored {
+            Some(encoded) => Self::verify_encoded(password, encoded),
+            None => self.verify_dummy(password),
+        }
+    }
+
+    /// Verifies the given password against a PHC string and upgrades the hash if needed
+    ///
+    /// If the password matches and the stored hash was created with a different algorithm or
+    /// version, or with less memory, fewer passes, fewer lanes or a shorter output than this
+    /// instance, the password is hashed again with the parameters of this instance and a newly
+    /// generated salt. Hashes stronger than this instance are kept.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to verify
+    /// - `stored` - The hash in the PHC string format
+    ///
+    /// ## Returns
+    ///
+    /// Whether the password matches and, if the hash was upgraded, the new PHC string to store
+    #[cfg(feature = "getrandom")]
+    pub fn verify_and_upgrade(
+        &self,
+        password: impl AsRef<[u8]>,
+        stored: &str,
+    ) -> Result<(bool, Option<String>), Error> {
+        let password = password.as_ref();
+        let decoded = phc::decode(stored)?;
+
+        if decoded.keyid.is_some() {
+            return Err(Error::PepperRequired);
+        }
+
+        let matches = decoded
+            .argon2
+            .verify_raw(password, &decoded.salt, &decoded.hash)?;
+
+        if !matches {
+            return Ok((false, None));
+        }
+
+        if !decoded.argon2.below(self) {
+            return Ok((true, None));
+        }
+
+        let upgraded = self.hash_password_encoded(password, &Salt::generate()?)?;
+        Ok((true, Some(upgraded)))
+    }
+}
+
+/// Integration with the `secrecy` crate
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::Argon2;
+/// use secrecy::{ExposeSecret, SecretString};
+///
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let password = SecretString::new("password".to_string());
+/// let key = argon2.hash_password_secret(&password, b"somesaltsomesalt").unwrap();
+/// assert_eq!(key.expose_secret().len(), 64);
+/// ```
+#[cfg(feature = "secrecy")]
+impl Argon2 {
+    /// Hashes a password kept in a [`secrecy::SecretString`] or [`secrecy::SecretVec`]
+    ///
+    /// ## Returns
+    ///
+    /// The hash of the password wrapped in a [`secrecy::SecretVec`]
+    pub fn hash_password_secret<S: AsRef<[u8]>>(
+        &self,
+        password: &impl secrecy::ExposeSecret<S>,
+        salt: &[u8],
+    ) -> Result<secrecy::SecretVec<u8>, Error> {
+        let hash = self.hash_password(password.expose_secret(), salt)?;
+        Ok(secrecy::SecretVec::new(hash))
+    }
+
+    /// Same as [`Argon2::verify_raw`] for a password kept in a secret
+    pub fn verify_raw_secret<S: AsRef<[u8]>>(
+        &self,
+        password: &impl secrecy::ExposeSecret<S>,
+        salt: &[u8],
+        expected_hash: &[u8],
+    ) -> Result<bool, Error> {
+        self.verify_raw(password.expose_secret(), salt, expected_hash)
+    }
+
+    /// Same as [`Argon2::verify_encoded`] for a password kept in a secret
+    pub fn verify_encoded_secret<S: AsRef<[u8]>>(
+        password: &impl secrecy::ExposeSecret<S>,
+        encoded: &str,
+    ) -> Result<bool, Error> {
+        Self::verify_encoded(password.expose_secret(), encoded)
     }
 }
 
+/// Converts a size in bytes to `usize`, [`Error::MemoryTooLargeForPlatform`] if it does not fit in
+/// the address space of this platform, as on 32-bit targets
+pub(crate) fn platform_len(bytes: u64) -> Result<usize, Error> {
+    usize::try_from(bytes)
+        .ok()
+        .filter(|&len| len <= isize::MAX as usize)
+        .ok_or(Error::MemoryTooLargeForPlatform(bytes))
+}
+
+impl Argon2 {
+    /// The `hash_length` as a `usize`, [`Error::InvalidHashLength`] if it is out of range
+    pub(crate) fn hash_len(&self) -> Result<usize, Error> {
+        check_hash_length(self.hash_length)?;
+        platform_len(self.hash_length)
+    }
+}
+
+/// Rejects hash lengths outside [`MIN_HASH_LENGTH`] and [`MAX_HASH_LENGTH`]
+///
+/// The length is passed to the implementation as a `u32`, a longer one would be truncated.
+pub(crate) fn check_hash_length(hash_length: u64) -> Result<(), Error> {
+    if !(MIN_HASH_LENGTH..=MAX_HASH_LENGTH).contains(&hash_length) {
+        return Err(Error::InvalidHashLength(hash_length));
+    }
+    Ok(())
+}
+
+/// A zeroed buffer of `len` bytes, [`Error::OutOfMemory`] instead of aborting if it cannot be
+/// allocated
+pub(crate) fn output_buffer(len: usize) -> Result<Vec<u8>, Error> {
+    let mut buffer = Vec::new();
+    buffer
+        .try_reserve_exact(len)
+        .map_err(|_| Error::OutOfMemory)?;
+    buffer.resize(len, 0);
+    Ok(buffer)
+}
+
+/// Compares two byte slices in constant time with respect to their contents
+#[cfg(feature = "subtle")]
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    use subtle::ConstantTimeEq;
+    a.ct_eq(b).into()
+}
+
+/// Compares two byte slices in constant time with respect to their contents
+#[cfg(not(feature = "subtle"))]
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
 // Argon2 Presets
 impl Argon2 {
     pub fn very_fast() -> Self {
@@ -227,7 +862,7 @@ impl Argon2 {
 
     pub fn balanced() -> Self {
         Self {
-            m_cost: 1024_000,
+            m_cost: 1_024_000,
             t_cost: 8,
             p_cost: 1,
             hash_length: RECOMMENDED_HASH_LENGTH,
@@ -237,7 +872,7 @@ impl Argon2 {
 
     pub fn slow() -> Self {
         Self {
-            m_cost: 2048_000,
+            m_cost: 2_048_000,
             t_cost: 8,
             p_cost: 1,
             hash_length: RECOMMENDED_HASH_LENGTH,
@@ -247,13 +882,68 @@ impl Argon2 {
 
     pub fn very_slow() -> Self {
         Self {
-            m_cost: 3072_000,
+            m_cost: 3_072_000,
             t_cost: 8,
             p_cost: 1,
             hash_length: RECOMMENDED_HASH_LENGTH,
             ..Default::default()
         }
     }
+
+    /// The first recommended option of RFC 9106, for machines with memory to spare
+    ///
+    /// Argon2id with 2 GiB of memory, 1 pass, 4 lanes and a 32-byte tag.
+    pub fn rfc9106_high_memory() -> Self {
+        Self {
+            m_cost: 2 * 1024 * 1024,
+            t_cost: 1,
+            p_cost: 4,
+            hash_length: 32,
+            ..Default::default()
+        }
+    }
+
+    /// The second recommended option of RFC 9106, for memory-constrained machines
+    ///
+    /// Argon2id with 64 MiB of memory, 3 passes, 4 lanes and a 32-byte tag. [`Argon2::default`]
+    /// has the same costs with a [`RECOMMENDED_HASH_LENGTH`] hash.
+    pub fn rfc9106_low_memory() -> Self {
+        Self {
+            m_cost: 64 * 1024,
+            t_cost: 3,
+            p_cost: 4,
+            hash_length: 32,
+            ..Default::default()
+        }
+    }
+
+    /// The configuration the OWASP Password Storage Cheat Sheet lists for Argon2id
+    ///
+    /// 19 MiB of memory, 2 passes and 1 lane, see [`Argon2::owasp_configurations`] for the
+    /// equivalent ones that trade memory for passes.
+    pub fn owasp() -> Self {
+        Self {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+            hash_length: RECOMMENDED_HASH_LENGTH,
+            ..Default::default()
+        }
+    }
+
+    /// The equivalent Argon2id configurations of the OWASP Password Storage Cheat Sheet
+    ///
+    /// From 46 MiB with 1 pass down to 7 MiB with 5 passes, all with 1 lane. The cheat sheet
+    /// considers them equally strong, pick the one whose memory the server can afford.
+    pub fn owasp_configurations() -> [Self; 5] {
+        [(46, 1), (19, 2), (12, 3), (9, 4), (7, 5)].map(|(mebibytes, t_cost)| Self {
+            m_cost: mebibytes * 1024,
+            t_cost,
+            p_cost: 1,
+            hash_length: RECOMMENDED_HASH_LENGTH,
+            ..Default::default()
+        })
+    }
 }
 
 #[cfg(test)]
@@ -264,7 +954,319 @@ mod tests {
     fn test_argon2() {
         let argon2 = Argon2::very_fast();
         let salt = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
-        let hash = argon2.hash_password("password", salt).unwrap();
+        let hash = argon2.hash_password("password", &salt).unwrap();
         assert_eq!(hash.len(), 64);
     }
+
+    #[test]
+    fn test_hash_password_bytes() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let salt = b"somesaltsomesalt";
+        let hash = argon2.hash_password("password", salt).unwrap();
+
+        let bytes = argon2.hash_password(b"password", salt).unwrap();
+        assert_eq!(hash, bytes);
+
+        let non_utf8 = [0xff, 0xfe, 0x00, 0x80];
+        let hash = argon2.hash_password(non_utf8, salt).unwrap();
+        assert!(argon2.verify_raw(&non_utf8[..], salt, &hash).unwrap());
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_hash_password_detached() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let (salt, hash) = argon2.hash_password_detached("password").unwrap();
+        let (other_salt, _) = argon2.hash_password_detached("password").unwrap();
+
+        assert_ne!(salt, other_salt);
+        assert!(argon2.verify_raw("password", &salt, &hash).unwrap());
+    }
+
+    #[test]
+    fn test_hash_password_into() {
+        let argon2 = Argon2::new(1024, 2, 1).with_hash_length(32);
+        let salt = b"somesaltsomesalt";
+        let hash = argon2.hash_password("password", salt).unwrap();
+
+        let mut out = [0u8; 32];
+        argon2
+            .hash_password_into("password", salt, &mut out)
+            .unwrap();
+        assert_eq!(hash, out);
+
+        let mut out = [0u8; 2];
+        assert!(
+            argon2
+                .hash_password_into("password", salt, &mut out)
+                .is_err()
+        );
+    }
+
+    #[cfg(feature = "c")]
+    #[test]
+    fn test_hash_password_encoded() {
+        let argon2 = Argon2::new(1024, 2, 1).with_hash_length(32);
+        let salt = b"somesaltsomesalt";
+        let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+
+        // Compare against the encoder of the reference implementation
+        let mut hash = vec![0u8; 32];
+        let mut expected = vec![0u8; 128];
+        let code = unsafe {
+            argon2_sys::argon2_hash(
+                2,
+                1024,
+                1,
+                b"password".as_ptr() as *const _,
+                8,
+                salt.as_ptr() as *const _,
+                salt.len(),
+                hash.as_mut_ptr() as *mut _,
+                hash.len(),
+                expected.as_mut_ptr() as *mut _,
+                expected.len(),
+                Algorithm::Argon2id as u32,
+                Version::V0x13 as u32,
+            )
+        };
+        assert_eq!(code, 0);
+
+        let expected = std::ffi::CStr::from_bytes_until_nul(&expected).unwrap();
+        assert_eq!(encoded, expected.to_str().unwrap());
+    }
+
+    #[cfg(all(feature = "c", feature = "pure-rust", not(feature = "argon2id-only")))]
+    #[test]
+    fn test_cross_check() {
+        for algorithm in [Algorithm::Argon2d, Algorithm::Argon2i, Algorithm::Argon2id] {
+            let argon2 = Argon2::new(256, 2, 2)
+                .with_algorithm(algorithm)
+                .with_ad(b"data");
+            assert!(argon2.cross_check("password", b"somesaltsomesalt").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_associated_data() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let salt = b"somesaltsomesalt";
+        let hash = argon2.hash_password("password", salt).unwrap();
+
+        let bound = argon2.clone().with_ad(b"user1");
+        let bound_hash = bound.hash_password("password", salt).unwrap();
+        assert_ne!(hash, bound_hash);
+
+        let encoded = bound.hash_password_encoded("password", salt).unwrap();
+        assert!(encoded.contains(",data=dXNlcjE$"));
+        assert!(Argon2::verify_encoded("password", &encoded).unwrap());
+        assert_eq!(Argon2::from_encoded(&encoded).unwrap(), bound);
+    }
+
+    #[test]
+    fn test_default() {
+        let argon2 = Argon2::default();
+        assert_eq!(argon2, Argon2::new(64 * 1024, 3, 4));
+        assert!(argon2.validate().is_ok());
+
+        let updated = Argon2 {
+            t_cost: 1,
+            ..Default::default()
+        };
+        assert!(
+            updated
+                .hash_password("password", b"somesaltsomesalt")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_rfc9106() {
+        let high = Argon2::rfc9106_high_memory();
+        assert_eq!(high, Argon2::new(2_097_152, 1, 4).with_hash_length(32));
+        assert_eq!(high.working_memory_bytes(), 2 * 1024 * 1024 * 1024);
+
+        let low = Argon2::rfc9106_low_memory();
+        assert_eq!(low, Argon2::default().with_hash_length(32));
+        assert!(low.hash_password("password", &[0; 16]).is_ok());
+    }
+
+    #[test]
+    fn test_owasp() {
+        let owasp = Argon2::owasp();
+        assert_eq!(owasp, Argon2::new(19_456, 2, 1));
+        assert_eq!(owasp.algorithm, Algorithm::Argon2id);
+
+        let configurations = Argon2::owasp_configurations();
+        assert_eq!(configurations[0], Argon2::new(47_104, 1, 1));
+        assert_eq!(configurations[4], Argon2::new(7_168, 5, 1));
+        assert!(configurations.contains(&owasp));
+        assert!(
+            configurations
+                .iter()
+                .all(|argon2| argon2.validate().is_ok())
+        );
+    }
+
+    #[test]
+    fn test_flags() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let salt = b"somesaltsomesalt";
+        let password = b"password".to_vec();
+        let hash = argon2.hash_password(&password, salt).unwrap();
+
+        let clearing = argon2.with_flags(Flags::CLEAR_PASSWORD | Flags::CLEAR_SECRET);
+        assert!(clearing.flags.contains(Flags::CLEAR_PASSWORD));
+        assert_eq!(clearing.hash_password(&password, salt).unwrap(), hash);
+        assert_eq!(password, b"password");
+
+        let encoded = clearing.hash_password_encoded(&password, salt).unwrap();
+        assert!(!clearing.needs_rehash(&encoded).unwrap());
+    }
+
+    #[cfg(not(feature = "argon2id-only"))]
+    #[test]
+    fn test_from_encoded() {
+        let argon2 = Argon2::new(1024, 2, 1)
+            .with_algorithm(Algorithm::Argon2i)
+            .with_version(Version::V0x10)
+            .with_hash_length(32);
+        let salt = b"somesaltsomesalt";
+        let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+
+        let decoded = Argon2::from_encoded(&encoded).unwrap();
+        assert_eq!(decoded.m_cost, 1024);
+        assert_eq!(decoded.t_cost, 2);
+        assert_eq!(decoded.p_cost, 1);
+        assert_eq!(decoded.hash_length, 32);
+        assert_eq!(decoded.algorithm, Algorithm::Argon2i);
+        assert_eq!(decoded.version, Version::V0x10);
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let salt = b"somesaltsomesalt";
+        let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+
+        assert!(!argon2.needs_rehash(&encoded).unwrap());
+        assert!(Argon2::new(2048, 2, 1).needs_rehash(&encoded).unwrap());
+        assert!(
+            argon2
+                .clone()
+                .with_hash_length(32)
+                .needs_rehash(&encoded)
+                .unwrap()
+        );
+
+        // The number of threads is not part of the hash
+        let threaded = Argon2::new(1024, 2, 4).with_threads(2);
+        let encoded = threaded.hash_password_encoded("password", salt).unwrap();
+        assert!(!threaded.needs_rehash(&encoded).unwrap());
+    }
+
+    #[test]
+    fn test_verify_or_dummy() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let encoded = argon2
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+
+        assert!(argon2.verify_or_dummy("password", Some(&encoded)).unwrap());
+        assert!(!argon2.verify_or_dummy("password", None).unwrap());
+        assert!(Argon2::new(0, 0, 0).verify_dummy("password").is_err());
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_verify_and_upgrade() {
+        let old = Argon2::new(1024, 1, 1);
+        let old_salt = b"oldsalt1";
+        let stored = old.hash_password_encoded("password", old_salt).unwrap();
+
+        let argon2 = Argon2::new(2048, 2, 1);
+        assert_eq!(
+            argon2.verify_and_upgrade("wrong", &stored).unwrap(),
+            (false, None)
+        );
+
+        let (valid, upgraded) = argon2.verify_and_upgrade("password", &stored).unwrap();
+        assert!(valid);
+        let upgraded = upgraded.unwrap();
+        assert!(!argon2.needs_rehash(&upgraded).unwrap());
+        assert!(Argon2::verify_encoded("password", &upgraded).unwrap());
+
+        // The upgraded hash gets a new salt of the recommended length
+        let decoded = phc::decode(&upgraded).unwrap();
+        assert_eq!(decoded.salt.len(), salt::RECOMMENDED_SALT_LENGTH);
+        assert_ne!(decoded.salt, old_salt);
+
+        assert_eq!(
+            argon2.verify_and_upgrade("password", &upgraded).unwrap(),
+            (true, None)
+        );
+
+        // A hash stronger than the target is not downgraded
+        let stronger = Argon2::new(4096, 3, 2).with_hash_length(64);
+        let stored = stronger
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+        assert_eq!(
+            argon2.verify_and_upgrade("password", &stored).unwrap(),
+            (true, None)
+        );
+
+        // The number of threads is not part of the hash
+        let threaded = Argon2::new(1024, 2, 4).with_threads(2);
+        let stored = threaded
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+        assert_eq!(
+            threaded.verify_and_upgrade("password", &stored).unwrap(),
+            (true, None)
+        );
+    }
+
+    #[test]
+    fn test_verify_raw() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let salt = b"somesaltsomesalt";
+        let hash = argon2.hash_password("password", salt).unwrap();
+
+        assert!(argon2.verify_raw("password", salt, &hash).unwrap());
+        assert!(!argon2.verify_raw("wrong", salt, &hash).unwrap());
+        assert!(!argon2.verify_raw("password", salt, &hash[..32]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_encoded() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let salt = b"somesaltsomesalt";
+        let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+
+        assert!(Argon2::verify_encoded("password", &encoded).unwrap());
+        assert!(!Argon2::verify_encoded("wrong", &encoded).unwrap());
+        assert!(Argon2::verify_encoded("password", "$argon2id$").is_err());
+    }
+
+    #[test]
+    fn test_output_buffer() {
+        assert_eq!(output_buffer(32).unwrap(), [0u8; 32]);
+        assert!(matches!(output_buffer(usize::MAX), Err(Error::OutOfMemory)));
+    }
+
+    #[test]
+    fn test_platform_len() {
+        assert_eq!(platform_len(1024).unwrap(), 1024);
+        assert!(matches!(
+            platform_len(u64::MAX),
+            Err(Error::MemoryTooLargeForPlatform(u64::MAX))
+        ));
+
+        #[cfg(target_pointer_width = "32")]
+        assert!(matches!(
+            Argon2::new(4 * 1024 * 1024, 1, 1).hash_password("password", b"somesaltsomesalt"),
+            Err(Error::MemoryTooLargeForPlatform(_))
+        ));
+    }
 }
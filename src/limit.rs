@@ -0,0 +1,247 @@
+use super::{Error, instrument};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// What a hash does when [`max_concurrent_hashes`] are already running
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Overflow {
+    /// Waits until one of the running hashes completes
+    Wait,
+    /// Waits at most the given time for one of the running hashes to complete
+    WaitFor(Duration),
+    /// Fails with [`Error::TooManyHashes`] right away
+    Fail,
+}
+
+/// Limits the number of hashes and verifications running at the same time in this process
+///
+/// Every hash holds its working memory until it completes, a burst of logins multiplies the
+/// memory cost by the number of requests. With a limit the peak memory is bounded by the limit
+/// times [`Argon2::memory_usage_bytes`](crate::Argon2::memory_usage_bytes), the excess calls
+/// queue or fail as `overflow` says. The limit is shared by every `Argon2` in the process and
+/// applies to the calls already waiting.
+///
+/// ## Arguments
+///
+/// - `max` - The number of hashes that may run at once, at least 1, `None` for no limit
+/// - `overflow` - What the calls beyond the limit do
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, limit::{self, Overflow}};
+/// use std::time::Duration;
+///
+/// let argon2 = Argon2::new(64 * 1024, 3, 4);
+/// let limit = 8 * 1024 * 1024 * 1024 / argon2.memory_usage_bytes() as usize;
+/// limit::set_max_concurrent_hashes(Some(limit), Overflow::WaitFor(Duration::from_secs(2)));
+///
+/// assert_eq!(limit::max_concurrent_hashes(), Some(limit));
+/// ```
+pub fn set_max_concurrent_hashes(max: Option<usize>, overflow: Overflow) {
+    LIMITER.set(max, overflow);
+}
+
+/// The limit set by [`set_max_concurrent_hashes`], `None` if there is none
+pub fn max_concurrent_hashes() -> Option<usize> {
+    LIMITER.state().max
+}
+
+/// The number of hashes running right now, not counting those waiting for the limit
+pub fn running_hashes() -> usize {
+    LIMITER.state().running
+}
+
+/// Rejects passwords longer than `max` bytes with [`Error::PasswordTooLong`]
+///
+/// Argon2 pre-hashes the whole password, an endpoint that accepts anything a client sends can be
+/// made to read and hash megabytes per request. The limit applies to every hash and verification
+/// in the process. Without it passwords are only limited to `u32::MAX` bytes by Argon2 itself.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, error::Error, limit};
+///
+/// limit::set_max_password_length(Some(1024));
+///
+/// let result = Argon2::new(1024, 2, 1).hash_password(vec![b'a'; 2048], b"somesaltsomesalt");
+/// assert!(matches!(result, Err(Error::PasswordTooLong(2048, 1024))));
+/// # limit::set_max_password_length(None);
+/// ```
+pub fn set_max_password_length(max: Option<usize>) {
+    MAX_PASSWORD_LENGTH.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// The limit set by [`set_max_password_length`], `None` if there is none
+pub fn max_password_length() -> Option<usize> {
+    match MAX_PASSWORD_LENGTH.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        max => Some(max),
+    }
+}
+
+static MAX_PASSWORD_LENGTH: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Rejects a password longer than the limit set by [`set_max_password_length`]
+pub(crate) fn check_password_length(len: usize) -> Result<(), Error> {
+    match max_password_length() {
+        Some(max) if len > max => Err(Error::PasswordTooLong(len, max)),
+        _ => Ok(()),
+    }
+}
+
+static LIMITER: Limiter = Limiter::new();
+
+/// Takes a place among the running hashes, held until the permit is dropped
+pub(crate) fn acquire() -> Result<Permit<'static>, Error> {
+    LIMITER.acquire()
+}
+
+struct State {
+    max: Option<usize>,
+    overflow: Overflow,
+    running: usize,
+}
+
+/// A counting semaphore whose size can change while calls wait on it
+struct Limiter {
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+impl Limiter {
+    const fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                max: None,
+                overflow: Overflow::Wait,
+                running: 0,
+            }),
+            released: Condvar::new(),
+        }
+    }
+
+    // A panicking hash leaves the count consistent, its permit is dropped while unwinding
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set(&self, max: Option<usize>, overflow: Overflow) {
+        let mut state = self.state();
+        state.max = max.map(|max| max.max(1));
+        state.overflow = overflow;
+        // A higher limit lets waiting calls through
+        self.released.notify_all();
+    }
+
+    fn acquire(&self) -> Result<Permit<'_>, Error> {
+        let mut state = self.state();
+        let full = |state: &mut State| state.max.is_some_and(|max| state.running >= max);
+
+        if full(&mut state) {
+            match state.overflow {
+                Overflow::Wait => {
+                    state = self
+                        .released
+                        .wait_while(state, full)
+                        .unwrap_or_else(PoisonError::into_inner);
+                }
+                Overflow::WaitFor(timeout) => {
+                    let deadline = Instant::now() + timeout;
+                    while full(&mut state) {
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                            return Err(Error::TooManyHashes(state.running));
+                        }
+                        state = self
+                            .released
+                            .wait_timeout(state, remaining)
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .0;
+                    }
+                }
+                Overflow::Fail => return Err(Error::TooManyHashes(state.running)),
+            }
+        }
+
+        state.running += 1;
+        instrument::concurrent_hashes(state.running);
+        Ok(Permit(self))
+    }
+}
+
+pub(crate) struct Permit<'a>(&'a Limiter);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state();
+        state.running -= 1;
+        instrument::concurrent_hashes(state.running);
+        drop(state);
+        self.0.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fail() {
+        let limiter = Limiter::new();
+        limiter.set(Some(2), Overflow::Fail);
+
+        let first = limiter.acquire().unwrap();
+        let _second = limiter.acquire().unwrap();
+        assert!(matches!(limiter.acquire(), Err(Error::TooManyHashes(2))));
+
+        drop(first);
+        assert!(limiter.acquire().is_ok());
+
+        limiter.set(None, Overflow::Fail);
+        let _more: Vec<_> = (0..10).map(|_| limiter.acquire().unwrap()).collect();
+        assert_eq!(limiter.state().running, 11);
+    }
+
+    #[test]
+    fn test_wait() {
+        let limiter = Arc::new(Limiter::new());
+        limiter.set(Some(2), Overflow::Wait);
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let (limiter, peak) = (limiter.clone(), peak.clone());
+                std::thread::spawn(move || {
+                    let _permit = limiter.acquire().unwrap();
+                    peak.fetch_max(limiter.state().running, Ordering::Relaxed);
+                    std::thread::sleep(Duration::from_millis(10));
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert!((1..=2).contains(&peak.load(Ordering::Relaxed)));
+        assert_eq!(limiter.state().running, 0);
+    }
+
+    #[test]
+    fn test_wait_for() {
+        let limiter = Limiter::new();
+        limiter.set(Some(1), Overflow::WaitFor(Duration::from_millis(20)));
+
+        let _permit = limiter.acquire().unwrap();
+        let started = Instant::now();
+        assert!(matches!(limiter.acquire(), Err(Error::TooManyHashes(1))));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+
+        // Raising the limit lets the call through
+        limiter.set(Some(2), Overflow::Fail);
+        assert!(limiter.acquire().is_ok());
+    }
+}
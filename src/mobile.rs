@@ -0,0 +1,102 @@
+use super::{Algorithm, Argon2, Error, Version};
+
+/// The parameters of [`Argon2`] as exposed to Kotlin and Swift
+///
+/// Flags are not exposed, they do not affect the resulting hash.
+#[derive(uniffi::Record, Clone, Debug, Eq, PartialEq)]
+pub struct Params {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub hash_length: u64,
+    pub algorithm: Algorithm,
+    pub version: Version,
+    pub ad: Vec<u8>,
+}
+
+impl From<Params> for Argon2 {
+    fn from(params: Params) -> Self {
+        Self {
+            m_cost: params.m_cost,
+            t_cost: params.t_cost,
+            p_cost: params.p_cost,
+            hash_length: params.hash_length,
+            algorithm: params.algorithm,
+            version: params.version,
+            ad: params.ad,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Argon2> for Params {
+    fn from(argon2: Argon2) -> Self {
+        Self {
+            m_cost: argon2.m_cost,
+            t_cost: argon2.t_cost,
+            p_cost: argon2.p_cost,
+            hash_length: argon2.hash_length,
+            algorithm: argon2.algorithm,
+            version: argon2.version,
+            ad: argon2.ad,
+        }
+    }
+}
+
+/// Creates the parameters with the defaults of [`Argon2::new`]
+#[uniffi::export]
+pub fn new_params(m_cost: u32, t_cost: u32, p_cost: u32) -> Params {
+    Argon2::new(m_cost, t_cost, p_cost).into()
+}
+
+/// Reads the parameters back from a PHC string
+#[uniffi::export]
+pub fn params_from_encoded(encoded: String) -> Result<Params, Error> {
+    Argon2::from_encoded(&encoded).map(Params::from)
+}
+
+/// See [`Argon2::hash_password`]
+#[uniffi::export]
+pub fn hash_password(params: Params, password: Vec<u8>, salt: Vec<u8>) -> Result<Vec<u8>, Error> {
+    Argon2::from(params).hash_password(password, &salt)
+}
+
+/// See [`Argon2::hash_password_encoded`]
+#[uniffi::export]
+pub fn hash_password_encoded(
+    params: Params,
+    password: Vec<u8>,
+    salt: Vec<u8>,
+) -> Result<String, Error> {
+    Argon2::from(params).hash_password_encoded(password, &salt)
+}
+
+/// See [`Argon2::verify_encoded`]
+#[uniffi::export]
+pub fn verify_encoded(password: Vec<u8>, encoded: String) -> Result<bool, Error> {
+    Argon2::verify_encoded(password, &encoded)
+}
+
+/// See [`Argon2::needs_rehash`]
+#[uniffi::export]
+pub fn needs_rehash(params: Params, encoded: String) -> Result<bool, Error> {
+    Argon2::from(params).needs_rehash(&encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_roundtrip() {
+        let argon2 = Argon2::new(1024, 2, 1).with_ad(b"context");
+        let params = Params::from(argon2.clone());
+        assert_eq!(Argon2::from(params.clone()), argon2);
+
+        let salt = b"somesaltsomesalt".to_vec();
+        let encoded = hash_password_encoded(params.clone(), b"password".to_vec(), salt).unwrap();
+        assert_eq!(params_from_encoded(encoded.clone()).unwrap(), params);
+        assert!(verify_encoded(b"password".to_vec(), encoded.clone()).unwrap());
+        assert!(!needs_rehash(params, encoded).unwrap());
+    }
+}
@@ -0,0 +1,140 @@
+use super::{Argon2, Error, output::HashBytes};
+
+/// The Unicode normalization form passwords are brought to before hashing
+///
+/// The same password can be typed as different code points, e.g. `é` as one precomposed code
+/// point on one keyboard and as `e` followed by a combining accent on another. Normalizing makes
+/// both hash the same. Requires the `unicode` feature to hash.
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Normalization {
+    /// Canonical composition, visually identical passwords hash the same
+    Nfc,
+    /// Compatibility composition, also folds variants such as full-width letters and ligatures
+    Nfkc,
+}
+
+impl Normalization {
+    /// The name of the form as used in the `norm` parameter of PHC strings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Normalization::Nfc => "nfc",
+            Normalization::Nfkc => "nfkc",
+        }
+    }
+}
+
+impl std::fmt::Display for Normalization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Normalization {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "nfc" => Ok(Normalization::Nfc),
+            "nfkc" => Ok(Normalization::Nfkc),
+            _ => Err(Error::InvalidPhcString("unknown normalization")),
+        }
+    }
+}
+
+impl Argon2 {
+    /// Normalizes passwords to the given Unicode form before hashing them
+    ///
+    /// Passwords that are not valid UTF-8 are hashed as they are. The form is stored in the `norm`
+    /// parameter of PHC strings and in binary records, so verifying a stored hash normalizes the
+    /// same way. Other Argon2 implementations do not know the parameter and cannot verify such
+    /// hashes.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "unicode")]
+    /// # {
+    /// use argon2_rs::{Argon2, normalize::Normalization};
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 1).with_normalization(Normalization::Nfc);
+    /// let encoded = argon2
+    ///     .hash_password_encoded("caf\u{e9}", b"somesaltsomesalt")
+    ///     .unwrap();
+    ///
+    /// assert!(encoded.contains(",norm=nfc"));
+    /// assert!(Argon2::verify_encoded("cafe\u{301}", &encoded).unwrap());
+    /// # }
+    /// ```
+    pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+        self.normalization = Some(normalization);
+        self
+    }
+
+    /// The normalized password the implementation is given instead, if it is to be normalized
+    #[cfg(feature = "unicode")]
+    pub(crate) fn normalized(&self, password: &[u8]) -> Result<Option<HashBytes>, Error> {
+        use unicode_normalization::UnicodeNormalization;
+
+        let (Some(normalization), Ok(password)) =
+            (self.normalization, std::str::from_utf8(password))
+        else {
+            return Ok(None);
+        };
+
+        let normalized: String = match normalization {
+            Normalization::Nfc => password.nfc().collect(),
+            Normalization::Nfkc => password.nfkc().collect(),
+        };
+        Ok(Some(normalized.into_bytes().into()))
+    }
+
+    #[cfg(not(feature = "unicode"))]
+    pub(crate) fn normalized(&self, _password: &[u8]) -> Result<Option<HashBytes>, Error> {
+        match self.normalization {
+            Some(normalization) => Err(Error::InvalidConfig(format!(
+                "{normalization} normalization requires the `unicode` feature"
+            ))),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "unicode"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        let salt = b"somesaltsomesalt";
+        let argon2 = Argon2::new(1024, 2, 1);
+        let nfc = argon2.clone().with_normalization(Normalization::Nfc);
+
+        // Precomposed and decomposed forms hash the same, as the precomposed one without it
+        let hash = argon2.hash_password("caf\u{e9}", salt).unwrap();
+        assert_eq!(nfc.hash_password("caf\u{e9}", salt).unwrap(), hash);
+        assert_eq!(nfc.hash_password("cafe\u{301}", salt).unwrap(), hash);
+        assert_ne!(argon2.hash_password("cafe\u{301}", salt).unwrap(), hash);
+
+        // Only NFKC folds compatibility variants
+        let nfkc = argon2.with_normalization(Normalization::Nfkc);
+        assert_eq!(
+            nfkc.hash_password("\u{ff41}b", salt).unwrap(),
+            nfkc.hash_password("ab", salt).unwrap()
+        );
+        assert_ne!(
+            nfc.hash_password("\u{ff41}b", salt).unwrap(),
+            nfc.hash_password("ab", salt).unwrap()
+        );
+
+        // Invalid UTF-8 is hashed as it is
+        assert!(nfc.hash_password([0xff, 0xfe, 0xfd], salt).is_ok());
+
+        let encoded = nfkc.hash_password_encoded("\u{ff41}b", salt).unwrap();
+        assert_eq!(
+            Argon2::from_encoded(&encoded).unwrap().normalization,
+            Some(Normalization::Nfkc)
+        );
+        assert!(Argon2::verify_encoded("ab", &encoded).unwrap());
+    }
+}
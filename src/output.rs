@@ -0,0 +1,128 @@
+use super::{constant_time_eq, phc};
+
+/// Overwrites the given bytes with zeros in a way the compiler will not optimize away
+pub(crate) fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// A derived hash that is wiped from memory when dropped
+///
+/// Comparisons with `==` are done in constant time.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::Argon2;
+///
+/// let argon2 = Argon2::new(1024, 2, 1).with_hash_length(32);
+/// let hash = argon2.hash_password_bytes("password", b"somesaltsomesalt").unwrap();
+/// assert_eq!(hash.to_hex().len(), 64);
+/// ```
+#[derive(Clone, Eq)]
+pub struct HashBytes(Vec<u8>);
+
+impl HashBytes {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Compares the hash with the given bytes in constant time
+    ///
+    /// Prefer this (or `==`) over comparing the raw bytes of the hash.
+    pub fn ct_eq(&self, other: impl AsRef<[u8]>) -> bool {
+        constant_time_eq(&self.0, other.as_ref())
+    }
+
+    /// The hash as lowercase hex
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// The hash as Base64 without padding, the same form used in PHC strings
+    pub fn to_base64(&self) -> String {
+        phc::b64_encode(&self.0)
+    }
+}
+
+impl From<Vec<u8>> for HashBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+}
+
+impl std::ops::Deref for HashBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for HashBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq for HashBytes {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.0, &other.0)
+    }
+}
+
+impl PartialEq<[u8]> for HashBytes {
+    fn eq(&self, other: &[u8]) -> bool {
+        constant_time_eq(&self.0, other)
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for HashBytes {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        self.0.as_slice().ct_eq(other.0.as_slice())
+    }
+}
+
+impl std::fmt::Debug for HashBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("HashBytes([REDACTED])")
+    }
+}
+
+impl Drop for HashBytes {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for HashBytes {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for HashBytes {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_bytes() {
+        let hash = HashBytes::from(vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(hash.to_hex(), "deadbeef");
+        assert_eq!(hash.to_base64(), "3q2+7w");
+        assert_eq!(format!("{:?}", hash), "HashBytes([REDACTED])");
+
+        assert_eq!(hash, HashBytes::from(vec![0xde, 0xad, 0xbe, 0xef]));
+        assert_ne!(hash, HashBytes::from(vec![0xde, 0xad, 0xbe]));
+        assert!(hash == [0xde, 0xad, 0xbe, 0xef][..]);
+        assert!(hash.ct_eq([0xde, 0xad, 0xbe, 0xef]));
+        assert!(!hash.ct_eq([0xde, 0xad, 0xbe, 0xee]));
+    }
+}
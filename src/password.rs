@@ -0,0 +1,110 @@
+use super::output::wipe;
+
+/// A password that is wiped from memory when dropped and never shown by `Debug`
+///
+/// Every method that takes a password accepts it like a string or raw bytes. Like the other
+/// secret-bearing types of this crate, [`Pepper`](crate::pepper::Pepper),
+/// [`HashBytes`](crate::output::HashBytes) and [`KeyedArgon2`](crate::pepper::KeyedArgon2), its
+/// `Debug` output is redacted so logging a struct that holds one cannot leak it.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, password::Password};
+///
+/// let password = Password::from("hunter2");
+/// assert_eq!(format!("{password:?}"), "Password([REDACTED])");
+///
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let hash = argon2.hash_password(&password, b"somesaltsomesalt").unwrap();
+/// assert_eq!(hash, argon2.hash_password("hunter2", b"somesaltsomesalt").unwrap());
+/// ```
+#[derive(Clone, Eq, PartialEq)]
+pub struct Password(Vec<u8>);
+
+impl Password {
+    pub fn new(password: impl Into<Vec<u8>>) -> Self {
+        Self(password.into())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Password {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<&str> for Password {
+    fn from(password: &str) -> Self {
+        Self::new(password)
+    }
+}
+
+impl From<String> for Password {
+    fn from(password: String) -> Self {
+        Self::new(password)
+    }
+}
+
+impl From<Vec<u8>> for Password {
+    fn from(password: Vec<u8>) -> Self {
+        Self::new(password)
+    }
+}
+
+impl std::fmt::Debug for Password {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Password([REDACTED])")
+    }
+}
+
+impl Drop for Password {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Password {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for Password {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Argon2,
+        output::HashBytes,
+        pepper::{KeyedArgon2, Pepper},
+    };
+
+    #[test]
+    fn test_debug_redacted() {
+        let secret = "hunter2";
+        let keyed = KeyedArgon2::new(Argon2::new(1024, 2, 1), Pepper::new("v1", secret))
+            .with_previous(Pepper::new("v0", secret));
+
+        let debug = [
+            format!("{:?}", Password::from(secret)),
+            format!("{:?}", HashBytes::from(secret.as_bytes().to_vec())),
+            format!("{:?}", Pepper::new("v1", secret)),
+            format!("{keyed:?}"),
+            format!("{keyed:#?}"),
+        ];
+        for debug in debug {
+            assert!(debug.contains("[REDACTED]"), "{debug}");
+            assert!(!debug.contains(secret), "{debug}");
+            // Bytes would be shown as a list of numbers
+            assert!(!debug.contains("104"), "{debug}");
+        }
+    }
+}
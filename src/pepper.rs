@@ -0,0 +1,512 @@
+use super::{
+    Argon2, Error, instrument::Operation, output::HashBytes, output::wipe, output_buffer, phc,
+    stored::StoredHash,
+};
+
+#[cfg(all(feature = "locked-memory", any(unix, windows)))]
+use super::allocator::LockedSecret;
+
+#[cfg(all(feature = "keyutils", target_os = "linux"))]
+mod keyring;
+#[cfg(all(feature = "keyutils", target_os = "linux"))]
+pub use keyring::KeyringSecretProvider;
+
+#[cfg(all(feature = "dpapi", windows))]
+mod dpapi;
+#[cfg(all(feature = "dpapi", windows))]
+pub use dpapi::DpapiSecretProvider;
+
+/// A server-side secret (pepper) identified by a key ID
+///
+/// The key ID is stored in the `keyid` parameter of PHC strings so the matching pepper can be
+/// selected at verification time. The secret is wiped from memory when dropped.
+#[derive(Clone)]
+pub struct Pepper {
+    id: Vec<u8>,
+    secret: Secret,
+}
+
+#[derive(Clone)]
+enum Secret {
+    Heap(Vec<u8>),
+    #[cfg(all(feature = "locked-memory", any(unix, windows)))]
+    Locked(LockedSecret),
+}
+
+impl Pepper {
+    /// Create a new pepper
+    ///
+    /// ## Arguments
+    ///
+    /// - `id` - The key ID of the pepper (e.g. `"2024-01"`)
+    /// - `secret` - The secret bytes of the pepper
+    pub fn new(id: impl Into<Vec<u8>>, secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            id: id.into(),
+            secret: Secret::Heap(secret.into()),
+        }
+    }
+
+    /// Create a new pepper whose secret is kept in memory locked into RAM
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{allocator::LockedSecret, pepper::Pepper};
+    ///
+    /// let pepper = Pepper::locked("v1", LockedSecret::from(b"secret".to_vec()));
+    /// assert_eq!(pepper.id(), b"v1");
+    /// ```
+    #[cfg(all(feature = "locked-memory", any(unix, windows)))]
+    pub fn locked(id: impl Into<Vec<u8>>, secret: LockedSecret) -> Self {
+        Self {
+            id: id.into(),
+            secret: Secret::Locked(secret),
+        }
+    }
+
+    pub fn id(&self) -> &[u8] {
+        &self.id
+    }
+
+    pub(crate) fn secret(&self) -> &[u8] {
+        match &self.secret {
+            Secret::Heap(secret) => secret,
+            #[cfg(all(feature = "locked-memory", any(unix, windows)))]
+            Secret::Locked(secret) => secret,
+        }
+    }
+}
+
+impl std::fmt::Debug for Pepper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pepper")
+            .field("id", &String::from_utf8_lossy(&self.id))
+            .field("secret", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl Drop for Pepper {
+    fn drop(&mut self) {
+        match &mut self.secret {
+            Secret::Heap(secret) => wipe(secret),
+            // Wipes itself
+            #[cfg(all(feature = "locked-memory", any(unix, windows)))]
+            Secret::Locked(_) => {}
+        }
+    }
+}
+
+/// A source of peppers resolved at hash and verification time
+///
+/// Implement this to load the pepper from an external source (environment, vault, KMS) so the secret
+/// only lives in memory for the duration of a call.
+pub trait SecretProvider {
+    /// The pepper new hashes are keyed with
+    fn current(&self) -> Result<Pepper, Error>;
+
+    /// Looks up a pepper by its key ID, `None` if it is unknown
+    fn get(&self, id: &[u8]) -> Result<Option<Pepper>, Error>;
+
+    /// Every pepper accepted for verification, tried in order when the key ID of a hash is unknown
+    fn all(&self) -> Result<Vec<Pepper>, Error> {
+        Ok(vec![self.current()?])
+    }
+}
+
+/// Async version of [`SecretProvider`] for sources that require I/O
+///
+/// Resolve the peppers with [`Peppers::resolve`] and hash with the resulting [`KeyedArgon2`].
+pub trait AsyncSecretProvider {
+    /// The pepper new hashes are keyed with
+    fn current(&self) -> impl Future<Output = Result<Pepper, Error>> + Send;
+
+    /// Every pepper accepted for verification, the first one must be the current pepper
+    fn all(&self) -> impl Future<Output = Result<Vec<Pepper>, Error>> + Send;
+}
+
+/// A fixed set of peppers kept in memory
+#[derive(Clone, Debug)]
+pub struct Peppers {
+    current: Pepper,
+    previous: Vec<Pepper>,
+}
+
+impl Peppers {
+    pub fn new(current: Pepper) -> Self {
+        Self {
+            current,
+            previous: Vec::new(),
+        }
+    }
+
+    /// Adds a previous pepper that is still accepted for verification
+    pub fn with_previous(mut self, pepper: Pepper) -> Self {
+        self.previous.push(pepper);
+        self
+    }
+
+    /// Resolves the peppers of an async provider
+    pub async fn resolve(provider: &impl AsyncSecretProvider) -> Result<Self, Error> {
+        let mut peppers = provider.all().await?.into_iter();
+        let current = match peppers.next() {
+            Some(current) => current,
+            None => provider.current().await?,
+        };
+
+        Ok(Self {
+            current,
+            previous: peppers.collect(),
+        })
+    }
+}
+
+impl SecretProvider for Peppers {
+    fn current(&self) -> Result<Pepper, Error> {
+        Ok(self.current.clone())
+    }
+
+    fn get(&self, id: &[u8]) -> Result<Option<Pepper>, Error> {
+        Ok(std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|pepper| pepper.id() == id)
+            .cloned())
+    }
+
+    fn all(&self) -> Result<Vec<Pepper>, Error> {
+        Ok(std::iter::once(&self.current)
+            .chain(&self.previous)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Reads the pepper from environment variables on every call
+///
+/// The key ID and the secret are read from two separate variables, the secret is used as raw bytes.
+#[derive(Clone, Debug)]
+pub struct EnvSecretProvider {
+    id_var: String,
+    secret_var: String,
+}
+
+impl EnvSecretProvider {
+    /// ## Arguments
+    ///
+    /// - `id_var` - The variable holding the key ID (e.g. `PEPPER_ID`)
+    /// - `secret_var` - The variable holding the secret (e.g. `PEPPER`)
+    pub fn new(id_var: impl Into<String>, secret_var: impl Into<String>) -> Self {
+        Self {
+            id_var: id_var.into(),
+            secret_var: secret_var.into(),
+        }
+    }
+
+    fn var(name: &str) -> Result<String, Error> {
+        std::env::var(name).map_err(|e| Error::SecretProvider(format!("{}: {}", name, e)))
+    }
+}
+
+impl SecretProvider for EnvSecretProvider {
+    fn current(&self) -> Result<Pepper, Error> {
+        let id = Self::var(&self.id_var)?;
+        let secret = Self::var(&self.secret_var)?;
+        Ok(Pepper::new(id, secret))
+    }
+
+    fn get(&self, id: &[u8]) -> Result<Option<Pepper>, Error> {
+        let current = self.current()?;
+        Ok((current.id() == id).then_some(current))
+    }
+}
+
+/// An [`Argon2`] instance keyed with a pepper
+///
+/// New hashes are always keyed with the current pepper of the provider. Stored hashes are verified
+/// with the pepper matching their key ID, falling back through every known pepper if the key ID is
+/// unknown. This allows rotating the pepper without invalidating every stored hash.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, pepper::{KeyedArgon2, Pepper}};
+///
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let old = KeyedArgon2::new(argon2.clone(), Pepper::new("v1", b"old secret".to_vec()));
+/// let stored = old.hash_password_encoded("password", b"somesaltsomesalt").unwrap();
+///
+/// // Rotate the pepper, hashes keyed with "v1" can still be verified
+/// let keyed = KeyedArgon2::new(argon2, Pepper::new("v2", b"new secret".to_vec()))
+///     .with_previous(Pepper::new("v1", b"old secret".to_vec()));
+///
+/// assert!(keyed.verify_encoded("password", &stored).unwrap());
+/// assert!(keyed.needs_rehash(&stored).unwrap());
+/// ```
+#[derive(Clone)]
+pub struct KeyedArgon2<P = Peppers> {
+    pub argon2: Argon2,
+    provider: P,
+}
+
+impl<P> std::fmt::Debug for KeyedArgon2<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyedArgon2")
+            .field("argon2", &self.argon2)
+            .field("provider", &"[REDACTED]")
+            .finish()
+    }
+}
+
+impl KeyedArgon2<Peppers> {
+    /// Create a new keyed instance
+    ///
+    /// ## Arguments
+    ///
+    /// - `argon2` - The parameters to hash with
+    /// - `current` - The pepper new hashes are keyed with
+    pub fn new(argon2: Argon2, current: Pepper) -> Self {
+        Self::with_provider(argon2, Peppers::new(current))
+    }
+
+    /// Adds a previous pepper that is still accepted for verification
+    ///
+    /// Previous peppers are tried in the order they were added.
+    pub fn with_previous(mut self, pepper: Pepper) -> Self {
+        self.provider = self.provider.with_previous(pepper);
+        self
+    }
+}
+
+impl<P: SecretProvider> KeyedArgon2<P> {
+    /// Create a new keyed instance that resolves the pepper from the given provider on every call
+    pub fn with_provider(argon2: Argon2, provider: P) -> Self {
+        Self { argon2, provider }
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    fn hash_keyed(&self, password: &[u8], salt: &[u8], pepper: &Pepper) -> Result<Vec<u8>, Error> {
+        let mut hash = output_buffer(self.argon2.hash_len()?)?;
+        self.argon2
+            .hash_with_secret(password, salt, pepper.secret(), &mut hash)?;
+        Ok(hash)
+    }
+
+    /// Hashes the given password keyed with the current pepper
+    ///
+    /// ## Returns
+    ///
+    /// The hash of the password in its raw byte form
+    pub fn hash_password(&self, password: impl AsRef<[u8]>, salt: &[u8]) -> Result<Vec<u8>, Error> {
+        let pepper = self.provider.current()?;
+        self.hash_keyed(password.as_ref(), salt, &pepper)
+    }
+
+    /// Hashes the given password keyed with the current pepper and encodes the result as a PHC string
+    ///
+    /// The key ID of the pepper is stored in the `keyid` parameter.
+    pub fn hash_password_encoded(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<String, Error> {
+        let pepper = self.provider.current()?;
+        let hash = self.hash_keyed(password.as_ref(), salt, &pepper)?;
+        Ok(phc::encode(&self.argon2, Some(pepper.id()), salt, &hash))
+    }
+
+    /// Hashes the given password keyed with the current pepper and bundles the result with its
+    /// parameters, salt and the key ID of the pepper
+    pub fn hash_password_stored(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<StoredHash, Error> {
+        let pepper = self.provider.current()?;
+        let hash = self.hash_keyed(password.as_ref(), salt, &pepper)?;
+        Ok(StoredHash::new(self.argon2.clone(), salt, hash).with_keyid(pepper.id()))
+    }
+
+    /// Verifies the given password against a PHC string
+    ///
+    /// The pepper is selected by the `keyid` parameter of the hash. If the key ID is missing or
+    /// unknown every pepper of the provider is tried.
+    ///
+    /// ## Returns
+    ///
+    /// `true` if the password matches the hash
+    pub fn verify_encoded(&self, password: impl AsRef<[u8]>, encoded: &str) -> Result<bool, Error> {
+        self.verify_stored(password, &encoded.parse()?)
+    }
+
+    /// Verifies the given password against a [`StoredHash`]
+    ///
+    /// The pepper is selected by the key ID of the hash as in [`KeyedArgon2::verify_encoded`].
+    pub fn verify_stored(
+        &self,
+        password: impl AsRef<[u8]>,
+        stored: &StoredHash,
+    ) -> Result<bool, Error> {
+        let operation = Operation::verify(&stored.argon2);
+        let result = self.verify_with_peppers(password.as_ref(), stored);
+        operation.finish_verify(&result);
+        result
+    }
+
+    fn verify_with_peppers(&self, password: &[u8], stored: &StoredHash) -> Result<bool, Error> {
+        let selected = match stored.keyid.as_deref() {
+            Some(keyid) => self.provider.get(keyid)?,
+            None => None,
+        };
+
+        let candidates = match selected {
+            Some(pepper) => vec![pepper],
+            None => self.provider.all()?,
+        };
+
+        for pepper in candidates {
+            let mut hash = output_buffer(stored.hash.len())?;
+            stored
+                .argon2
+                .hash_with_secret(password, &stored.salt, pepper.secret(), &mut hash)?;
+
+            if HashBytes::from(hash).ct_eq(&stored.hash) {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Checks whether a stored hash should be recomputed
+    ///
+    /// Returns `true` if the parameters of the hash differ from this instance or if it was not keyed
+    /// with the current pepper.
+    pub fn needs_rehash(&self, encoded: &str) -> Result<bool, Error> {
+        let decoded = phc::decode(encoded)?;
+        let current = self.provider.current()?;
+        Ok(!decoded.argon2.same_parameters(&self.argon2)
+            || decoded.keyid.as_deref() != Some(current.id()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pepper_rotation() {
+        let argon2 = Argon2::new(1024, 1, 1);
+        let salt = b"somesaltsomesalt";
+
+        let v1 = KeyedArgon2::new(argon2.clone(), Pepper::new("v1", b"secret1".to_vec()));
+        let stored = v1.hash_password_encoded("password", salt).unwrap();
+        assert!(stored.contains(",keyid=djE$"));
+        assert!(!v1.needs_rehash(&stored).unwrap());
+        let threaded = KeyedArgon2::new(
+            argon2.clone().with_threads(1),
+            Pepper::new("v1", b"secret1".to_vec()),
+        );
+        assert!(!threaded.needs_rehash(&stored).unwrap());
+
+        // The pepper changes the hash
+        let plain = argon2.hash_password("password", salt).unwrap();
+        assert_ne!(v1.hash_password("password", salt).unwrap(), plain);
+        assert!(Argon2::verify_encoded("password", &stored).is_err());
+
+        let v2 = KeyedArgon2::new(argon2.clone(), Pepper::new("v2", b"secret2".to_vec()))
+            .with_previous(Pepper::new("v1", b"secret1".to_vec()));
+        assert!(v2.verify_encoded("password", &stored).unwrap());
+        assert!(!v2.verify_encoded("wrong", &stored).unwrap());
+        assert!(v2.needs_rehash(&stored).unwrap());
+
+        // Without the old pepper the hash can no longer be verified
+        let v3 = KeyedArgon2::new(argon2, Pepper::new("v3", b"secret3".to_vec()));
+        assert!(!v3.verify_encoded("password", &stored).unwrap());
+    }
+
+    #[test]
+    fn test_keyed_stored_hash() {
+        let argon2 = Argon2::new(1024, 1, 1).with_ad(b"user1");
+        let keyed = KeyedArgon2::new(argon2, Pepper::new("v1", b"secret1".to_vec()));
+
+        let stored = keyed
+            .hash_password_stored("password", b"somesaltsomesalt")
+            .unwrap();
+        let encoded = stored.to_string();
+        assert!(encoded.contains(",keyid=djE,data=dXNlcjE$"));
+
+        // Both the key ID and the associated data survive the round trip
+        let parsed: StoredHash = encoded.parse().unwrap();
+        assert_eq!(parsed, stored);
+        assert!(keyed.verify_stored("password", &parsed).unwrap());
+        assert!(!keyed.verify_stored("wrong", &parsed).unwrap());
+        assert!(keyed.verify_encoded("password", &encoded).unwrap());
+    }
+
+    #[test]
+    fn test_env_secret_provider() {
+        // SAFETY: no other test reads or writes these variables
+        unsafe {
+            std::env::set_var("ARGON2_RS_TEST_PEPPER_ID", "env1");
+            std::env::set_var("ARGON2_RS_TEST_PEPPER", "secret");
+        }
+
+        let provider = EnvSecretProvider::new("ARGON2_RS_TEST_PEPPER_ID", "ARGON2_RS_TEST_PEPPER");
+        let keyed = KeyedArgon2::with_provider(Argon2::new(1024, 1, 1), provider);
+        let stored = keyed
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+
+        let peppers = KeyedArgon2::new(Argon2::new(1024, 1, 1), Pepper::new("env1", "secret"));
+        assert!(peppers.verify_encoded("password", &stored).unwrap());
+
+        let missing = EnvSecretProvider::new("ARGON2_RS_TEST_MISSING_ID", "ARGON2_RS_TEST_MISSING");
+        let keyed = KeyedArgon2::with_provider(Argon2::new(1024, 1, 1), missing);
+        assert!(
+            keyed
+                .hash_password("password", b"somesaltsomesalt")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_resolve_async_provider() {
+        struct Vault;
+
+        impl AsyncSecretProvider for Vault {
+            async fn current(&self) -> Result<Pepper, Error> {
+                Ok(Pepper::new("v2", "secret2"))
+            }
+
+            async fn all(&self) -> Result<Vec<Pepper>, Error> {
+                Ok(vec![
+                    Pepper::new("v2", "secret2"),
+                    Pepper::new("v1", "secret1"),
+                ])
+            }
+        }
+
+        let future = std::pin::pin!(Peppers::resolve(&Vault));
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        let peppers = match future.poll(&mut cx) {
+            std::task::Poll::Ready(peppers) => peppers.unwrap(),
+            std::task::Poll::Pending => unreachable!(),
+        };
+
+        assert_eq!(peppers.current().unwrap().id(), b"v2");
+        assert!(peppers.get(b"v1").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_pepper_debug() {
+        let pepper = Pepper::new("v1", b"secret".to_vec());
+        assert_eq!(
+            format!("{:?}", pepper),
+            r#"Pepper { id: "v1", secret: "[REDACTED]" }"#
+        );
+    }
+}
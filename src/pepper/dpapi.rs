@@ -0,0 +1,173 @@
+use super::{Pepper, SecretProvider};
+use crate::{Error, output::wipe};
+use windows_sys::Win32::Foundation::LocalFree;
+use windows_sys::Win32::Security::Cryptography::{
+    CRYPT_INTEGER_BLOB, CRYPTPROTECT_UI_FORBIDDEN, CryptProtectData, CryptUnprotectData,
+};
+
+/// Unwraps a DPAPI-protected pepper on Windows
+///
+/// The pepper is stored encrypted at rest (e.g. in a file or the registry) and only decrypted for
+/// the duration of a call. The blob is bound to the user or machine that protected it.
+#[derive(Clone, Debug)]
+pub struct DpapiSecretProvider {
+    current: DpapiEntry,
+    previous: Vec<DpapiEntry>,
+}
+
+#[derive(Clone, Debug)]
+struct DpapiEntry {
+    id: Vec<u8>,
+    blob: Vec<u8>,
+}
+
+impl DpapiSecretProvider {
+    /// ## Arguments
+    ///
+    /// - `id` - The key ID of the pepper stored in the hashes
+    /// - `blob` - The pepper as protected by `CryptProtectData`
+    pub fn new(id: impl Into<Vec<u8>>, blob: impl Into<Vec<u8>>) -> Self {
+        Self {
+            current: DpapiEntry {
+                id: id.into(),
+                blob: blob.into(),
+            },
+            previous: Vec::new(),
+        }
+    }
+
+    /// Same as [`DpapiSecretProvider::new`] with the blob read from a file
+    pub fn from_file(
+        id: impl Into<Vec<u8>>,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<Self, Error> {
+        let blob = std::fs::read(path).map_err(|e| Error::SecretProvider(e.to_string()))?;
+        Ok(Self::new(id, blob))
+    }
+
+    /// Adds a previous pepper that is still accepted for verification
+    pub fn with_previous(mut self, id: impl Into<Vec<u8>>, blob: impl Into<Vec<u8>>) -> Self {
+        self.previous.push(DpapiEntry {
+            id: id.into(),
+            blob: blob.into(),
+        });
+        self
+    }
+
+    /// Protects a pepper for the current user with `CryptProtectData`
+    ///
+    /// ## Returns
+    ///
+    /// The blob to pass to [`DpapiSecretProvider::new`]
+    pub fn protect(secret: &[u8]) -> Result<Vec<u8>, Error> {
+        let input = CRYPT_INTEGER_BLOB {
+            cbData: secret.len() as u32,
+            pbData: secret.as_ptr() as *mut u8,
+        };
+        let mut output = CRYPT_INTEGER_BLOB {
+            cbData: 0,
+            pbData: std::ptr::null_mut(),
+        };
+
+        let ok = unsafe {
+            CryptProtectData(
+                &input,
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                CRYPTPROTECT_UI_FORBIDDEN,
+                &mut output,
+            )
+        };
+
+        if ok == 0 {
+            return Err(dpapi_error());
+        }
+
+        let blob =
+            unsafe { std::slice::from_raw_parts(output.pbData, output.cbData as usize) }.to_vec();
+        unsafe { LocalFree(output.pbData as _) };
+        Ok(blob)
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &DpapiEntry> {
+        std::iter::once(&self.current).chain(&self.previous)
+    }
+}
+
+fn dpapi_error() -> Error {
+    Error::SecretProvider(format!("DPAPI: {}", std::io::Error::last_os_error()))
+}
+
+fn unprotect(blob: &[u8]) -> Result<Vec<u8>, Error> {
+    let input = CRYPT_INTEGER_BLOB {
+        cbData: blob.len() as u32,
+        pbData: blob.as_ptr() as *mut u8,
+    };
+    let mut output = CRYPT_INTEGER_BLOB {
+        cbData: 0,
+        pbData: std::ptr::null_mut(),
+    };
+
+    let ok = unsafe {
+        CryptUnprotectData(
+            &input,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            std::ptr::null(),
+            std::ptr::null(),
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+    };
+
+    if ok == 0 {
+        return Err(dpapi_error());
+    }
+
+    // Copy the secret out and wipe the buffer allocated by DPAPI before freeing it
+    let plain = unsafe { std::slice::from_raw_parts_mut(output.pbData, output.cbData as usize) };
+    let secret = plain.to_vec();
+    wipe(plain);
+    unsafe { LocalFree(output.pbData as _) };
+
+    Ok(secret)
+}
+
+impl SecretProvider for DpapiSecretProvider {
+    fn current(&self) -> Result<Pepper, Error> {
+        let secret = unprotect(&self.current.blob)?;
+        Ok(Pepper::new(self.current.id.clone(), secret))
+    }
+
+    fn get(&self, id: &[u8]) -> Result<Option<Pepper>, Error> {
+        match self.entries().find(|entry| entry.id == id) {
+            Some(entry) => Ok(Some(Pepper::new(entry.id.clone(), unprotect(&entry.blob)?))),
+            None => Ok(None),
+        }
+    }
+
+    fn all(&self) -> Result<Vec<Pepper>, Error> {
+        self.entries()
+            .map(|entry| Ok(Pepper::new(entry.id.clone(), unprotect(&entry.blob)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dpapi_secret_provider() {
+        let blob = DpapiSecretProvider::protect(b"dpapi secret").unwrap();
+        let provider = DpapiSecretProvider::new("d1", blob);
+        assert_eq!(provider.current().unwrap().secret(), b"dpapi secret");
+        assert!(
+            DpapiSecretProvider::new("d2", b"not a blob".to_vec())
+                .current()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,165 @@
+use super::{Pepper, SecretProvider};
+use crate::{Error, output::wipe};
+use std::ffi::CString;
+
+/// Loads the pepper from the Linux kernel keyring
+///
+/// Each pepper is a `user` key found through the keyrings of the process (thread, process, session
+/// and user keyrings), e.g. one added with `keyctl add user argon2:pepper:v1 <secret> @u`.
+/// The key is read on every call so the secret never stays in process memory.
+#[derive(Clone, Debug)]
+pub struct KeyringSecretProvider {
+    current: KeyringEntry,
+    previous: Vec<KeyringEntry>,
+}
+
+#[derive(Clone, Debug)]
+struct KeyringEntry {
+    id: Vec<u8>,
+    description: String,
+}
+
+impl KeyringSecretProvider {
+    /// ## Arguments
+    ///
+    /// - `id` - The key ID of the pepper stored in the hashes
+    /// - `description` - The description of the key in the keyring
+    pub fn new(id: impl Into<Vec<u8>>, description: impl Into<String>) -> Self {
+        Self {
+            current: KeyringEntry {
+                id: id.into(),
+                description: description.into(),
+            },
+            previous: Vec::new(),
+        }
+    }
+
+    /// Adds a previous pepper that is still accepted for verification
+    pub fn with_previous(mut self, id: impl Into<Vec<u8>>, description: impl Into<String>) -> Self {
+        self.previous.push(KeyringEntry {
+            id: id.into(),
+            description: description.into(),
+        });
+        self
+    }
+
+    fn entries(&self) -> impl Iterator<Item = &KeyringEntry> {
+        std::iter::once(&self.current).chain(&self.previous)
+    }
+}
+
+fn keyring_error(description: &str) -> Error {
+    Error::SecretProvider(format!(
+        "keyring key {}: {}",
+        description,
+        std::io::Error::last_os_error()
+    ))
+}
+
+fn read_key(description: &str) -> Result<Vec<u8>, Error> {
+    let key_type = c"user";
+    let desc = CString::new(description)
+        .map_err(|_| Error::SecretProvider("keyring description contains a NUL byte".into()))?;
+
+    let serial = unsafe {
+        libc::syscall(
+            libc::SYS_request_key,
+            key_type.as_ptr(),
+            desc.as_ptr(),
+            std::ptr::null::<libc::c_char>(),
+            0,
+        )
+    };
+
+    if serial < 0 {
+        return Err(keyring_error(description));
+    }
+
+    let mut buffer: Vec<u8> = Vec::new();
+
+    // The key may change size between the calls, retry until it fits in the buffer
+    loop {
+        let len = unsafe {
+            libc::syscall(
+                libc::SYS_keyctl,
+                libc::KEYCTL_READ,
+                serial,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+            )
+        };
+
+        if len < 0 {
+            wipe(&mut buffer);
+            return Err(keyring_error(description));
+        }
+
+        let len = len as usize;
+        if len <= buffer.len() {
+            buffer.truncate(len);
+            return Ok(buffer);
+        }
+
+        wipe(&mut buffer);
+        buffer = vec![0u8; len];
+    }
+}
+
+impl SecretProvider for KeyringSecretProvider {
+    fn current(&self) -> Result<Pepper, Error> {
+        let secret = read_key(&self.current.description)?;
+        Ok(Pepper::new(self.current.id.clone(), secret))
+    }
+
+    fn get(&self, id: &[u8]) -> Result<Option<Pepper>, Error> {
+        match self.entries().find(|entry| entry.id == id) {
+            Some(entry) => Ok(Some(Pepper::new(
+                entry.id.clone(),
+                read_key(&entry.description)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    fn all(&self) -> Result<Vec<Pepper>, Error> {
+        self.entries()
+            .map(|entry| Ok(Pepper::new(entry.id.clone(), read_key(&entry.description)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Argon2, pepper::KeyedArgon2};
+
+    #[test]
+    fn test_keyring_secret_provider() {
+        let description = c"argon2-rs:test:pepper";
+        let secret = b"keyring secret";
+
+        let serial = unsafe {
+            libc::syscall(
+                libc::SYS_add_key,
+                c"user".as_ptr(),
+                description.as_ptr(),
+                secret.as_ptr(),
+                secret.len(),
+                libc::KEY_SPEC_PROCESS_KEYRING,
+            )
+        };
+        assert!(serial > 0, "{}", std::io::Error::last_os_error());
+
+        let provider = KeyringSecretProvider::new("k1", "argon2-rs:test:pepper");
+        assert_eq!(provider.current().unwrap().secret(), secret);
+
+        let keyed = KeyedArgon2::with_provider(Argon2::new(1024, 1, 1), provider);
+        let stored = keyed
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+        assert!(keyed.verify_encoded("password", &stored).unwrap());
+
+        let missing = KeyringSecretProvider::new("k2", "argon2-rs:test:missing");
+        assert!(missing.current().is_err());
+    }
+}
@@ -0,0 +1,369 @@
+use super::{Algorithm, Argon2, Error, Version};
+
+/// The B64 alphabet used by the PHC string format (standard Base64 without padding)
+const B64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes the given bytes in the unpadded B64 form used by the PHC string format
+pub(crate) fn b64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let n = (b0 << 16) | (b1 << 8) | b2;
+
+        out.push(B64_ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(B64_ALPHABET[(n >> 12) as usize & 63] as char);
+
+        if chunk.len() > 1 {
+            out.push(B64_ALPHABET[(n >> 6) as usize & 63] as char);
+        }
+
+        if chunk.len() > 2 {
+            out.push(B64_ALPHABET[n as usize & 63] as char);
+        }
+    }
+
+    out
+}
+
+fn b64_value(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some((c - b'A') as u32),
+        b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+        b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Decodes unpadded B64 as used by the PHC string format
+///
+/// Like the decoder of the C implementation it rejects a final chunk whose unused bits are not
+/// zero, so the same bytes have exactly one encoding.
+pub(crate) fn b64_decode(s: &str) -> Option<Vec<u8>> {
+    let bytes = s.as_bytes();
+
+    if bytes.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() * 3 / 4);
+
+    for chunk in bytes.chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            n |= b64_value(*c)? << (18 - 6 * i);
+        }
+
+        // A final chunk of 2 or 3 characters carries 4 or 2 bits beyond its bytes
+        let unused = match chunk.len() {
+            2 => 0xffff,
+            3 => 0xff,
+            _ => 0,
+        };
+        if n & unused != 0 {
+            return None;
+        }
+
+        out.push((n >> 16) as u8);
+
+        if chunk.len() > 2 {
+            out.push((n >> 8) as u8);
+        }
+
+        if chunk.len() > 3 {
+            out.push(n as u8);
+        }
+    }
+
+    Some(out)
+}
+
+impl Algorithm {
+    /// The identifier of the algorithm as used in the PHC string format
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Algorithm::Argon2d => "argon2d",
+            Algorithm::Argon2i => "argon2i",
+            Algorithm::Argon2id => "argon2id",
+        }
+    }
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Algorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "argon2d" => Ok(Algorithm::Argon2d),
+            "argon2i" => Ok(Algorithm::Argon2i),
+            "argon2id" => Ok(Algorithm::Argon2id),
+            _ => Err(Error::InvalidPhcString("unknown algorithm")),
+        }
+    }
+}
+
+impl Version {
+    /// The numeric value of the version as used in the PHC string format
+    pub fn as_u32(&self) -> u32 {
+        *self as u32
+    }
+
+    /// The version for the given numeric value
+    pub fn from_u32(version: u32) -> Result<Self, Error> {
+        match version {
+            0x10 => Ok(Version::V0x10),
+            0x13 => Ok(Version::V0x13),
+            _ => Err(Error::InvalidPhcString("unknown version")),
+        }
+    }
+}
+
+/// Displays the version in hex, e.g. `0x13`
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#x}", self.as_u32())
+    }
+}
+
+/// Parses the version either in hex (`0x13`) or in decimal as in PHC strings (`19`)
+impl std::str::FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let version = match s.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => s.parse(),
+        };
+
+        version
+            .map_err(|_| Error::InvalidPhcString("unknown version"))
+            .and_then(Version::from_u32)
+    }
+}
+
+/// Encodes the parameters, salt and hash into a PHC string
+///
+/// `$argon2id$v=19$m=65536,t=2,p=1$<salt>$<hash>`
+///
+/// The pre-hash threshold, the normalization form, the key ID of the pepper and the associated
+/// data are appended as the `ph`, `norm`, `keyid` and `data` parameters if present.
+pub(crate) fn encode(argon2: &Argon2, keyid: Option<&[u8]>, salt: &[u8], hash: &[u8]) -> String {
+    let mut params = format!(
+        "m={},t={},p={}",
+        argon2.m_cost, argon2.t_cost, argon2.p_cost
+    );
+
+    if let Some(threshold) = argon2.prehash {
+        params.push_str(&format!(",ph={threshold}"));
+    }
+
+    if let Some(normalization) = argon2.normalization {
+        params.push_str(",norm=");
+        params.push_str(normalization.as_str());
+    }
+
+    if let Some(keyid) = keyid {
+        params.push_str(",keyid=");
+        params.push_str(&b64_encode(keyid));
+    }
+
+    if !argon2.ad.is_empty() {
+        params.push_str(",data=");
+        params.push_str(&b64_encode(&argon2.ad));
+    }
+
+    format!(
+        "${}$v={}${}${}${}",
+        argon2.algorithm.as_str(),
+        argon2.version.as_u32(),
+        params,
+        b64_encode(salt),
+        b64_encode(hash)
+    )
+}
+
+/// The contents of a decoded PHC string
+pub(crate) struct Decoded {
+    pub argon2: Argon2,
+    pub keyid: Option<Vec<u8>>,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+fn parse_u32(value: &str) -> Result<u32, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::InvalidPhcString("invalid parameter value"))
+}
+
+/// Decodes a PHC string of the form `$argon2id$v=19$m=65536,t=2,p=1$<salt>$<hash>`
+pub(crate) fn decode(encoded: &str) -> Result<Decoded, Error> {
+    let mut fields = encoded.split('$');
+
+    if fields.next() != Some("") {
+        return Err(Error::InvalidPhcString("missing leading '$'"));
+    }
+
+    let algorithm = fields.next().unwrap_or_default().parse()?;
+
+    let mut field = fields.next();
+
+    // The version is optional, hashes produced before v1.3 do not carry it
+    let version = match field.and_then(|f| f.strip_prefix("v=")) {
+        Some(v) => {
+            field = fields.next();
+            Version::from_u32(parse_u32(v)?)?
+        }
+        None => Version::V0x10,
+    };
+
+    let params = field.ok_or(Error::InvalidPhcString("missing parameters"))?;
+    let (mut m_cost, mut t_cost, mut p_cost) = (None, None, None);
+    let (mut prehash, mut normalization, mut keyid, mut ad) = (None, None, None, None);
+
+    for param in params.split(',') {
+        let (key, value) = param
+            .split_once('=')
+            .ok_or(Error::InvalidPhcString("malformed parameter"))?;
+
+        let duplicate = match key {
+            "m" => m_cost.replace(parse_u32(value)?).is_some(),
+            "t" => t_cost.replace(parse_u32(value)?).is_some(),
+            "p" => p_cost.replace(parse_u32(value)?).is_some(),
+            "ph" => prehash.replace(parse_u32(value)?).is_some(),
+            "norm" => normalization.replace(value.parse()?).is_some(),
+            "keyid" => {
+                let id = b64_decode(value).ok_or(Error::InvalidPhcString("invalid keyid"))?;
+                keyid.replace(id).is_some()
+            }
+            "data" => {
+                let data = b64_decode(value).ok_or(Error::InvalidPhcString("invalid data"))?;
+                ad.replace(data).is_some()
+            }
+            _ => return Err(Error::InvalidPhcString("unknown parameter")),
+        };
+
+        if duplicate {
+            return Err(Error::InvalidPhcString("duplicate parameter"));
+        }
+    }
+
+    let salt = fields
+        .next()
+        .and_then(b64_decode)
+        .ok_or(Error::InvalidPhcString("missing or invalid salt"))?;
+
+    let hash = fields
+        .next()
+        .and_then(b64_decode)
+        .ok_or(Error::InvalidPhcString("missing or invalid hash"))?;
+
+    if fields.next().is_some() {
+        return Err(Error::InvalidPhcString("trailing data"));
+    }
+
+    let argon2 = Argon2 {
+        m_cost: m_cost.ok_or(Error::InvalidPhcString("missing m_cost"))?,
+        t_cost: t_cost.ok_or(Error::InvalidPhcString("missing t_cost"))?,
+        p_cost: p_cost.ok_or(Error::InvalidPhcString("missing p_cost"))?,
+        hash_length: hash.len() as u64,
+        algorithm,
+        version,
+        ad: ad.unwrap_or_default(),
+        prehash,
+        normalization,
+        ..Default::default()
+    };
+
+    Ok(Decoded {
+        argon2,
+        keyid,
+        salt,
+        hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_b64_encode() {
+        assert_eq!(b64_encode(b""), "");
+        assert_eq!(b64_encode(b"f"), "Zg");
+        assert_eq!(b64_encode(b"fo"), "Zm8");
+        assert_eq!(b64_encode(b"foo"), "Zm9v");
+        assert_eq!(b64_encode(b"foob"), "Zm9vYg");
+    }
+
+    #[test]
+    fn test_b64_roundtrip() {
+        for len in 0..16 {
+            let bytes: Vec<u8> = (0..len).map(|i: u8| i.wrapping_mul(37)).collect();
+            assert_eq!(b64_decode(&b64_encode(&bytes)).unwrap(), bytes);
+        }
+        assert!(b64_decode("Z").is_none());
+        assert!(b64_decode("Zm9v=").is_none());
+
+        // The unused bits of the final chunk must be zero
+        assert_eq!(b64_decode("c2FsdA").unwrap(), b"salt");
+        assert!(b64_decode("c2FsdB").is_none());
+        assert_eq!(b64_decode("Zm8").unwrap(), b"fo");
+        assert!(b64_decode("Zm9").is_none());
+        assert!(matches!(
+            decode("$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHR$aGFzaGhhc2g"),
+            Err(Error::InvalidPhcString(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode() {
+        let decoded = decode("$argon2i$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaGhhc2g").unwrap();
+        assert_eq!(decoded.argon2.algorithm, Algorithm::Argon2i);
+        assert_eq!(decoded.argon2.version, Version::V0x13);
+        assert_eq!(decoded.argon2.m_cost, 4096);
+        assert_eq!(decoded.argon2.t_cost, 3);
+        assert_eq!(decoded.argon2.p_cost, 1);
+        assert_eq!(decoded.salt, b"somesalt");
+        assert_eq!(decoded.hash, b"hashhash");
+
+        let decoded = decode("$argon2id$v=19$m=4096,t=3,p=1,data=dXNlcjE$c29tZXNhbHQ$aGFzaGhhc2g");
+        assert_eq!(decoded.unwrap().argon2.ad, b"user1");
+
+        let decoded = decode("$argon2id$v=19$m=4096,t=3,p=1,ph=1024$c29tZXNhbHQ$aGFzaGhhc2g");
+        assert_eq!(decoded.unwrap().argon2.prehash, Some(1024));
+
+        let decoded = decode("$argon2id$v=19$m=4096,t=3,p=1,norm=nfkc$c29tZXNhbHQ$aGFzaGhhc2g");
+        assert_eq!(
+            decoded.unwrap().argon2.normalization,
+            Some(crate::normalize::Normalization::Nfkc)
+        );
+        assert!(decode("$argon2id$v=19$m=4096,t=3,p=1,norm=nfd$c29tZXNhbHQ$aGFzaGhhc2g").is_err());
+
+        assert!(decode("$argon2x$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaGhhc2g").is_err());
+        assert!(decode("$argon2i$v=19$m=4096,t=3$c29tZXNhbHQ$aGFzaGhhc2g").is_err());
+        assert!(decode("$argon2i$v=19$m=4096,t=3,p=1$c29tZXNhbHQ").is_err());
+    }
+
+    #[test]
+    fn test_algorithm_version_from_str() {
+        assert_eq!("argon2d".parse::<Algorithm>().unwrap(), Algorithm::Argon2d);
+        assert_eq!(Algorithm::Argon2id.to_string(), "argon2id");
+        assert!("argon2".parse::<Algorithm>().is_err());
+
+        assert_eq!("0x13".parse::<Version>().unwrap(), Version::V0x13);
+        assert_eq!("16".parse::<Version>().unwrap(), Version::V0x10);
+        assert_eq!(Version::V0x13.to_string(), "0x13");
+        assert!("0x14".parse::<Version>().is_err());
+    }
+}
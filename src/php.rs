@@ -0,0 +1,153 @@
+use super::{Algorithm, Argon2, Error, phc};
+
+/// `PASSWORD_ARGON2_DEFAULT_MEMORY_COST` since PHP 7.3 (64 MiB in kibibytes)
+pub const DEFAULT_MEMORY_COST: u32 = 65536;
+
+/// `PASSWORD_ARGON2_DEFAULT_TIME_COST` since PHP 7.3
+pub const DEFAULT_TIME_COST: u32 = 4;
+
+/// `PASSWORD_ARGON2_DEFAULT_THREADS` since PHP 7.3
+pub const DEFAULT_THREADS: u32 = 1;
+
+/// The length of the hash embedded by `password_hash`
+pub const HASH_LENGTH: u64 = 32;
+
+/// The `Argon2` parameters `password_hash` uses by default for the given algorithm
+///
+/// `PASSWORD_ARGON2I` maps to [`Algorithm::Argon2i`] and `PASSWORD_ARGON2ID` to
+/// [`Algorithm::Argon2id`]. PHP 7.2 used `m=1024,t=2,p=2`, hashes created with those defaults are
+/// still verified since the parameters are read from the hash.
+pub fn params(algorithm: Algorithm) -> Argon2 {
+    Argon2::new(DEFAULT_MEMORY_COST, DEFAULT_TIME_COST, DEFAULT_THREADS)
+        .with_algorithm(algorithm)
+        .with_hash_length(HASH_LENGTH)
+}
+
+/// Equivalent of PHP's `password_verify` for Argon2 hashes
+///
+/// Handles the variations found in hashes created by the different PHP builds:
+///
+/// - `$argon2i$` hashes (the only variant before PHP 7.3) and `$argon2id$` hashes
+/// - Hashes without the `v=` segment created by builds linked against libargon2 older than 1.3,
+///   which are verified as version `0x10`
+/// - Trailing whitespace or a NUL terminator left over from fixed-width database columns
+///
+/// Like PHP it returns `false` instead of an error for hashes that are not Argon2 PHC strings
+/// (e.g. `$2y$` bcrypt hashes), so it can be called on every row of a mixed user table.
+///
+/// ## Example
+///
+/// ```
+/// # #[cfg(not(feature = "argon2id-only"))]
+/// # {
+/// use argon2_rs::php;
+///
+/// // The Argon2i example from the PHP manual
+/// let hash = "$argon2i$v=19$m=1024,t=2,p=2$YzJBSzV4TUhkMzc3d3laeg$zqU/1IN0/AogfP4cmSJI1vc8lpXRW9/S0sYY2i2jHT0";
+/// assert!(php::password_verify("rasmuslerdorf", hash).unwrap());
+/// # }
+/// ```
+pub fn password_verify(password: impl AsRef<[u8]>, hash: &str) -> Result<bool, Error> {
+    let hash = hash.trim_end_matches(|c: char| c.is_ascii_whitespace() || c == '\0');
+
+    match Argon2::verify_encoded(password, hash) {
+        Err(Error::InvalidPhcString(_)) => Ok(false),
+        result => result,
+    }
+}
+
+/// Equivalent of PHP's `password_needs_rehash` with the default options
+///
+/// Returns `true` if the hash was not created with [`params`] for the given algorithm, including
+/// hashes created with the PHP 7.2 defaults and hashes that are not Argon2 at all.
+pub fn password_needs_rehash(hash: &str, algorithm: Algorithm) -> bool {
+    match phc::decode(hash) {
+        Ok(decoded) => !decoded.argon2.same_parameters(&params(algorithm)),
+        Err(_) => true,
+    }
+}
+
+/// Whether the hash was created by a PHP build linked against libargon2 older than 1.3
+///
+/// These hashes omit the `v=` segment and use version `0x10`.
+pub fn is_legacy_hash(hash: &str) -> bool {
+    phc::decode(hash).is_ok() && !hash.contains("$v=")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The Argon2i example from the PHP manual, created by PHP 7.2 with its defaults
+    const PHP_MANUAL_ARGON2I: &str = "$argon2i$v=19$m=1024,t=2,p=2$YzJBSzV4TUhkMzc3d3laeg$zqU/1IN0/AogfP4cmSJI1vc8lpXRW9/S0sYY2i2jHT0";
+
+    #[cfg(not(feature = "argon2id-only"))]
+    #[test]
+    fn test_password_verify_matrix() {
+        let salt = b"0123456789abcdef";
+
+        let argon2i = Argon2::new(1024, 2, 1)
+            .with_algorithm(Algorithm::Argon2i)
+            .with_hash_length(HASH_LENGTH);
+        let argon2id = argon2i.clone().with_algorithm(Algorithm::Argon2id);
+        let legacy = argon2i
+            .clone()
+            .with_version(crate::Version::V0x10)
+            .hash_password_encoded("password", salt)
+            .unwrap()
+            .replace("$v=16$", "$");
+
+        let matrix = [
+            (PHP_MANUAL_ARGON2I.to_string(), "rasmuslerdorf", true),
+            (PHP_MANUAL_ARGON2I.to_string(), "rasmuslerdorF", false),
+            (format!("{PHP_MANUAL_ARGON2I}\n"), "rasmuslerdorf", true),
+            (format!("{PHP_MANUAL_ARGON2I}\0\0"), "rasmuslerdorf", true),
+            (
+                argon2i.hash_password_encoded("password", salt).unwrap(),
+                "password",
+                true,
+            ),
+            (
+                argon2id.hash_password_encoded("password", salt).unwrap(),
+                "password",
+                true,
+            ),
+            (
+                argon2id.hash_password_encoded("password", salt).unwrap(),
+                "wrong",
+                false,
+            ),
+            (legacy.clone(), "password", true),
+            (legacy.clone(), "wrong", false),
+            (
+                "$2y$10$.vGA1O9wmRjrwAVXD98HNOgsNpDczlqm3Jq7KnEd1rVAGv3Fykk1a".to_string(),
+                "rasmuslerdorf",
+                false,
+            ),
+        ];
+
+        for (hash, password, expected) in matrix {
+            assert_eq!(
+                password_verify(password, &hash).unwrap(),
+                expected,
+                "{hash} with {password}"
+            );
+        }
+
+        assert!(is_legacy_hash(&legacy));
+        assert!(!is_legacy_hash(PHP_MANUAL_ARGON2I));
+    }
+
+    #[test]
+    fn test_password_needs_rehash() {
+        assert!(password_needs_rehash(
+            PHP_MANUAL_ARGON2I,
+            Algorithm::Argon2i
+        ));
+        assert!(password_needs_rehash("$2y$10$abc", Algorithm::Argon2id));
+
+        let current = "$argon2id$v=19$m=65536,t=4,p=1$c29tZXNhbHRzb21lc2FsdA$aGFzaGhhc2hoYXNoaGFzaGhhc2hoYXNoaGFzaGhhc2g";
+        assert!(!password_needs_rehash(current, Algorithm::Argon2id));
+        assert!(password_needs_rehash(current, Algorithm::Argon2i));
+    }
+}
@@ -0,0 +1,211 @@
+use super::{Algorithm, Argon2, Error};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Restricts the algorithms every hash and verification in this process may use
+///
+/// Argon2d is open to side-channel attacks and Argon2i is weaker against GPU cracking, an
+/// application that only ever means to use Argon2id can rule them out so a misconfiguration or a
+/// crafted stored hash cannot select them. Hashing or verifying with any other algorithm then
+/// fails with [`Error::PolicyViolation`], and so does [`Argon2::validate`].
+///
+/// All algorithms are allowed by default, only Argon2id with the `argon2id-only` feature.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, Algorithm, error::Error, policy};
+///
+/// policy::set_allowed_algorithms(&[Algorithm::Argon2id]);
+///
+/// let argon2i = Argon2::new(1024, 2, 1).with_algorithm(Algorithm::Argon2i);
+/// let result = argon2i.hash_password("password", b"somesaltsomesalt");
+/// assert!(matches!(result, Err(Error::PolicyViolation(_))));
+///
+/// // Verifying a legacy hash needs the algorithm allowed again
+/// policy::set_allowed_algorithms(&[Algorithm::Argon2i, Algorithm::Argon2id]);
+/// assert!(argon2i.hash_password("password", b"somesaltsomesalt").is_ok());
+/// ```
+pub fn set_allowed_algorithms(algorithms: &[Algorithm]) {
+    let mask = algorithms
+        .iter()
+        .fold(0, |mask, &algorithm| mask | bit(algorithm));
+    ALLOWED_ALGORITHMS.store(mask, Ordering::Relaxed);
+}
+
+/// The algorithms allowed by [`set_allowed_algorithms`]
+pub fn allowed_algorithms() -> Vec<Algorithm> {
+    let mask = ALLOWED_ALGORITHMS.load(Ordering::Relaxed);
+    [Algorithm::Argon2d, Algorithm::Argon2i, Algorithm::Argon2id]
+        .into_iter()
+        .filter(|&algorithm| mask & bit(algorithm) != 0)
+        .collect()
+}
+
+#[cfg(not(feature = "argon2id-only"))]
+static ALLOWED_ALGORITHMS: AtomicU8 = AtomicU8::new(0b111);
+#[cfg(feature = "argon2id-only")]
+static ALLOWED_ALGORITHMS: AtomicU8 = AtomicU8::new(1 << Algorithm::Argon2id as u8);
+
+fn bit(algorithm: Algorithm) -> u8 {
+    1 << algorithm as u8
+}
+
+/// Rejects an algorithm that is not allowed by [`set_allowed_algorithms`]
+pub(crate) fn check_algorithm(algorithm: Algorithm) -> Result<(), Error> {
+    if ALLOWED_ALGORITHMS.load(Ordering::Relaxed) & bit(algorithm) == 0 {
+        return Err(Error::PolicyViolation(format!(
+            "algorithm {algorithm} is not allowed in this process"
+        )));
+    }
+    Ok(())
+}
+
+/// Minimum requirements a set of Argon2 parameters must meet
+///
+/// Use it to enforce an organizational floor across services and to detect stored hashes that were
+/// created with weaker parameters.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, Algorithm, policy::HashPolicy};
+///
+/// let policy = HashPolicy::new(256_000, 8).with_allowed_algorithms(vec![Algorithm::Argon2id]);
+///
+/// assert!(policy.validate(&Argon2::fast()).is_ok());
+/// assert!(policy.validate(&Argon2::new(64_000, 8, 1)).is_err());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HashPolicy {
+    /// The minimum memory cost in kibibytes
+    pub min_m_cost: u32,
+    /// The minimum iteration cost
+    pub min_t_cost: u32,
+    /// The algorithms that are allowed
+    pub allowed_algorithms: Vec<Algorithm>,
+    /// The minimum length of the hash in bytes
+    pub min_hash_length: u64,
+}
+
+impl HashPolicy {
+    /// Create a new policy with the given minimum costs
+    ///
+    /// By default all algorithms are allowed and the hash must be at least `16 bytes` long.
+    ///
+    /// ## Arguments
+    ///
+    /// - `min_m_cost` - The minimum memory cost in kibibytes
+    /// - `min_t_cost` - The minimum iteration cost
+    pub fn new(min_m_cost: u32, min_t_cost: u32) -> Self {
+        Self {
+            min_m_cost,
+            min_t_cost,
+            allowed_algorithms: vec![Algorithm::Argon2d, Algorithm::Argon2i, Algorithm::Argon2id],
+            min_hash_length: 16,
+        }
+    }
+
+    pub fn with_allowed_algorithms(mut self, allowed_algorithms: Vec<Algorithm>) -> Self {
+        self.allowed_algorithms = allowed_algorithms;
+        self
+    }
+
+    pub fn with_min_hash_length(mut self, min_hash_length: u64) -> Self {
+        self.min_hash_length = min_hash_length;
+        self
+    }
+
+    /// Checks that the given parameters meet this policy
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::PolicyViolation`] describing the first requirement that is not met
+    pub fn validate(&self, argon2: &Argon2) -> Result<(), Error> {
+        if argon2.m_cost < self.min_m_cost {
+            return Err(Error::PolicyViolation(format!(
+                "m_cost {} is below the minimum of {}",
+                argon2.m_cost, self.min_m_cost
+            )));
+        }
+
+        if argon2.t_cost < self.min_t_cost {
+            return Err(Error::PolicyViolation(format!(
+                "t_cost {} is below the minimum of {}",
+                argon2.t_cost, self.min_t_cost
+            )));
+        }
+
+        if !self.allowed_algorithms.contains(&argon2.algorithm) {
+            return Err(Error::PolicyViolation(format!(
+                "algorithm {} is not allowed",
+                argon2.algorithm.as_str()
+            )));
+        }
+
+        if argon2.hash_length < self.min_hash_length {
+            return Err(Error::PolicyViolation(format!(
+                "hash length {} is below the minimum of {}",
+                argon2.hash_length, self.min_hash_length
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the parameters of a stored hash meet this policy
+    ///
+    /// ## Arguments
+    ///
+    /// - `encoded` - The hash in the PHC string format
+    ///
+    /// ## Returns
+    ///
+    /// `false` if the hash is out of policy, an error if the string could not be decoded
+    pub fn satisfied_by(&self, encoded: &str) -> Result<bool, Error> {
+        let argon2 = Argon2::from_encoded(encoded)?;
+        Ok(self.validate(&argon2).is_ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_satisfied_by() {
+        let policy = HashPolicy::new(4096, 3).with_allowed_algorithms(vec![Algorithm::Argon2id]);
+
+        let encoded = "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaGhhc2hoYXNoaGFzaA";
+        assert!(policy.satisfied_by(encoded).unwrap());
+
+        let encoded = "$argon2i$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaGhhc2hoYXNoaGFzaA";
+        assert!(!policy.satisfied_by(encoded).unwrap());
+
+        let encoded = "$argon2id$v=19$m=4096,t=2,p=1$c29tZXNhbHQ$aGFzaGhhc2hoYXNoaGFzaA";
+        assert!(!policy.satisfied_by(encoded).unwrap());
+
+        let encoded = "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaGhhc2g";
+        assert!(!policy.satisfied_by(encoded).unwrap());
+    }
+
+    #[cfg(feature = "argon2id-only")]
+    #[test]
+    fn test_argon2id_only() {
+        assert_eq!(allowed_algorithms(), [Algorithm::Argon2id]);
+
+        let salt = b"somesaltsomesalt";
+        for algorithm in [Algorithm::Argon2d, Algorithm::Argon2i] {
+            let argon2 = Argon2::new(1024, 2, 1).with_algorithm(algorithm);
+            assert!(matches!(
+                argon2.hash_password("password", salt),
+                Err(Error::PolicyViolation(_))
+            ));
+            assert!(matches!(argon2.validate(), Err(Error::PolicyViolation(_))));
+        }
+        assert!(
+            Argon2::new(1024, 2, 1)
+                .hash_password("password", salt)
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use super::{
+    Argon2, Error,
+    allocator::{BLOCK_SIZE, Block, MemoryAllocator},
+    backend, constant_time_eq, output_buffer, phc,
+};
+
+mod pool;
+pub(crate) use pool::LanePool;
+#[cfg(all(feature = "thread-priority", target_os = "linux"))]
+mod sched;
+#[cfg(all(feature = "thread-priority", target_os = "linux"))]
+pub use sched::ThreadScheduling;
+
+/// Hashes with working memory that is allocated once and reused across calls
+///
+/// Every call to [`Argon2::hash_password`] allocates and page-faults `m_cost` KiB of fresh
+/// memory, which dominates the latency with memory costs in the GiB range. A `Hasher` allocates
+/// the working memory up front and hands it to the C implementation on every call. The memory is
+/// wiped by the C implementation after each hash.
+///
+/// A `Hasher` hashes one password at a time, keep one per worker thread to hash concurrently.
+///
+/// With [`Hasher::with_thread_pool`] the lanes are filled by threads the hasher keeps alive,
+/// instead of threads the C implementation spawns and joins for every slice of every pass.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, prealloc::Hasher};
+///
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let mut hasher = Hasher::new(argon2.clone());
+///
+/// let encoded = hasher.hash_password_encoded("password", b"somesaltsomesalt").unwrap();
+/// assert!(hasher.verify_encoded("password", &encoded).unwrap());
+/// assert!(Argon2::verify_encoded("password", &encoded).unwrap());
+/// ```
+pub struct Hasher {
+    argon2: Argon2,
+    memory: Reused,
+    pool: Option<LanePool>,
+}
+
+/// Hands the same memory to every hash, only replacing it when it is too small
+struct Reused(Option<Box<[Block]>>);
+
+impl Reused {
+    fn reserve(&mut self, blocks: usize) {
+        if self.0.as_ref().is_none_or(|memory| memory.len() < blocks) {
+            // Freed first so both never have to fit, a failure shows up as `Error::OutOfMemory`
+            // when hashing
+            self.0 = None;
+            self.0 = Block::try_alloc(blocks);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0
+            .as_ref()
+            .map_or(0, |memory| memory.len() * BLOCK_SIZE)
+    }
+}
+
+impl MemoryAllocator for Reused {
+    fn allocate(&mut self, blocks: usize) -> Option<Box<[Block]>> {
+        self.0.take().filter(|memory| memory.len() >= blocks)
+    }
+
+    fn free(&mut self, memory: Box<[Block]>) {
+        self.0 = Some(memory);
+    }
+}
+
+impl Hasher {
+    /// Creates a hasher and allocates the working memory for the given parameters
+    pub fn new(argon2: Argon2) -> Self {
+        let mut memory = Reused(None);
+        memory.reserve(argon2.memory_blocks());
+        Self {
+            argon2,
+            memory,
+            pool: None,
+        }
+    }
+
+    /// Fills the lanes on a pool of `threads` threads owned by the hasher
+    ///
+    /// The threads are spawned here and joined when the hasher is dropped, which removes the
+    /// thread creation overhead from every hash with a parallelism cost above 1. The pool is used
+    /// for every hash of this hasher, [`Argon2::threads`] is ignored.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, prealloc::Hasher};
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 4);
+    /// let mut hasher = Hasher::new(argon2.clone()).with_thread_pool(4);
+    ///
+    /// let hash = hasher.hash_password("password", b"somesaltsomesalt").unwrap();
+    /// assert_eq!(hash, argon2.hash_password("password", b"somesaltsomesalt").unwrap());
+    /// ```
+    pub fn with_thread_pool(mut self, threads: usize) -> Self {
+        self.pool = Some(LanePool::new(threads));
+        self
+    }
+
+    /// The number of threads of the pool, `None` if the C implementation spawns its own
+    pub fn pool_threads(&self) -> Option<usize> {
+        self.pool.as_ref().map(LanePool::threads)
+    }
+
+    /// The parameters this hasher hashes with
+    pub fn argon2(&self) -> &Argon2 {
+        &self.argon2
+    }
+
+    /// The size of the working memory in bytes
+    ///
+    /// The memory grows when verifying a hash with a larger memory cost and is never shrunk.
+    pub fn memory_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// See [`Argon2::hash_password`]
+    pub fn hash_password(
+        &mut self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut hash = output_buffer(self.argon2.hash_len()?)?;
+        Self::hash_into(
+            &self.argon2,
+            password.as_ref(),
+            salt,
+            &mut hash,
+            &mut self.memory,
+            self.pool.as_ref(),
+        )?;
+        Ok(hash)
+    }
+
+    /// See [`Argon2::hash_password_encoded`]
+    pub fn hash_password_encoded(
+        &mut self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<String, Error> {
+        let hash = self.hash_password(password, salt)?;
+        Ok(phc::encode(&self.argon2, None, salt, &hash))
+    }
+
+    /// See [`Argon2::verify_encoded`]
+    ///
+    /// The hash is verified with its own parameters, which need not match those of this hasher.
+    pub fn verify_encoded(
+        &mut self,
+        password: impl AsRef<[u8]>,
+        encoded: &str,
+    ) -> Result<bool, Error> {
+        let decoded = phc::decode(encoded)?;
+
+        if decoded.keyid.is_some() {
+            return Err(Error::PepperRequired);
+        }
+
+        self.memory.reserve(decoded.argon2.memory_blocks());
+
+        let mut hash = output_buffer(decoded.hash.len())?;
+        Self::hash_into(
+            &decoded.argon2,
+            password.as_ref(),
+            &decoded.salt,
+            &mut hash,
+            &mut self.memory,
+            self.pool.as_ref(),
+        )?;
+
+        Ok(constant_time_eq(&hash, &decoded.hash))
+    }
+
+    fn hash_into(
+        argon2: &Argon2,
+        password: &[u8],
+        salt: &[u8],
+        out: &mut [u8],
+        memory: &mut Reused,
+        pool: Option<&LanePool>,
+    ) -> Result<(), Error> {
+        match pool {
+            Some(pool) => backend::hash_with_pool(argon2, password, salt, &[], out, memory, pool),
+            None => backend::hash_with_allocator(argon2, password, salt, &[], out, memory),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hasher_matches_argon2() {
+        let salt = b"somesaltsomesalt";
+
+        for argon2 in [
+            Argon2::new(1024, 2, 1),
+            Argon2::new(2048, 1, 4),
+            Argon2::new(30, 1, 3),
+        ] {
+            let mut hasher = Hasher::new(argon2.clone());
+            assert_eq!(hasher.memory_size(), argon2.memory_blocks() * BLOCK_SIZE);
+
+            for password in ["password", "another password"] {
+                assert_eq!(
+                    hasher.hash_password(password, salt).unwrap(),
+                    argon2.hash_password(password, salt).unwrap()
+                );
+            }
+        }
+    }
+
+    #[cfg(not(feature = "argon2id-only"))]
+    #[test]
+    fn test_hasher_thread_pool() {
+        let salt = b"somesaltsomesalt";
+
+        for threads in [1, 3, 4] {
+            let mut hasher = Hasher::new(Argon2::new(2048, 2, 4)).with_thread_pool(threads);
+            assert_eq!(hasher.pool_threads(), Some(threads));
+
+            for argon2 in [
+                Argon2::new(2048, 2, 4),
+                Argon2::new(30, 1, 3).with_version(crate::Version::V0x10),
+                Argon2::new(1024, 3, 1).with_algorithm(crate::Algorithm::Argon2d),
+            ] {
+                let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+                assert!(hasher.verify_encoded("password", &encoded).unwrap());
+                assert!(!hasher.verify_encoded("wrong", &encoded).unwrap());
+            }
+
+            assert_eq!(
+                hasher.hash_password("password", salt).unwrap(),
+                Argon2::new(2048, 2, 4)
+                    .hash_password("password", salt)
+                    .unwrap()
+            );
+        }
+
+        // Invalid inputs fail before the pool is involved
+        let mut hasher = Hasher::new(Argon2::new(1024, 2, 2)).with_thread_pool(2);
+        assert!(hasher.hash_password("password", b"short").is_err());
+        assert!(hasher.hash_password("password", salt).is_ok());
+    }
+
+    #[test]
+    fn test_hasher_verify_grows_memory() {
+        let mut hasher = Hasher::new(Argon2::new(1024, 2, 1));
+        let size = hasher.memory_size();
+
+        let encoded = Argon2::new(4096, 1, 2)
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+        assert!(hasher.verify_encoded("password", &encoded).unwrap());
+        assert!(!hasher.verify_encoded("wrong", &encoded).unwrap());
+        assert!(hasher.memory_size() > size);
+
+        // Still hashes with its own parameters afterwards
+        let encoded = hasher
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+        assert!(encoded.contains("m=1024,t=2,p=1"));
+        assert!(Argon2::verify_encoded("password", &encoded).unwrap());
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread::JoinHandle;
+
+/// A lane for a worker to fill, the function outlives the job as [`LanePool::run`] waits for
+/// every job it sends
+struct Job {
+    fill: *const (dyn Fn(u32) + Sync),
+    lane: u32,
+}
+
+unsafe impl Send for Job {}
+
+struct Worker {
+    jobs: Option<Sender<Job>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Persistent threads that fill the segments of the lanes
+///
+/// The C implementation spawns and joins a thread per lane for each of the four slices of every
+/// pass. The pool keeps its threads alive across hashes and hands them the segments instead.
+pub(crate) struct LanePool {
+    workers: Vec<Worker>,
+    done: Receiver<()>,
+}
+
+impl LanePool {
+    pub(crate) fn new(threads: usize) -> Self {
+        Self::with_init(threads, Arc::new(|| {}))
+    }
+
+    /// Spawns the threads, each calls `init` once before taking any job
+    pub(crate) fn with_init(threads: usize, init: Arc<dyn Fn() + Send + Sync>) -> Self {
+        let (done_sender, done) = channel();
+
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let (jobs, receiver) = channel::<Job>();
+                let done = done_sender.clone();
+                let init = init.clone();
+                let handle = std::thread::spawn(move || {
+                    init();
+                    for job in receiver {
+                        unsafe { (*job.fill)(job.lane) };
+                        let _ = done.send(());
+                    }
+                });
+                Worker {
+                    jobs: Some(jobs),
+                    handle: Some(handle),
+                }
+            })
+            .collect();
+
+        Self { workers, done }
+    }
+
+    pub(crate) fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Calls `fill` for each of the given lanes on the pool threads and waits for all of them
+    ///
+    /// `fill` must not panic, a worker that stops leaves the pool waiting forever.
+    pub(crate) fn run(&self, lanes: u32, fill: &(dyn Fn(u32) + Sync)) {
+        // Every job is waited for below, the function is never called after `run` returns
+        let fill = unsafe {
+            std::mem::transmute::<*const (dyn Fn(u32) + Sync + '_), *const (dyn Fn(u32) + Sync)>(
+                fill,
+            )
+        };
+
+        for (lane, worker) in (0..lanes).zip(self.workers.iter().cycle()) {
+            let sender = worker
+                .jobs
+                .as_ref()
+                .expect("workers are only stopped on drop");
+            sender
+                .send(Job { fill, lane })
+                .expect("lane pool worker stopped");
+        }
+
+        for _ in 0..lanes {
+            self.done.recv().expect("lane pool worker stopped");
+        }
+    }
+}
+
+impl Drop for LanePool {
+    fn drop(&mut self) {
+        for worker in &mut self.workers {
+            worker.jobs.take();
+        }
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+}
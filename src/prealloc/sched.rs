@@ -0,0 +1,188 @@
+use super::{Hasher, LanePool};
+use crate::{Argon2, Error, backend::Control};
+use std::sync::Arc;
+
+/// The scheduling priority and the cores of the threads that fill the lanes
+///
+/// Bulk re-hashing in the background competes with the threads serving logins for the same
+/// cores. A higher nice value makes the scheduler prefer the other threads, pinning to a set of
+/// cores keeps the rest of them free. Only the lane threads are changed, never the calling
+/// thread.
+///
+/// Failing system calls, e.g. a negative nice value without `CAP_SYS_NICE` or cores outside the
+/// cgroup, leave the threads with the priority and cores they inherit.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, prealloc::{Hasher, ThreadScheduling}};
+///
+/// let scheduling = ThreadScheduling::new().with_nice(10).with_cores([0]);
+/// let argon2 = Argon2::new(1024, 2, 2);
+/// let mut hasher = Hasher::new(argon2.clone()).with_scheduled_thread_pool(2, scheduling);
+///
+/// let hash = hasher.hash_password("password", b"somesaltsomesalt").unwrap();
+/// assert_eq!(hash, argon2.hash_password("password", b"somesaltsomesalt").unwrap());
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ThreadScheduling {
+    nice: Option<i32>,
+    cores: Vec<usize>,
+}
+
+impl ThreadScheduling {
+    /// Leaves the priority and the cores as they are
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs the lane threads at the given nice value, from -20 (highest priority) to 19 (lowest)
+    pub fn with_nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice.clamp(-20, 19));
+        self
+    }
+
+    /// Runs the lane threads only on the given cores
+    pub fn with_cores(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.cores = cores.into_iter().collect();
+        self
+    }
+
+    pub fn nice(&self) -> Option<i32> {
+        self.nice
+    }
+
+    pub fn cores(&self) -> &[usize] {
+        &self.cores
+    }
+
+    /// Applies the scheduling to the calling thread
+    fn apply(&self) {
+        if let Some(nice) = self.nice {
+            // On Linux the nice value of a thread id only applies to that thread
+            let tid = unsafe { libc::gettid() };
+            unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) };
+        }
+
+        if !self.cores.is_empty() {
+            let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+            for &cpu in &self.cores {
+                if cpu < libc::CPU_SETSIZE as usize {
+                    unsafe { libc::CPU_SET(cpu, &mut set) };
+                }
+            }
+            let size = std::mem::size_of::<libc::cpu_set_t>();
+            unsafe { libc::sched_setaffinity(0, size, &set) };
+        }
+    }
+}
+
+impl Hasher {
+    /// Fills the lanes on a pool of `threads` threads with the given scheduling, see
+    /// [`Hasher::with_thread_pool`]
+    ///
+    /// The scheduling is applied once when the threads are spawned.
+    pub fn with_scheduled_thread_pool(
+        mut self,
+        threads: usize,
+        scheduling: ThreadScheduling,
+    ) -> Self {
+        self.pool = Some(LanePool::with_init(
+            threads,
+            Arc::new(move || scheduling.apply()),
+        ));
+        self
+    }
+}
+
+impl Argon2 {
+    /// Hashes the given password with the lanes filled on threads with the given scheduling
+    ///
+    /// The lane threads are spawned for each of the four slices of every pass, as the C
+    /// implementation does, and apply the scheduling each time. Prefer
+    /// [`Hasher::with_scheduled_thread_pool`] for many hashes.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `scheduling` - The priority and the cores of the lane threads
+    pub fn hash_password_with_scheduling(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        scheduling: &ThreadScheduling,
+    ) -> Result<Vec<u8>, Error> {
+        let init = || scheduling.apply();
+        let control = Control {
+            thread_init: Some(&init),
+            ..Default::default()
+        };
+        self.hash_controlled(password.as_ref(), salt, &control)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The nice value and the cores of the calling thread
+    fn current() -> (i32, Vec<usize>) {
+        let tid = unsafe { libc::gettid() };
+        let nice = unsafe { libc::getpriority(libc::PRIO_PROCESS, tid as libc::id_t) };
+
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        let size = std::mem::size_of::<libc::cpu_set_t>();
+        unsafe { libc::sched_getaffinity(0, size, &mut set) };
+        let cores = (0..libc::CPU_SETSIZE as usize)
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .collect();
+
+        (nice, cores)
+    }
+
+    #[test]
+    fn test_apply() {
+        let (nice, cores) = std::thread::spawn(current).join().unwrap();
+        let scheduling = ThreadScheduling::new()
+            .with_nice((nice + 5).min(19))
+            .with_cores([cores[0]]);
+
+        let applied = std::thread::spawn(move || {
+            scheduling.apply();
+            current()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(applied, ((nice + 5).min(19), vec![cores[0]]));
+
+        // The calling thread is left alone
+        assert_eq!(current().0, nice);
+        assert_eq!(ThreadScheduling::new().with_nice(100).nice(), Some(19));
+    }
+
+    #[test]
+    fn test_scheduled_hashes() {
+        let scheduling = ThreadScheduling::new().with_nice(19);
+        let salt = b"somesaltsomesalt";
+
+        for argon2 in [Argon2::new(1024, 2, 1), Argon2::new(2048, 1, 4)] {
+            let expected = argon2.hash_password("password", salt).unwrap();
+            let hash = argon2
+                .hash_password_with_scheduling("password", salt, &scheduling)
+                .unwrap();
+            assert_eq!(hash, expected);
+
+            let mut hasher =
+                Hasher::new(argon2.clone()).with_scheduled_thread_pool(2, scheduling.clone());
+            assert_eq!(hasher.hash_password("password", salt).unwrap(), expected);
+        }
+
+        // The lanes of a single-threaded hash never run on the calling thread
+        let before = current().0;
+        Argon2::new(1024, 1, 1)
+            .hash_password_with_scheduling("password", salt, &scheduling)
+            .unwrap();
+        assert_eq!(current().0, before);
+    }
+}
@@ -0,0 +1,83 @@
+use super::{Argon2, output::HashBytes};
+use blake2::{Blake2b512, Digest};
+
+impl Argon2 {
+    /// Pre-hashes passwords longer than `threshold` bytes with BLAKE2b-512 before hashing them
+    ///
+    /// Passwords longer than the threshold are replaced by their 64-byte digest, shorter ones are
+    /// hashed as they are so existing hashes still verify. This does not make long passwords any
+    /// cheaper, BLAKE2b reads the whole password just like Argon2 does. To bound the work of a
+    /// login cap the length of passwords with
+    /// [`limit::set_max_password_length`](crate::limit::set_max_password_length).
+    ///
+    /// The threshold is stored in the `ph` parameter of PHC strings and in binary records, so
+    /// verifying a stored hash pre-hashes exactly when hashing did. Other Argon2 implementations
+    /// do not know the parameter and cannot verify such hashes.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = Argon2::new(1024, 2, 1).with_prehash(1024);
+    /// let password = vec![b'a'; 4 * 1024 * 1024];
+    ///
+    /// let encoded = argon2.hash_password_encoded(&password, b"somesaltsomesalt").unwrap();
+    /// assert!(encoded.contains(",ph=1024"));
+    /// assert!(Argon2::verify_encoded(&password, &encoded).unwrap());
+    /// ```
+    pub fn with_prehash(mut self, threshold: u32) -> Self {
+        self.prehash = Some(threshold);
+        self
+    }
+
+    /// The digest the implementation is given instead of the password, if it exceeds the
+    /// threshold
+    pub(crate) fn prehashed(&self, password: &[u8]) -> Option<HashBytes> {
+        match self.prehash {
+            Some(threshold) if password.len() > threshold as usize => {
+                Some(blake2b(password).into())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn blake2b(input: &[u8]) -> Vec<u8> {
+    Blake2b512::digest(input).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prehash() {
+        let salt = b"somesaltsomesalt";
+        let argon2 = Argon2::new(1024, 2, 1);
+        let prehashing = argon2.clone().with_prehash(16);
+
+        // Up to the threshold the hash does not change
+        let short = [b'a'; 16];
+        assert_eq!(
+            prehashing.hash_password(short, salt).unwrap(),
+            argon2.hash_password(short, salt).unwrap()
+        );
+
+        let long = [b'a'; 17];
+        let digest = blake2b(&long);
+        assert_eq!(digest.len(), 64);
+        assert_eq!(
+            prehashing.hash_password(long, salt).unwrap(),
+            argon2.hash_password(&digest, salt).unwrap()
+        );
+
+        // BLAKE2b-512 of the empty string
+        assert_eq!(blake2b(b"")[..4], [0x78, 0x6a, 0x02, 0xf7]);
+
+        let encoded = prehashing.hash_password_encoded(long, salt).unwrap();
+        assert_eq!(Argon2::from_encoded(&encoded).unwrap().prehash, Some(16));
+        assert!(Argon2::verify_encoded(long, &encoded).unwrap());
+        assert!(!Argon2::verify_encoded(digest, &encoded).unwrap());
+    }
+}
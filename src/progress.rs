@@ -0,0 +1,79 @@
+use super::{Argon2, Error, backend::Control};
+
+impl Argon2 {
+    /// Hashes the given password and reports the progress after every pass
+    ///
+    /// Deriving a key with slow parameters takes seconds, report the progress to keep the UI
+    /// responsive instead of appearing frozen. `progress` is called on the calling thread with the
+    /// number of completed passes and the total number of passes (`t_cost`).
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `progress` - Called with `(completed_passes, total_passes)` after every pass
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    /// use std::cell::Cell;
+    ///
+    /// let argon2 = Argon2::new(1024, 3, 1);
+    /// let percent = Cell::new(0);
+    ///
+    /// let hash = argon2
+    ///     .hash_password_with_progress("password", b"somesaltsomesalt", |pass, passes| {
+    ///         percent.set(pass * 100 / passes);
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(percent.get(), 100);
+    /// ```
+    pub fn hash_password_with_progress(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        progress: impl Fn(u32, u32),
+    ) -> Result<Vec<u8>, Error> {
+        let control = Control {
+            progress: Some(&progress),
+            ..Default::default()
+        };
+        self.hash_controlled(password.as_ref(), salt, &control)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_hash_password_with_progress() {
+        let argon2 = Argon2::new(1024, 3, 2);
+        let salt = b"somesaltsomesalt";
+        let calls = RefCell::new(Vec::new());
+
+        let hash = argon2
+            .hash_password_with_progress("password", salt, |pass, passes| {
+                calls.borrow_mut().push((pass, passes));
+            })
+            .unwrap();
+
+        assert_eq!(hash, argon2.hash_password("password", salt).unwrap());
+        assert_eq!(calls.into_inner(), [(1, 3), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn test_progress_panic() {
+        let argon2 = Argon2::new(1024, 3, 2);
+        let salt = b"somesaltsomesalt";
+
+        // The working memory is wiped and freed while the panic unwinds
+        let result = std::panic::catch_unwind(|| {
+            argon2.hash_password_with_progress("password", salt, |_, _| panic!("progress"))
+        });
+        assert!(result.is_err());
+        assert!(argon2.hash_password("password", salt).is_ok());
+    }
+}
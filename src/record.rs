@@ -0,0 +1,317 @@
+use super::{Algorithm, Argon2, Error, Version, normalize::Normalization};
+
+/// The version of the binary record format
+pub(crate) const FORMAT_VERSION: u8 = 1;
+
+/// The version of records that carry an options byte after the costs
+const FORMAT_VERSION_OPTIONS: u8 = 2;
+
+/// The options byte is followed by the pre-hash threshold
+const OPTION_PREHASH: u8 = 1;
+/// Passwords are normalized to NFC
+const OPTION_NFC: u8 = 2;
+/// Passwords are normalized to NFKC
+const OPTION_NFKC: u8 = 4;
+
+/// Appends `value` as an unsigned LEB128 varint
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Encodes the parameters, salt and hash into the binary record format
+///
+/// The layout is documented on [`StoredHash::to_bytes`](crate::stored::StoredHash::to_bytes).
+pub(crate) fn encode(argon2: &Argon2, keyid: Option<&[u8]>, salt: &[u8], hash: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(16 + argon2.ad.len() + salt.len() + hash.len());
+
+    let options = match argon2.normalization {
+        Some(Normalization::Nfc) => OPTION_NFC,
+        Some(Normalization::Nfkc) => OPTION_NFKC,
+        None => 0,
+    } | if argon2.prehash.is_some() {
+        OPTION_PREHASH
+    } else {
+        0
+    };
+
+    // Records without options stay readable by earlier versions
+    out.push(match options {
+        0 => FORMAT_VERSION,
+        _ => FORMAT_VERSION_OPTIONS,
+    });
+    out.push(argon2.algorithm as u8);
+    out.push(argon2.version.as_u32() as u8);
+    put_varint(&mut out, argon2.m_cost as u64);
+    put_varint(&mut out, argon2.t_cost as u64);
+    put_varint(&mut out, argon2.p_cost as u64);
+    if options != 0 {
+        out.push(options);
+    }
+    if let Some(threshold) = argon2.prehash {
+        put_varint(&mut out, threshold as u64);
+    }
+    put_bytes(&mut out, &argon2.ad);
+
+    match keyid {
+        Some(keyid) => {
+            out.push(1);
+            put_bytes(&mut out, keyid);
+        }
+        None => out.push(0),
+    }
+
+    put_bytes(&mut out, salt);
+    put_bytes(&mut out, hash);
+    out
+}
+
+/// The contents of a decoded binary record
+pub(crate) struct Decoded {
+    pub argon2: Argon2,
+    pub keyid: Option<Vec<u8>>,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn u8(&mut self) -> Result<u8, Error> {
+        let (first, rest) = self
+            .bytes
+            .split_first()
+            .ok_or(Error::InvalidRecord("unexpected end of record"))?;
+        self.bytes = rest;
+        Ok(*first)
+    }
+
+    /// Reads an unsigned LEB128 varint in its shortest encoding
+    ///
+    /// Overlong encodings are rejected so every value has exactly one encoding, as are values
+    /// that do not fit in a `u64`.
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            let bits = (byte & 0x7f) as u64;
+
+            // The tenth byte only has room for the top bit of a `u64`
+            if shift == 63 && bits > 1 {
+                return Err(Error::InvalidRecord("varint overflow"));
+            }
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                if byte == 0 && shift > 0 {
+                    return Err(Error::InvalidRecord("overlong varint"));
+                }
+                return Ok(value);
+            }
+        }
+
+        Err(Error::InvalidRecord("varint overflow"))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        u32::try_from(self.varint()?).map_err(|_| Error::InvalidRecord("parameter out of range"))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.varint()?;
+
+        if len > self.bytes.len() as u64 {
+            return Err(Error::InvalidRecord("unexpected end of record"));
+        }
+
+        let (bytes, rest) = self.bytes.split_at(len as usize);
+        self.bytes = rest;
+        Ok(bytes)
+    }
+}
+
+/// Decodes a binary record produced by [`encode`]
+pub(crate) fn decode(bytes: &[u8]) -> Result<Decoded, Error> {
+    let mut reader = Reader { bytes };
+
+    let format_version = reader.u8()?;
+    if format_version != FORMAT_VERSION && format_version != FORMAT_VERSION_OPTIONS {
+        return Err(Error::InvalidRecord("unsupported format version"));
+    }
+
+    let algorithm = match reader.u8()? {
+        0 => Algorithm::Argon2d,
+        1 => Algorithm::Argon2i,
+        2 => Algorithm::Argon2id,
+        _ => return Err(Error::InvalidRecord("unknown algorithm")),
+    };
+
+    let version = match reader.u8()? {
+        0x10 => Version::V0x10,
+        0x13 => Version::V0x13,
+        _ => return Err(Error::InvalidRecord("unknown version")),
+    };
+
+    let m_cost = reader.u32()?;
+    let t_cost = reader.u32()?;
+    let p_cost = reader.u32()?;
+    let options = match format_version {
+        // Records without options are always written with the earlier version
+        FORMAT_VERSION_OPTIONS => match reader.u8()? {
+            0 => return Err(Error::InvalidRecord("empty options")),
+            options => options,
+        },
+        _ => 0,
+    };
+    if options & !(OPTION_PREHASH | OPTION_NFC | OPTION_NFKC) != 0 {
+        return Err(Error::InvalidRecord("unknown options"));
+    }
+    let prehash = match options & OPTION_PREHASH {
+        0 => None,
+        _ => Some(reader.u32()?),
+    };
+    let normalization = match options & (OPTION_NFC | OPTION_NFKC) {
+        0 => None,
+        OPTION_NFC => Some(Normalization::Nfc),
+        OPTION_NFKC => Some(Normalization::Nfkc),
+        _ => return Err(Error::InvalidRecord("conflicting normalization options")),
+    };
+    let ad = reader.bytes()?.to_vec();
+
+    let keyid = match reader.u8()? {
+        0 => None,
+        1 => Some(reader.bytes()?.to_vec()),
+        _ => return Err(Error::InvalidRecord("invalid keyid tag")),
+    };
+
+    let salt = reader.bytes()?.to_vec();
+    let hash = reader.bytes()?.to_vec();
+
+    if !reader.bytes.is_empty() {
+        return Err(Error::InvalidRecord("trailing data"));
+    }
+
+    let argon2 = Argon2 {
+        m_cost,
+        t_cost,
+        p_cost,
+        hash_length: hash.len() as u64,
+        algorithm,
+        version,
+        ad,
+        prehash,
+        normalization,
+        ..Default::default()
+    };
+
+    Ok(Decoded {
+        argon2,
+        keyid,
+        salt,
+        hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint() {
+        for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut out = Vec::new();
+            put_varint(&mut out, value);
+            assert_eq!(Reader { bytes: &out }.varint().unwrap(), value);
+        }
+
+        let mut out = Vec::new();
+        put_varint(&mut out, 300);
+        assert_eq!(out, [0xac, 0x02]);
+
+        let invalid: [&[u8]; 4] = [
+            // 0 and 1 with a redundant continuation byte
+            &[0x80, 0x00],
+            &[0x81, 0x80, 0x00],
+            // u64::MAX with one more bit in the tenth byte
+            &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x03],
+            // An eleventh byte
+            &[
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x81, 0x00,
+            ],
+        ];
+        for bytes in invalid {
+            assert!(Reader { bytes }.varint().is_err(), "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let argon2 = Argon2::new(4096, 3, 1).with_ad(b"user1");
+        let encoded = encode(&argon2, Some(b"v1"), b"somesalt", b"hashhash");
+        assert_eq!(encoded[..4], [FORMAT_VERSION, 2, 0x13, 0x80]);
+
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded.argon2, argon2.clone().with_hash_length(8));
+        assert_eq!(decoded.keyid.as_deref(), Some(&b"v1"[..]));
+        assert_eq!(decoded.salt, b"somesalt");
+        assert_eq!(decoded.hash, b"hashhash");
+
+        for len in 0..encoded.len() {
+            assert!(decode(&encoded[..len]).is_err());
+        }
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(decode(&trailing).is_err());
+
+        let mut unknown = encoded;
+        unknown[0] = FORMAT_VERSION_OPTIONS + 1;
+        assert!(decode(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_decode_options() {
+        let argon2 = Argon2::new(4096, 3, 1)
+            .with_hash_length(8)
+            .with_prehash(1024)
+            .with_normalization(Normalization::Nfkc);
+        let encoded = encode(&argon2, None, b"somesalt", b"hashhash");
+        assert_eq!(
+            encoded[..7],
+            [FORMAT_VERSION_OPTIONS, 2, 0x13, 0x80, 0x20, 3, 1]
+        );
+        assert_eq!(encoded[7..10], [OPTION_PREHASH | OPTION_NFKC, 0x80, 0x08]);
+        assert_eq!(decode(&encoded).unwrap().argon2, argon2);
+
+        let argon2 = argon2.with_normalization(Normalization::Nfc);
+        let mut encoded = encode(&argon2, None, b"somesalt", b"hashhash");
+        assert_eq!(decode(&encoded).unwrap().argon2, argon2);
+
+        encoded[7] |= OPTION_NFKC;
+        assert!(decode(&encoded).is_err());
+        encoded[7] = 0x80;
+        assert!(decode(&encoded).is_err());
+
+        // A record without options has a single encoding
+        let plain = Argon2::new(4096, 3, 1).with_hash_length(8);
+        let mut encoded = encode(&plain, None, b"somesalt", b"hashhash");
+        assert_eq!(encoded[0], FORMAT_VERSION);
+        encoded[0] = FORMAT_VERSION_OPTIONS;
+        encoded.insert(7, 0);
+        assert!(matches!(
+            decode(&encoded),
+            Err(Error::InvalidRecord("empty options"))
+        ));
+    }
+}
@@ -0,0 +1,296 @@
+use super::{Argon2, Error};
+
+/// Verifies the hashes of one password hashing scheme
+pub trait Verifier: Send + Sync {
+    /// The name reported when a hash of this scheme is verified, e.g. `bcrypt`
+    fn scheme(&self) -> &'static str;
+
+    /// Whether the encoded hash belongs to this scheme, usually by its prefix
+    fn recognizes(&self, encoded: &str) -> bool;
+
+    /// Verifies the given password against the encoded hash
+    fn verify(&self, password: &[u8], encoded: &str) -> Result<bool, Error>;
+}
+
+/// The outcome of [`VerifierRegistry::verify`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Verification {
+    /// The scheme of the hash
+    pub scheme: &'static str,
+    /// Whether the password matches
+    pub valid: bool,
+    /// Whether the password should be rehashed with the Argon2 parameters of the registry
+    ///
+    /// Always `false` if the password does not match.
+    pub needs_rehash: bool,
+}
+
+/// Verifies hashes of several schemes during a gradual migration to Argon2
+///
+/// The scheme of a hash is recognized by its prefix. Argon2 hashes are always recognized and only
+/// need a rehash if their parameters differ from those of the registry, valid hashes of any other
+/// scheme always need a rehash.
+///
+/// [`VerifierRegistry::new`] registers every scheme enabled by features:
+///
+/// - `argon2` - PHC strings of this crate
+/// - `bcrypt` - `$2a$`, `$2b$`, `$2x$` and `$2y$` hashes with the `bcrypt` feature
+/// - `pbkdf2` - `$pbkdf2$`, `$pbkdf2-sha256$` and `$pbkdf2-sha512$` PHC strings with the `pbkdf2`
+///   feature
+/// - `wrap-bcrypt` and `wrap-scrypt` - legacy hashes wrapped with [`Argon2::wrap_legacy`] with the
+///   `bcrypt` and `scrypt` features
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, registry::VerifierRegistry};
+///
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let registry = VerifierRegistry::new(argon2.clone());
+///
+/// let old = Argon2::new(512, 2, 1)
+///     .hash_password_encoded("password", b"somesaltsomesalt")
+///     .unwrap();
+///
+/// let verification = registry.verify("password", &old).unwrap();
+/// assert_eq!(verification.scheme, "argon2");
+/// assert!(verification.valid);
+/// assert!(verification.needs_rehash);
+/// ```
+pub struct VerifierRegistry {
+    argon2: Argon2,
+    verifiers: Vec<Box<dyn Verifier>>,
+}
+
+impl VerifierRegistry {
+    /// Creates a registry with the schemes enabled by features
+    ///
+    /// ## Arguments
+    ///
+    /// - `argon2` - The parameters passwords should be hashed with
+    pub fn new(argon2: Argon2) -> Self {
+        let registry = Self {
+            argon2,
+            verifiers: Vec::new(),
+        };
+
+        #[cfg(feature = "bcrypt")]
+        let registry = registry
+            .with_verifier(BcryptVerifier)
+            .with_verifier(WrappedVerifier(super::legacy::Bcrypt));
+
+        #[cfg(feature = "scrypt")]
+        let registry = registry.with_verifier(WrappedVerifier(super::legacy::Scrypt));
+
+        #[cfg(feature = "pbkdf2")]
+        let registry = registry.with_verifier(Pbkdf2Verifier);
+
+        registry
+    }
+
+    /// Registers a verifier for another scheme
+    ///
+    /// Verifiers are tried in the order they were registered, Argon2 hashes are recognized
+    /// before any of them.
+    pub fn with_verifier(mut self, verifier: impl Verifier + 'static) -> Self {
+        self.verifiers.push(Box::new(verifier));
+        self
+    }
+
+    /// The schemes this registry recognizes
+    pub fn schemes(&self) -> Vec<&'static str> {
+        std::iter::once(ARGON2_SCHEME)
+            .chain(self.verifiers.iter().map(|verifier| verifier.scheme()))
+            .collect()
+    }
+
+    /// Verifies the given password against a hash of any registered scheme
+    ///
+    /// Returns [`Error::UnknownScheme`] if no registered scheme recognizes the hash.
+    pub fn verify(&self, password: impl AsRef<[u8]>, encoded: &str) -> Result<Verification, Error> {
+        let password = password.as_ref();
+
+        if encoded.starts_with("$argon2") {
+            let valid = Argon2::verify_encoded(password, encoded)?;
+            return Ok(Verification {
+                scheme: ARGON2_SCHEME,
+                valid,
+                needs_rehash: valid && self.argon2.needs_rehash(encoded)?,
+            });
+        }
+
+        let verifier = self
+            .verifiers
+            .iter()
+            .find(|verifier| verifier.recognizes(encoded))
+            .ok_or(Error::UnknownScheme)?;
+
+        let valid = verifier.verify(password, encoded)?;
+        Ok(Verification {
+            scheme: verifier.scheme(),
+            valid,
+            needs_rehash: valid,
+        })
+    }
+}
+
+const ARGON2_SCHEME: &str = "argon2";
+
+/// Verifies legacy hashes wrapped with [`Argon2::wrap_legacy`]
+#[cfg(any(feature = "bcrypt", feature = "scrypt"))]
+struct WrappedVerifier<S>(S);
+
+#[cfg(any(feature = "bcrypt", feature = "scrypt"))]
+impl<S: super::legacy::LegacyScheme + Send + Sync> Verifier for WrappedVerifier<S> {
+    fn scheme(&self) -> &'static str {
+        match self.0.name() {
+            "bcrypt" => "wrap-bcrypt",
+            "scrypt" => "wrap-scrypt",
+            _ => "wrap",
+        }
+    }
+
+    fn recognizes(&self, encoded: &str) -> bool {
+        encoded
+            .strip_prefix(super::legacy::WRAP_PREFIX)
+            .and_then(|rest| rest.strip_prefix(self.0.name()))
+            .is_some_and(|rest| rest.starts_with('$'))
+    }
+
+    fn verify(&self, password: &[u8], encoded: &str) -> Result<bool, Error> {
+        encoded
+            .parse::<super::legacy::WrappedHash>()?
+            .verify(&self.0, password)
+    }
+}
+
+#[cfg(feature = "bcrypt")]
+struct BcryptVerifier;
+
+#[cfg(feature = "bcrypt")]
+impl Verifier for BcryptVerifier {
+    fn scheme(&self) -> &'static str {
+        "bcrypt"
+    }
+
+    fn recognizes(&self, encoded: &str) -> bool {
+        ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| encoded.starts_with(prefix))
+    }
+
+    fn verify(&self, password: &[u8], encoded: &str) -> Result<bool, Error> {
+        bcrypt::verify(password, encoded).map_err(|e| Error::InvalidLegacyHash(e.to_string()))
+    }
+}
+
+#[cfg(feature = "pbkdf2")]
+struct Pbkdf2Verifier;
+
+#[cfg(feature = "pbkdf2")]
+impl Verifier for Pbkdf2Verifier {
+    fn scheme(&self) -> &'static str {
+        "pbkdf2"
+    }
+
+    fn recognizes(&self, encoded: &str) -> bool {
+        ["$pbkdf2$", "$pbkdf2-sha256$", "$pbkdf2-sha512$"]
+            .iter()
+            .any(|prefix| encoded.starts_with(prefix))
+    }
+
+    fn verify(&self, password: &[u8], encoded: &str) -> Result<bool, Error> {
+        use pbkdf2::password_hash::{self, PasswordHash, PasswordVerifier};
+
+        let hash =
+            PasswordHash::new(encoded).map_err(|e| Error::InvalidLegacyHash(e.to_string()))?;
+
+        match pbkdf2::Pbkdf2.verify_password(password, &hash) {
+            Ok(()) => Ok(true),
+            Err(password_hash::Error::Password) => Ok(false),
+            Err(e) => Err(Error::InvalidLegacyHash(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Plain;
+
+    impl Verifier for Plain {
+        fn scheme(&self) -> &'static str {
+            "plain"
+        }
+
+        fn recognizes(&self, encoded: &str) -> bool {
+            encoded.starts_with("$plain$")
+        }
+
+        fn verify(&self, password: &[u8], encoded: &str) -> Result<bool, Error> {
+            Ok(encoded.as_bytes()[7..] == *password)
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let registry = VerifierRegistry::new(argon2.clone()).with_verifier(Plain);
+        assert_eq!(registry.schemes().first(), Some(&"argon2"));
+        assert_eq!(registry.schemes().last(), Some(&"plain"));
+
+        let current = argon2
+            .hash_password_encoded("password", b"somesaltsomesalt")
+            .unwrap();
+        let verification = registry.verify("password", &current).unwrap();
+        assert_eq!(
+            verification,
+            Verification {
+                scheme: "argon2",
+                valid: true,
+                needs_rehash: false,
+            }
+        );
+
+        let verification = registry.verify("password", "$plain$password").unwrap();
+        assert_eq!(verification.scheme, "plain");
+        assert!(verification.valid && verification.needs_rehash);
+
+        let verification = registry.verify("wrong", "$plain$password").unwrap();
+        assert!(!verification.valid && !verification.needs_rehash);
+
+        assert!(matches!(
+            registry.verify("password", "$md5$password"),
+            Err(Error::UnknownScheme)
+        ));
+    }
+
+    #[cfg(all(feature = "bcrypt", feature = "pbkdf2"))]
+    #[test]
+    fn test_registry_legacy_schemes() {
+        let argon2 = Argon2::new(1024, 2, 1);
+        let registry = VerifierRegistry::new(argon2.clone());
+
+        let bcrypt = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+        let verification = registry.verify("U*U", bcrypt).unwrap();
+        assert_eq!(verification.scheme, "bcrypt");
+        assert!(verification.valid && verification.needs_rehash);
+        assert!(!registry.verify("U*V", bcrypt).unwrap().valid);
+
+        let wrapped = argon2
+            .wrap_legacy(&crate::legacy::Bcrypt, bcrypt, b"somesaltsomesalt")
+            .unwrap()
+            .to_string();
+        let verification = registry.verify("U*U", &wrapped).unwrap();
+        assert_eq!(verification.scheme, "wrap-bcrypt");
+        assert!(verification.valid && verification.needs_rehash);
+
+        // Computed with Python's hashlib.pbkdf2_hmac
+        let pbkdf2 = "$pbkdf2-sha256$i=1000,l=32$c29tZXNhbHRzb21lc2FsdA$s5LQUeAEZUMuFVrnmF3OMNPXs3QWnF8SO/5BXmCj6QQ";
+        let verification = registry.verify("password", pbkdf2).unwrap();
+        assert_eq!(verification.scheme, "pbkdf2");
+        assert!(verification.valid);
+        assert!(!registry.verify("wrong", pbkdf2).unwrap().valid);
+    }
+}
@@ -0,0 +1,394 @@
+use super::{Argon2, Error, output::wipe};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+mod service;
+pub use service::{HashService, RejectionPolicy, ServiceConfig, ServiceTask};
+
+/// Runs blocking work off the async executor
+///
+/// Hashing takes hundreds of milliseconds with recommended parameters, computing it on an async
+/// worker thread stalls every other task scheduled on it. Implement this for the blocking pool of
+/// your runtime and hash with [`Argon2::hash_password_on`] and [`Argon2::verify_on`].
+///
+/// [`ThreadSpawner`] works with any runtime, `TokioSpawner` is available with the `tokio`
+/// feature. For async-std and smol, forward to `async_std::task::spawn_blocking` or
+/// `blocking::unblock`:
+///
+/// ```
+/// use argon2_rs::{error::Error, runtime::Spawner};
+///
+/// struct Smol;
+///
+/// impl Spawner for Smol {
+///     fn spawn_blocking<T, F>(&self, f: F) -> impl Future<Output = Result<T, Error>> + Send
+///     where
+///         F: FnOnce() -> T + Send + 'static,
+///         T: Send + 'static,
+///     {
+///         // blocking::unblock(f) with the `blocking` crate
+///         async move { Ok(f()) }
+///     }
+/// }
+/// ```
+pub trait Spawner {
+    /// Runs `f` where blocking is allowed and resolves to its result
+    fn spawn_blocking<T, F>(&self, f: F) -> impl Future<Output = Result<T, Error>> + Send
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static;
+}
+
+/// Runs the work on a new OS thread and wakes the task when it is done
+///
+/// It needs no runtime support, at the cost of spawning a thread per hash.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadSpawner;
+
+impl Spawner for ThreadSpawner {
+    fn spawn_blocking<T, F>(&self, f: F) -> impl Future<Output = Result<T, Error>> + Send
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (run, task) = task(f);
+        let spawned = std::thread::Builder::new()
+            .name("argon2-rs".to_string())
+            .spawn(run);
+
+        if let Err(e) = spawned {
+            task.complete(Err(Error::Task(e.to_string())));
+        }
+        task
+    }
+}
+
+/// Pairs the work with a future that resolves to its result once the work has run
+///
+/// The future never resolves if the work is dropped without running, complete it instead.
+fn task<T, F>(f: F) -> (impl FnOnce() + Send + 'static, Task<T>)
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let task = Task {
+        shared: Arc::new(Mutex::new(Shared {
+            result: None,
+            waker: None,
+        })),
+    };
+
+    let completed = task.clone();
+    let run = move || {
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+            .map_err(|_| Error::Task("the blocking task panicked".to_string()));
+        completed.complete(result);
+    };
+
+    (run, task)
+}
+
+struct Shared<T> {
+    result: Option<Result<T, Error>>,
+    waker: Option<Waker>,
+}
+
+/// Resolves once the blocking work has stored its result
+struct Task<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Task<T> {
+    fn complete(&self, result: Result<T, Error>) {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        shared.result = Some(result);
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+// Not derived, it would require `T: Clone`
+impl<T> Clone for Task<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Runs the work on the blocking thread pool of the tokio runtime
+///
+/// Must be used from within a tokio runtime.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioSpawner;
+
+#[cfg(feature = "tokio")]
+impl Spawner for TokioSpawner {
+    fn spawn_blocking<T, F>(&self, f: F) -> impl Future<Output = Result<T, Error>> + Send
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let task = tokio::task::spawn_blocking(f);
+        async move { task.await.map_err(|e| Error::Task(e.to_string())) }
+    }
+}
+
+/// A copy of the password that is moved to the blocking thread and wiped when dropped
+struct PasswordCopy(Vec<u8>);
+
+impl Drop for PasswordCopy {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+impl Argon2 {
+    /// Hashes the given password on the blocking threads of the given [`Spawner`]
+    ///
+    /// The password and salt are copied to the blocking thread, the password copy is wiped
+    /// afterwards.
+    ///
+    /// ## Arguments
+    ///
+    /// - `spawner` - Runs the hash off the async executor
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, runtime::ThreadSpawner};
+    ///
+    /// async fn register(password: String) -> Result<String, argon2_rs::error::Error> {
+    ///     Argon2::new(1024, 2, 1)
+    ///         .hash_password_encoded_on(&ThreadSpawner, password, b"somesaltsomesalt")
+    ///         .await
+    /// }
+    /// ```
+    pub async fn hash_password_on(
+        &self,
+        spawner: &impl Spawner,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let argon2 = self.clone();
+        let password = PasswordCopy(password.as_ref().to_vec());
+        let salt = salt.to_vec();
+        spawner
+            .spawn_blocking(move || argon2.hash_password(&password.0, &salt))
+            .await?
+    }
+
+    /// Hashes the given password into a PHC string on the blocking threads of the given
+    /// [`Spawner`], see [`Argon2::hash_password_on`]
+    pub async fn hash_password_encoded_on(
+        &self,
+        spawner: &impl Spawner,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<String, Error> {
+        let argon2 = self.clone();
+        let password = PasswordCopy(password.as_ref().to_vec());
+        let salt = salt.to_vec();
+        spawner
+            .spawn_blocking(move || argon2.hash_password_encoded(&password.0, &salt))
+            .await?
+    }
+
+    /// Verifies the given password against a PHC string on the blocking threads of the given
+    /// [`Spawner`], see [`Argon2::verify_encoded`]
+    pub async fn verify_on(
+        spawner: &impl Spawner,
+        password: impl AsRef<[u8]>,
+        encoded: &str,
+    ) -> Result<bool, Error> {
+        let password = PasswordCopy(password.as_ref().to_vec());
+        let encoded = encoded.to_string();
+        spawner
+            .spawn_blocking(move || Argon2::verify_encoded(&password.0, &encoded))
+            .await?
+    }
+
+    /// Hashes the given password on the blocking thread pool of the tokio runtime
+    ///
+    /// Same as [`Argon2::hash_password_on`] with [`TokioSpawner`], must be called from within a
+    /// tokio runtime.
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// # runtime.block_on(async {
+    /// let argon2 = Argon2::new(1024, 2, 1);
+    /// let hash = argon2
+    ///     .hash_password_async("password", b"somesaltsomesalt")
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(hash, argon2.hash_password("password", b"somesaltsomesalt").unwrap());
+    /// # });
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn hash_password_async(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.hash_password_on(&TokioSpawner, password, salt).await
+    }
+
+    /// Hashes the given password into a PHC string on the blocking thread pool of the tokio
+    /// runtime, see [`Argon2::hash_password_async`]
+    #[cfg(feature = "tokio")]
+    pub async fn hash_password_encoded_async(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<String, Error> {
+        self.hash_password_encoded_on(&TokioSpawner, password, salt)
+            .await
+    }
+
+    /// Verifies the given password against a PHC string on the blocking thread pool of the tokio
+    /// runtime, see [`Argon2::verify_encoded`]
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// # let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// # runtime.block_on(async {
+    /// let encoded = Argon2::new(1024, 2, 1)
+    ///     .hash_password_encoded_async("password", b"somesaltsomesalt")
+    ///     .await
+    ///     .unwrap();
+    /// assert!(Argon2::verify_async("password", &encoded).await.unwrap());
+    /// # });
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub async fn verify_async(password: impl AsRef<[u8]>, encoded: &str) -> Result<bool, Error> {
+        Self::verify_on(&TokioSpawner, password, encoded).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+
+    /// Polls the future to completion on the current thread
+    pub(super) fn block_on<F: Future>(future: F) -> F::Output {
+        struct Unpark(std::thread::Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => std::thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_thread_spawner() {
+        let argon2 = Argon2::new(1024, 2, 2);
+        let salt = b"somesaltsomesalt";
+
+        block_on(async {
+            assert_eq!(
+                argon2
+                    .hash_password_on(&ThreadSpawner, "password", salt)
+                    .await
+                    .unwrap(),
+                argon2.hash_password("password", salt).unwrap()
+            );
+
+            let encoded = argon2
+                .hash_password_encoded_on(&ThreadSpawner, "password", salt)
+                .await
+                .unwrap();
+            assert!(
+                Argon2::verify_on(&ThreadSpawner, "password", &encoded)
+                    .await
+                    .unwrap()
+            );
+            assert!(
+                !Argon2::verify_on(&ThreadSpawner, "wrong", &encoded)
+                    .await
+                    .unwrap()
+            );
+
+            assert!(matches!(
+                ThreadSpawner.spawn_blocking(|| panic!("boom")).await,
+                Err::<(), _>(Error::Task(_))
+            ));
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_async_matches_blocking() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let argon2 = Argon2::new(1024, 2, 2);
+        let salt = b"somesaltsomesalt";
+
+        runtime.block_on(async {
+            assert_eq!(
+                argon2.hash_password_async("password", salt).await.unwrap(),
+                argon2.hash_password("password", salt).unwrap()
+            );
+
+            let encoded = argon2
+                .hash_password_encoded_async("password", salt)
+                .await
+                .unwrap();
+            assert!(Argon2::verify_async("password", &encoded).await.unwrap());
+            assert!(!Argon2::verify_async("wrong", &encoded).await.unwrap());
+
+            assert!(
+                argon2
+                    .hash_password_async("password", b"short")
+                    .await
+                    .is_err()
+            );
+            assert!(Argon2::verify_async("password", "$invalid").await.is_err());
+        });
+    }
+}
@@ -0,0 +1,299 @@
+use super::{PasswordCopy, Task, task};
+use crate::{Argon2, Error};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::thread::JoinHandle;
+
+/// What [`HashService`] does with a job when its queue is full
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectionPolicy {
+    /// Resolves the job to [`Error::QueueFull`] right away, to shed load under a burst
+    Reject,
+    /// Blocks the submitting thread until there is room in the queue
+    ///
+    /// Do not use it from an async task, blocking stalls the executor thread.
+    Wait,
+}
+
+/// The size of the pool and the queue of a [`HashService`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ServiceConfig {
+    /// The number of threads hashing at the same time, at least 1
+    pub workers: usize,
+    /// The number of jobs waiting for a worker before [`ServiceConfig::rejection`] applies
+    pub queue_depth: usize,
+    pub rejection: RejectionPolicy,
+}
+
+impl Default for ServiceConfig {
+    /// A worker per available core, a queue of 64 jobs and [`RejectionPolicy::Reject`]
+    fn default() -> Self {
+        Self {
+            workers: std::thread::available_parallelism().map_or(1, |cores| cores.get()),
+            queue_depth: 64,
+            rejection: RejectionPolicy::Reject,
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A pool of threads that hash and verify passwords from a bounded queue
+///
+/// Jobs return a [`ServiceTask`], a future that resolves once a worker has run the job, await it
+/// from any executor. The workers bound the number of hashes in memory at once, the queue bounds
+/// the number of waiting requests, and the [`RejectionPolicy`] decides what happens beyond that.
+///
+/// Dropping the service runs the queued jobs and joins the workers.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, error::Error, runtime::{HashService, ServiceConfig}};
+///
+/// async fn login(service: &HashService, password: String, stored: &str) -> Result<bool, Error> {
+///     match service.verify_encoded(password, stored).await {
+///         Err(Error::QueueFull) => Ok(false), // ask the client to retry later
+///         result => result,
+///     }
+/// }
+///
+/// let service = HashService::new(Argon2::new(1024, 2, 1), ServiceConfig::default());
+/// ```
+pub struct HashService {
+    argon2: Argon2,
+    config: ServiceConfig,
+    jobs: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl HashService {
+    /// Spawns the workers that hash with the given parameters
+    pub fn new(argon2: Argon2, config: ServiceConfig) -> Self {
+        let (jobs, receiver) = sync_channel::<Job>(config.queue_depth);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..config.workers.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+                std::thread::Builder::new()
+                    .name("argon2-rs-service".to_string())
+                    .spawn(move || work(&receiver))
+                    .expect("failed to spawn a hash service worker")
+            })
+            .collect();
+
+        Self {
+            argon2,
+            config,
+            jobs: Some(jobs),
+            workers,
+        }
+    }
+
+    /// The parameters new hashes are created with
+    pub fn argon2(&self) -> &Argon2 {
+        &self.argon2
+    }
+
+    pub fn config(&self) -> &ServiceConfig {
+        &self.config
+    }
+
+    /// Hashes the given password on a worker, see [`Argon2::hash_password`]
+    pub fn hash_password(&self, password: impl AsRef<[u8]>, salt: &[u8]) -> ServiceTask<Vec<u8>> {
+        let argon2 = self.argon2.clone();
+        let password = PasswordCopy(password.as_ref().to_vec());
+        let salt = salt.to_vec();
+        self.submit(move || argon2.hash_password(&password.0, &salt))
+    }
+
+    /// Hashes the given password into a PHC string on a worker, see
+    /// [`Argon2::hash_password_encoded`]
+    pub fn hash_password_encoded(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> ServiceTask<String> {
+        let argon2 = self.argon2.clone();
+        let password = PasswordCopy(password.as_ref().to_vec());
+        let salt = salt.to_vec();
+        self.submit(move || argon2.hash_password_encoded(&password.0, &salt))
+    }
+
+    /// Verifies the given password against a PHC string on a worker, see
+    /// [`Argon2::verify_encoded`]
+    pub fn verify_encoded(&self, password: impl AsRef<[u8]>, encoded: &str) -> ServiceTask<bool> {
+        let password = PasswordCopy(password.as_ref().to_vec());
+        let encoded = encoded.to_string();
+        self.submit(move || Argon2::verify_encoded(&password.0, &encoded))
+    }
+
+    /// Queues the job as the rejection policy says and returns its result once it has run
+    fn submit<T, F>(&self, f: F) -> ServiceTask<T>
+    where
+        F: FnOnce() -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (run, task) = task(f);
+        let jobs = self.jobs.as_ref().expect("jobs are only closed on drop");
+
+        let queued = match self.config.rejection {
+            RejectionPolicy::Reject => match jobs.try_send(Box::new(run)) {
+                Ok(()) => Ok(()),
+                Err(TrySendError::Full(_)) => Err(Error::QueueFull),
+                Err(TrySendError::Disconnected(_)) => Err(stopped()),
+            },
+            RejectionPolicy::Wait => jobs.send(Box::new(run)).map_err(|_| stopped()),
+        };
+
+        if let Err(e) = queued {
+            task.complete(Err(e));
+        }
+        ServiceTask(task)
+    }
+}
+
+/// Resolves to the result of a job of [`HashService`] once a worker has run it
+pub struct ServiceTask<T>(Task<Result<T, Error>>);
+
+impl<T> Future for ServiceTask<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0)
+            .poll(cx)
+            .map(|result| result.and_then(|result| result))
+    }
+}
+
+impl Drop for HashService {
+    fn drop(&mut self) {
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // The lock is only held while waiting for a job, not while running it
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+fn stopped() -> Error {
+    Error::Task("the hash service workers stopped".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::block_on;
+    use super::*;
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_service_matches_argon2() {
+        let argon2 = Argon2::new(1024, 2, 2);
+        let salt = b"somesaltsomesalt";
+        let service = HashService::new(argon2.clone(), ServiceConfig::default());
+
+        block_on(async {
+            assert_eq!(
+                service.hash_password("password", salt).await.unwrap(),
+                argon2.hash_password("password", salt).unwrap()
+            );
+
+            let encoded = service
+                .hash_password_encoded("password", salt)
+                .await
+                .unwrap();
+            assert!(service.verify_encoded("password", &encoded).await.unwrap());
+            assert!(!service.verify_encoded("wrong", &encoded).await.unwrap());
+            assert!(
+                service
+                    .verify_encoded("password", "$invalid")
+                    .await
+                    .is_err()
+            );
+        });
+    }
+
+    #[test]
+    fn test_reject_when_full() {
+        let config = ServiceConfig {
+            workers: 1,
+            queue_depth: 1,
+            rejection: RejectionPolicy::Reject,
+        };
+        let service = HashService::new(Argon2::new(1024, 2, 1), config);
+
+        // Holds the only worker until released
+        let (started, worker_busy) = std::sync::mpsc::channel();
+        let barrier = Arc::new(Barrier::new(2));
+        let held = {
+            let barrier = barrier.clone();
+            service.submit(move || {
+                started.send(()).unwrap();
+                barrier.wait();
+                Ok(())
+            })
+        };
+        worker_busy.recv().unwrap();
+        let queued = service.submit(|| Ok(()));
+
+        let rejected = service.hash_password("password", b"somesaltsomesalt");
+        assert!(matches!(block_on(rejected), Err(Error::QueueFull)));
+
+        barrier.wait();
+        block_on(held).unwrap();
+        block_on(queued).unwrap();
+    }
+
+    #[test]
+    fn test_wait_when_full() {
+        let config = ServiceConfig {
+            workers: 2,
+            queue_depth: 0,
+            rejection: RejectionPolicy::Wait,
+        };
+        let service = HashService::new(Argon2::new(1024, 2, 1), config);
+        let salt = b"somesaltsomesalt";
+
+        let hashes: Vec<_> = (0..6)
+            .map(|_| service.hash_password("password", salt))
+            .collect();
+        for hash in hashes {
+            assert!(block_on(hash).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_drop_runs_queued_jobs() {
+        let config = ServiceConfig {
+            workers: 1,
+            queue_depth: 8,
+            rejection: RejectionPolicy::Reject,
+        };
+        let service = HashService::new(Argon2::new(1024, 2, 1), config);
+        let hashes: Vec<_> = (0..4)
+            .map(|_| service.hash_password("password", b"somesaltsomesalt"))
+            .collect();
+
+        drop(service);
+        for hash in hashes {
+            assert!(block_on(hash).is_ok());
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use super::{Algorithm, Argon2, Version};
+use argon2::{AssociatedData, Params, ParamsBuilder};
+
+impl From<Algorithm> for argon2::Algorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Argon2d => argon2::Algorithm::Argon2d,
+            Algorithm::Argon2i => argon2::Algorithm::Argon2i,
+            Algorithm::Argon2id => argon2::Algorithm::Argon2id,
+        }
+    }
+}
+
+impl From<argon2::Algorithm> for Algorithm {
+    fn from(algorithm: argon2::Algorithm) -> Self {
+        match algorithm {
+            argon2::Algorithm::Argon2d => Algorithm::Argon2d,
+            argon2::Algorithm::Argon2i => Algorithm::Argon2i,
+            argon2::Algorithm::Argon2id => Algorithm::Argon2id,
+        }
+    }
+}
+
+impl From<Version> for argon2::Version {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::V0x10 => argon2::Version::V0x10,
+            Version::V0x13 => argon2::Version::V0x13,
+        }
+    }
+}
+
+impl From<argon2::Version> for Version {
+    fn from(version: argon2::Version) -> Self {
+        match version {
+            argon2::Version::V0x10 => Version::V0x10,
+            argon2::Version::V0x13 => Version::V0x13,
+        }
+    }
+}
+
+/// Converts the parameters of the RustCrypto `argon2` crate
+///
+/// `Params` do not carry the algorithm and version, the defaults (`Argon2id`, `0x13`) are used as
+/// in the RustCrypto crate. If no output length is set the hash length is
+/// [`Params::DEFAULT_OUTPUT_LEN`]. The key ID is not part of the hash input and is dropped.
+impl From<&Params> for Argon2 {
+    fn from(params: &Params) -> Self {
+        Self {
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+            hash_length: params.output_len().unwrap_or(Params::DEFAULT_OUTPUT_LEN) as u64,
+            ad: params.data().to_vec(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Params> for Argon2 {
+    fn from(params: Params) -> Self {
+        Self::from(&params)
+    }
+}
+
+/// Converts to the parameters of the RustCrypto `argon2` crate
+///
+/// Fails if a parameter is out of the range accepted by the RustCrypto crate, which limits the
+/// associated data to [`AssociatedData::MAX_LEN`] bytes. The algorithm and version are not part of
+/// `Params`, convert them separately when building an `argon2::Argon2`.
+impl TryFrom<&Argon2> for Params {
+    type Error = argon2::Error;
+
+    fn try_from(argon2: &Argon2) -> Result<Self, argon2::Error> {
+        let output_len =
+            usize::try_from(argon2.hash_length).map_err(|_| argon2::Error::OutputTooLong)?;
+
+        ParamsBuilder::new()
+            .m_cost(argon2.m_cost)
+            .t_cost(argon2.t_cost)
+            .p_cost(argon2.p_cost)
+            .output_len(output_len)
+            .data(AssociatedData::new(&argon2.ad)?)
+            .build()
+    }
+}
+
+impl TryFrom<Argon2> for Params {
+    type Error = argon2::Error;
+
+    fn try_from(argon2: Argon2) -> Result<Self, argon2::Error> {
+        Self::try_from(&argon2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_roundtrip() {
+        let argon2 = Argon2::new(1024, 2, 1)
+            .with_hash_length(32)
+            .with_ad(b"context");
+
+        let params = Params::try_from(&argon2).unwrap();
+        assert_eq!(params.m_cost(), 1024);
+        assert_eq!(params.t_cost(), 2);
+        assert_eq!(params.p_cost(), 1);
+        assert_eq!(params.output_len(), Some(32));
+        assert_eq!(Argon2::from(&params), argon2);
+
+        let salt = b"somesaltsomesalt";
+        let rustcrypto =
+            argon2::Argon2::new(argon2.algorithm.into(), argon2.version.into(), params);
+        let mut expected = [0u8; 32];
+        rustcrypto
+            .hash_password_into(b"password", salt, &mut expected)
+            .unwrap();
+        assert_eq!(argon2.hash_password("password", salt).unwrap(), expected);
+
+        assert!(Params::try_from(&Argon2::new(1024, 2, 1).with_ad(&[0u8; 64])).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use super::{Error, phc};
+
+/// The minimum length of a salt in bytes
+pub const MIN_SALT_LENGTH: usize = 8;
+
+/// The recommended length of a salt in bytes
+pub const RECOMMENDED_SALT_LENGTH: usize = 16;
+
+/// Rejects salts shorter than [`MIN_SALT_LENGTH`] before they reach the implementation
+///
+/// RFC 9106 sets the same minimum and both implementations enforce it, there is no way to hash
+/// with a shorter salt. Checking it here names the length instead of a bare error code.
+pub(crate) fn check(salt: &[u8]) -> Result<(), Error> {
+    if salt.len() < MIN_SALT_LENGTH {
+        return Err(Error::SaltTooShort(salt.len()));
+    }
+    Ok(())
+}
+
+/// A salt that is guaranteed to be at least [`MIN_SALT_LENGTH`] bytes long
+///
+/// Derefs to `[u8]` so it can be passed anywhere a salt is expected.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, salt::Salt};
+///
+/// let salt = Salt::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]).unwrap();
+/// let hash = Argon2::new(1024, 2, 1).hash_password("password", &salt).unwrap();
+///
+/// assert!(Salt::new(vec![1, 2, 3]).is_err());
+/// ```
+#[cfg_attr(feature = "bincode", derive(bincode::Encode, bincode::Decode))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Salt(Vec<u8>);
+
+impl Salt {
+    /// Create a new salt from the given bytes
+    ///
+    /// Returns [`Error::SaltTooShort`] if the salt is shorter than [`MIN_SALT_LENGTH`]
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Result<Self, Error> {
+        let bytes = bytes.into();
+        check(&bytes)?;
+        Ok(Self(bytes))
+    }
+
+    /// Generate a new random salt of [`RECOMMENDED_SALT_LENGTH`] bytes using the OS CSPRNG
+    #[cfg(feature = "getrandom")]
+    pub fn generate() -> Result<Self, Error> {
+        let mut bytes = vec![0u8; RECOMMENDED_SALT_LENGTH];
+        getrandom::getrandom(&mut bytes).map_err(|e| Error::Random(e.to_string()))?;
+        Ok(Self(bytes))
+    }
+
+    /// Generate a new random salt of [`RECOMMENDED_SALT_LENGTH`] bytes using the given RNG
+    ///
+    /// ## Arguments
+    ///
+    /// - `rng` - A cryptographically secure RNG
+    #[cfg(feature = "rand_core")]
+    pub fn generate_with(rng: &mut impl rand_core::CryptoRngCore) -> Result<Self, Error> {
+        let mut bytes = vec![0u8; RECOMMENDED_SALT_LENGTH];
+        rng.try_fill_bytes(&mut bytes)
+            .map_err(|e| Error::Random(e.to_string()))?;
+        Ok(Self(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl std::ops::Deref for Salt {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Salt {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl TryFrom<Vec<u8>> for Salt {
+    type Error = Error;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Error> {
+        Self::new(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Salt {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        Self::new(bytes)
+    }
+}
+
+/// A salt in the unpadded B64 form used by the PHC string format
+///
+/// This is the same representation as the salt segment of an encoded hash and the `SaltString`
+/// of the `password_hash` crate, so salts can be moved between implementations as plain text.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::salt::{Salt, SaltString};
+///
+/// let salt = Salt::new(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]).unwrap();
+/// let salt_string = SaltString::from(&salt);
+/// assert_eq!(salt_string.as_str(), "AQIDBAUGBwgJCgsMDQ4PEA");
+/// assert_eq!(salt_string.to_salt(), salt);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SaltString(String);
+
+impl SaltString {
+    /// Create a new salt string from its B64 form
+    ///
+    /// The string must be valid B64 and decode to at least [`MIN_SALT_LENGTH`] bytes
+    pub fn from_b64(s: &str) -> Result<Self, Error> {
+        let bytes = phc::b64_decode(s).ok_or(Error::InvalidPhcString("invalid salt"))?;
+        Salt::new(bytes)?;
+        Ok(Self(s.to_string()))
+    }
+
+    /// Extracts the salt segment of a PHC string
+    ///
+    /// ## Arguments
+    ///
+    /// - `encoded` - The hash in the PHC string format
+    pub fn from_encoded(encoded: &str) -> Result<Self, Error> {
+        let decoded = phc::decode(encoded)?;
+        let salt = Salt::new(decoded.salt)?;
+        Ok(Self::from(&salt))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Decodes the salt into its raw bytes
+    pub fn to_salt(&self) -> Salt {
+        // The string was validated on construction
+        Salt(phc::b64_decode(&self.0).unwrap_or_default())
+    }
+}
+
+impl From<&Salt> for SaltString {
+    fn from(salt: &Salt) -> Self {
+        Self(phc::b64_encode(salt))
+    }
+}
+
+impl std::fmt::Display for SaltString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::str::FromStr for SaltString {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::from_b64(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_salt_length() {
+        assert!(Salt::new(Vec::new()).is_err());
+        assert!(Salt::new(vec![0u8; MIN_SALT_LENGTH - 1]).is_err());
+        assert_eq!(
+            Salt::new(vec![0u8; MIN_SALT_LENGTH]).unwrap().len(),
+            MIN_SALT_LENGTH
+        );
+
+        let argon2 = crate::Argon2::new(1024, 2, 1);
+        assert!(matches!(
+            argon2.hash_password("password", b"s"),
+            Err(Error::SaltTooShort(1))
+        ));
+        assert!(argon2.hash_password("password", b"somesalt").is_ok());
+    }
+
+    #[test]
+    fn test_salt_string() {
+        let salt_string: SaltString = "c29tZXNhbHQ".parse().unwrap();
+        assert_eq!(salt_string.to_salt().as_bytes(), b"somesalt");
+        assert_eq!(salt_string.to_string(), "c29tZXNhbHQ");
+
+        assert!(SaltString::from_b64("c29tZQ").is_err());
+        assert!(SaltString::from_b64("c29tZXNhbHQ!").is_err());
+
+        let encoded = "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaGhhc2g";
+        assert_eq!(SaltString::from_encoded(encoded).unwrap(), salt_string);
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn test_generate() {
+        let a = Salt::generate().unwrap();
+        let b = Salt::generate().unwrap();
+        assert_eq!(a.len(), RECOMMENDED_SALT_LENGTH);
+        assert_ne!(a, b);
+    }
+
+    #[cfg(feature = "rand_core")]
+    #[test]
+    fn test_generate_with() {
+        struct CountingRng(u8);
+
+        impl rand_core::RngCore for CountingRng {
+            fn next_u32(&mut self) -> u32 {
+                rand_core::impls::next_u32_via_fill(self)
+            }
+
+            fn next_u64(&mut self) -> u64 {
+                rand_core::impls::next_u64_via_fill(self)
+            }
+
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                for byte in dest {
+                    self.0 = self.0.wrapping_add(1);
+                    *byte = self.0;
+                }
+            }
+
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        impl rand_core::CryptoRng for CountingRng {}
+
+        let salt = Salt::generate_with(&mut CountingRng(0)).unwrap();
+        assert_eq!(salt.as_bytes(), (1..=16).collect::<Vec<u8>>());
+    }
+}
@@ -0,0 +1,490 @@
+use super::{
+    Argon2, Error, Flags, allocator::Block, backend, constant_time_eq, output::wipe, phc, record,
+};
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Set in the environment of the child so [`serve_if_child`] knows to serve a hash
+const CHILD_ENV: &str = "ARGON2_RS_SANDBOX_CHILD";
+
+/// Precedes the response of the child on its stdout, anything before it is ignored
+const RESPONSE_MAGIC: &[u8] = b"\0argon2-rs sandbox\0";
+
+/// The capacity reserved for an error message of the child, it cannot allocate once restricted
+const MESSAGE_CAPACITY: usize = 1024;
+
+/// Hashes in a short-lived child process that cannot make system calls besides its pipes
+///
+/// A memory-safety bug in the C implementation reached through crafted parameters or a crafted
+/// PHC string then stays within the child. The child is the program of this process, or the one
+/// given with [`Sandbox::with_program`], started again with an environment variable that
+/// [`serve_if_child`] recognizes. It reads the request from a pipe, allocates everything it needs,
+/// restricts itself and hashes on a single thread.
+///
+/// On Linux the child only keeps `read`, `write`, `futex` and `exit` through a seccomp filter, on
+/// OpenBSD it pledges `stdio`. Other systems isolate the hash in its own process without
+/// restricting it.
+///
+/// Each hash pays for starting a process, use it where the isolation is worth a few milliseconds.
+///
+/// ## Example
+///
+/// ```no_run
+/// use argon2_rs::{Argon2, sandbox::{self, Sandbox}};
+///
+/// fn main() {
+///     // Must come first, the child exits here
+///     sandbox::serve_if_child();
+///
+///     let sandbox = Sandbox::new().unwrap();
+///     let hash = Argon2::new(1024, 2, 1)
+///         .hash_password_sandboxed("password", b"somesaltsomesalt", &sandbox)
+///         .unwrap();
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sandbox {
+    program: PathBuf,
+    args: Vec<OsString>,
+}
+
+impl Sandbox {
+    /// Starts the children from the executable of this process
+    pub fn new() -> Result<Self, Error> {
+        let program = std::env::current_exe().map_err(|e| Error::Sandbox(e.to_string()))?;
+        Ok(Self::with_program(program))
+    }
+
+    /// Starts the children from the given program, which must call [`serve_if_child`]
+    pub fn with_program(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Passes the given arguments to the children
+    pub fn with_args(mut self, args: impl IntoIterator<Item = impl Into<OsString>>) -> Self {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sends the request to a new child and returns the hash it computed
+    fn hash(&self, argon2: &Argon2, password: &[u8], salt: &[u8]) -> Result<Vec<u8>, Error> {
+        let error = |e: std::io::Error| Error::Sandbox(e.to_string());
+        // The request carries the length as a `u32`
+        argon2.hash_len()?;
+
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .env(CHILD_ENV, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(error)?;
+
+        let mut request = encode_request(argon2, password, salt);
+        let written = child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(&request);
+        wipe(&mut request);
+
+        let mut output = Vec::new();
+        let read = child
+            .stdout
+            .take()
+            .expect("stdout is piped")
+            .read_to_end(&mut output);
+        let status = child.wait().map_err(error)?;
+        written.and(read).map_err(error)?;
+
+        let response = find(&output, RESPONSE_MAGIC).map(|at| &output[at + RESPONSE_MAGIC.len()..]);
+        match response.and_then(<[u8]>::split_first) {
+            Some((0, hash)) => Ok(hash.to_vec()),
+            Some((_, message)) => Err(Error::Sandbox(
+                String::from_utf8_lossy(message).into_owned(),
+            )),
+            None => Err(Error::Sandbox(exited(status))),
+        }
+    }
+}
+
+/// Describes how a child that sent no response exited
+fn exited(status: std::process::ExitStatus) -> String {
+    use std::os::unix::process::ExitStatusExt;
+
+    match status.signal() {
+        Some(signal) => format!("the child was killed by signal {signal}"),
+        None => format!("the child exited with {status} without a response"),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The parameters, salt and password as a binary record followed by the hash length
+fn encode_request(argon2: &Argon2, password: &[u8], salt: &[u8]) -> Vec<u8> {
+    // The password takes the place of the hash, the record has no field for the hash length
+    let mut record = record::encode(argon2, None, salt, password);
+
+    let mut request = Vec::with_capacity(record.len() + 8);
+    request.extend_from_slice(&(record.len() as u32).to_le_bytes());
+    request.extend_from_slice(&record);
+    request.extend_from_slice(&(argon2.hash_length as u32).to_le_bytes());
+    wipe(&mut record);
+    request
+}
+
+/// The parameters with the hash length, the salt and the password of a request
+fn decode_request(request: &[u8]) -> Result<(Argon2, Vec<u8>, Vec<u8>), Error> {
+    let invalid = Error::InvalidRecord("truncated sandbox request");
+
+    let (len, rest) = request.split_first_chunk::<4>().ok_or(invalid)?;
+    let len = u32::from_le_bytes(*len) as usize;
+    if rest.len() != len + 4 {
+        return Err(Error::InvalidRecord("truncated sandbox request"));
+    }
+
+    let (record, hash_length) = rest.split_at(len);
+    let mut decoded = record::decode(record)?;
+    decoded.argon2.hash_length = u32::from_le_bytes(hash_length.try_into().unwrap()) as u64;
+    Ok((decoded.argon2, decoded.salt, decoded.hash))
+}
+
+/// Serves a hash and exits if this process was started as the child of a [`Sandbox`]
+///
+/// Call it first thing in `main`, before any other thread is spawned, it returns right away in
+/// any other process. Only the calling thread is restricted, the process exits with it.
+pub fn serve_if_child() {
+    if std::env::var_os(CHILD_ENV).is_none() {
+        return;
+    }
+
+    let mut response = Vec::new();
+    let code = match serve(&mut response) {
+        Ok(()) => 0,
+        Err(e) => {
+            use std::fmt::Write as _;
+
+            response.clear();
+            response.push(1);
+            let mut message = String::with_capacity(MESSAGE_CAPACITY);
+            let _ = write!(message, "{e}");
+            response.extend_from_slice(&message.as_bytes()[..message.len().min(MESSAGE_CAPACITY)]);
+            1
+        }
+    };
+
+    write_all(libc::STDOUT_FILENO, RESPONSE_MAGIC);
+    write_all(libc::STDOUT_FILENO, &response);
+    unsafe { libc::_exit(code) };
+}
+
+/// Reads the request and hashes it into `response`, which has room for the hash
+///
+/// Once restricted the child can neither allocate nor free, every buffer the hash needs is
+/// prepared before and the ones still alive after it are leaked, the process exits right after.
+fn serve(response: &mut Vec<u8>) -> Result<(), Error> {
+    let mut request = Vec::new();
+    std::io::stdin()
+        .read_to_end(&mut request)
+        .map_err(|e| Error::Sandbox(e.to_string()))?;
+    let decoded = decode_request(&request);
+    wipe(&mut request);
+    drop(request);
+    let (argon2, salt, mut password) = decoded?;
+
+    // Normalizes and pre-hashes now, the hash is given the result
+    if let Some(prepared) = backend::prepare_password(&argon2, &password)? {
+        wipe(&mut password);
+        password = prepared.to_vec();
+    }
+
+    let argon2 = Argon2 {
+        prehash: None,
+        normalization: None,
+        // A clear flag would make the hash copy the password
+        flags: Flags::default(),
+        // A single thread, spawning the lane threads needs system calls the filter denies
+        threads: Some(1),
+        ..argon2
+    };
+    let hash_length = argon2.hash_len()?;
+    let mut memory = Block::try_alloc(argon2.memory_blocks()).ok_or(Error::OutOfMemory)?;
+    response
+        .try_reserve_exact(1 + hash_length + MESSAGE_CAPACITY)
+        .map_err(|_| Error::OutOfMemory)?;
+    response.push(0);
+    response.resize(1 + hash_length, 0);
+
+    // Initializes the thread locals of the hash while system calls are still allowed
+    Argon2::new(8, 1, 1).hash_password_in_memory(
+        b"password",
+        &[0; 16],
+        &mut [0; 32],
+        &mut [Block::ZERO; 8],
+    )?;
+
+    restrict()?;
+    let result = argon2.hash_password_in_memory(&password, &salt, &mut response[1..], &mut memory);
+    wipe(&mut password);
+    // Freeing would unmap memory or trim the heap, which the filter denies
+    std::mem::forget((memory, password, salt, argon2));
+    result
+}
+
+fn write_all(fd: libc::c_int, mut bytes: &[u8]) {
+    while !bytes.is_empty() {
+        let written = unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+        if written <= 0 {
+            return;
+        }
+        bytes = &bytes[written as usize..];
+    }
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+/// Denies every system call of the calling thread besides reading, writing, waking and exiting
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn restrict() -> Result<(), Error> {
+    use libc::{BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, sock_filter};
+
+    let stmt = |code: u32, k: u32| sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+    let jump = |k: u32, jt: u8, jf: u8| sock_filter {
+        code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+        jt,
+        jf,
+        k,
+    };
+    let kill = stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_KILL_PROCESS);
+
+    let mut filter = vec![
+        stmt(
+            BPF_LD | BPF_W | BPF_ABS,
+            std::mem::offset_of!(libc::seccomp_data, arch) as u32,
+        ),
+        jump(AUDIT_ARCH, 1, 0),
+        kill,
+        stmt(
+            BPF_LD | BPF_W | BPF_ABS,
+            std::mem::offset_of!(libc::seccomp_data, nr) as u32,
+        ),
+    ];
+    // Releasing the permit of `limit` wakes the waiting hashes with `futex` even when none waits
+    for nr in [
+        libc::SYS_read,
+        libc::SYS_write,
+        libc::SYS_exit,
+        libc::SYS_exit_group,
+        libc::SYS_rt_sigreturn,
+        libc::SYS_futex,
+    ] {
+        filter.push(jump(nr as u32, 0, 1));
+        filter.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW));
+    }
+    filter.push(kill);
+
+    let program = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_mut_ptr(),
+    };
+    let failed = unsafe {
+        libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+            || libc::prctl(libc::PR_SET_SECCOMP, libc::SECCOMP_MODE_FILTER, &program) != 0
+    };
+    if failed {
+        return Err(Error::Sandbox(format!(
+            "failed to install the seccomp filter: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+/// Refuses to hash unrestricted on Linux architectures the filter does not know
+#[cfg(all(
+    target_os = "linux",
+    not(any(target_arch = "x86_64", target_arch = "aarch64"))
+))]
+fn restrict() -> Result<(), Error> {
+    Err(Error::Sandbox(
+        "seccomp is not supported on this architecture".to_string(),
+    ))
+}
+
+/// Keeps only the `stdio` promise, which covers reading, writing and memory
+#[cfg(target_os = "openbsd")]
+fn restrict() -> Result<(), Error> {
+    if unsafe { libc::pledge(c"stdio".as_ptr(), std::ptr::null()) } != 0 {
+        return Err(Error::Sandbox(format!(
+            "failed to pledge: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "openbsd")))]
+fn restrict() -> Result<(), Error> {
+    Ok(())
+}
+
+impl Argon2 {
+    /// Hashes the given password in a child process, see [`Sandbox`]
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash, either a string or raw bytes
+    /// - `salt` - The salt to use for hashing
+    /// - `sandbox` - Starts the child process
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::Sandbox`] if the child fails to start, to restrict itself or to hash, or is
+    /// killed for a denied system call
+    pub fn hash_password_sandboxed(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+        sandbox: &Sandbox,
+    ) -> Result<Vec<u8>, Error> {
+        sandbox.hash(self, password.as_ref(), salt)
+    }
+
+    /// Verifies the given password against a PHC string in a child process, see [`Sandbox`]
+    ///
+    /// The PHC string is parsed in this process, only its parameters reach the C implementation
+    /// in the child.
+    pub fn verify_encoded_sandboxed(
+        password: impl AsRef<[u8]>,
+        encoded: &str,
+        sandbox: &Sandbox,
+    ) -> Result<bool, Error> {
+        let decoded = phc::decode(encoded)?;
+
+        if decoded.keyid.is_some() {
+            return Err(Error::PepperRequired);
+        }
+
+        let argon2 = decoded.argon2.with_hash_length(decoded.hash.len() as u64);
+        let hash = sandbox.hash(&argon2, password.as_ref(), &decoded.salt)?;
+        Ok(constant_time_eq(&hash, &decoded.hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs only the given test of this test binary as the child
+    fn sandbox(test: &str) -> Sandbox {
+        Sandbox::new()
+            .unwrap()
+            .with_args([test, "--exact", "--test-threads=1", "--quiet"])
+    }
+
+    #[test]
+    fn child() {
+        serve_if_child();
+    }
+
+    #[test]
+    fn escaping_child() {
+        if std::env::var_os(CHILD_ENV).is_some() {
+            restrict().unwrap();
+            // Denied, the child is killed before it can open anything
+            let _ = std::fs::File::open("/etc/passwd");
+            write_all(libc::STDOUT_FILENO, b"escaped");
+            unsafe { libc::_exit(0) };
+        }
+    }
+
+    #[test]
+    fn test_request_roundtrip() {
+        let argon2 = Argon2::new(1024, 3, 2).with_hash_length(48);
+        let request = encode_request(&argon2, b"password", b"somesaltsomesalt");
+
+        let (decoded, salt, password) = decode_request(&request).unwrap();
+        assert_eq!(decoded, argon2);
+        assert_eq!(salt, b"somesaltsomesalt");
+        assert_eq!(password, b"password");
+
+        assert!(decode_request(&request[..request.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_sandboxed_matches_argon2() {
+        let sandbox = sandbox("sandbox::tests::child");
+        let salt = b"somesaltsomesalt";
+
+        for argon2 in [Argon2::new(1024, 2, 1), Argon2::new(2048, 1, 4)] {
+            assert_eq!(
+                argon2
+                    .hash_password_sandboxed("password", salt, &sandbox)
+                    .unwrap(),
+                argon2.hash_password("password", salt).unwrap()
+            );
+
+            let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+            assert!(Argon2::verify_encoded_sandboxed("password", &encoded, &sandbox).unwrap());
+            assert!(!Argon2::verify_encoded_sandboxed("wrong", &encoded, &sandbox).unwrap());
+        }
+
+        // Large passwords are freed before the child restricts itself
+        let password = vec![b'a'; 1024 * 1024];
+        for argon2 in [
+            Argon2::new(1024, 2, 1),
+            Argon2::new(1024, 2, 1).with_prehash(1024),
+        ] {
+            assert_eq!(
+                argon2
+                    .hash_password_sandboxed(&password, salt, &sandbox)
+                    .unwrap(),
+                argon2.hash_password(&password, salt).unwrap()
+            );
+        }
+
+        // The error of the child is sent back
+        assert!(matches!(
+            Argon2::new(1024, 2, 1).hash_password_sandboxed("password", b"short", &sandbox),
+            Err(Error::Sandbox(message)) if message.contains("Salt")
+        ));
+    }
+
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    #[test]
+    fn test_denied_system_call_kills_child() {
+        let result = Argon2::new(1024, 2, 1).hash_password_sandboxed(
+            "password",
+            b"somesaltsomesalt",
+            &sandbox("sandbox::tests::escaping_child"),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::Sandbox(message)) if message.contains("signal 31")
+        ));
+    }
+}
@@ -0,0 +1,301 @@
+use super::{Algorithm, Argon2, RECOMMENDED_HASH_LENGTH, Version, phc};
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Serialized as its PHC identifier, e.g. `"argon2id"`
+impl Serialize for Algorithm {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Algorithm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <Cow<'de, str>>::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| de::Error::unknown_variant(&name, &["argon2d", "argon2i", "argon2id"]))
+    }
+}
+
+/// Serialized in hex, e.g. `"0x13"`
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct VersionVisitor;
+
+impl Visitor<'_> for VersionVisitor {
+    type Value = Version;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an Argon2 version such as \"0x13\" or 19")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Version, E> {
+        u32::try_from(v)
+            .ok()
+            .and_then(|v| Version::from_u32(v).ok())
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Version, E> {
+        u64::try_from(v)
+            .map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+            .and_then(|v| self.visit_u64(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Version, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+/// Accepts the version in hex (`"0x13"`), in decimal (`"19"`) or as a number
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(VersionVisitor)
+    }
+}
+
+const FIELDS: &[&str] = &[
+    "m_cost",
+    "t_cost",
+    "p_cost",
+    "hash_length",
+    "algorithm",
+    "version",
+    "ad",
+    "prehash",
+    "normalization",
+    "threads",
+];
+
+/// Serialized as a struct with the associated data in the B64 form of PHC strings
+///
+/// The flags are not serialized, they do not affect the resulting hash. The number of threads is
+/// only serialized when set.
+impl Serialize for Argon2 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Argon2", FIELDS.len())?;
+        state.serialize_field("m_cost", &self.m_cost)?;
+        state.serialize_field("t_cost", &self.t_cost)?;
+        state.serialize_field("p_cost", &self.p_cost)?;
+        state.serialize_field("hash_length", &self.hash_length)?;
+        state.serialize_field("algorithm", &self.algorithm)?;
+        state.serialize_field("version", &self.version)?;
+
+        if self.ad.is_empty() {
+            state.skip_field("ad")?;
+        } else {
+            state.serialize_field("ad", &phc::b64_encode(&self.ad))?;
+        }
+
+        match self.prehash {
+            Some(threshold) => state.serialize_field("prehash", &threshold)?,
+            None => state.skip_field("prehash")?,
+        }
+
+        match self.normalization {
+            Some(normalization) => {
+                state.serialize_field("normalization", normalization.as_str())?
+            }
+            None => state.skip_field("normalization")?,
+        }
+
+        match self.threads {
+            Some(threads) => state.serialize_field("threads", &threads)?,
+            None => state.skip_field("threads")?,
+        }
+
+        state.end()
+    }
+}
+
+struct Argon2Visitor;
+
+impl<'de> Visitor<'de> for Argon2Visitor {
+    type Value = Argon2;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Argon2 parameters")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Argon2, A::Error> {
+        let (mut m_cost, mut t_cost, mut p_cost) = (None, None, None);
+        let mut argon2 = Argon2 {
+            hash_length: RECOMMENDED_HASH_LENGTH,
+            ..Default::default()
+        };
+
+        while let Some(key) = map.next_key::<Cow<'de, str>>()? {
+            match key.as_ref() {
+                "m_cost" => m_cost = Some(map.next_value()?),
+                "t_cost" => t_cost = Some(map.next_value()?),
+                "p_cost" => p_cost = Some(map.next_value()?),
+                "hash_length" => argon2.hash_length = map.next_value()?,
+                "algorithm" => argon2.algorithm = map.next_value()?,
+                "version" => argon2.version = map.next_value()?,
+                "ad" => {
+                    let ad = map.next_value::<Cow<'de, str>>()?;
+                    argon2.ad = phc::b64_decode(&ad).ok_or_else(|| {
+                        de::Error::invalid_value(de::Unexpected::Str(&ad), &"B64 encoded bytes")
+                    })?;
+                }
+                "prehash" => argon2.prehash = Some(map.next_value()?),
+                "normalization" => {
+                    let name = map.next_value::<Cow<'de, str>>()?;
+                    let normalization = name
+                        .parse()
+                        .map_err(|_| de::Error::unknown_variant(&name, &["nfc", "nfkc"]))?;
+                    argon2.normalization = Some(normalization);
+                }
+                "threads" => argon2.threads = Some(map.next_value()?),
+                other => return Err(de::Error::unknown_field(other, FIELDS)),
+            }
+        }
+
+        argon2.m_cost = m_cost.ok_or_else(|| de::Error::missing_field("m_cost"))?;
+        argon2.t_cost = t_cost.ok_or_else(|| de::Error::missing_field("t_cost"))?;
+        argon2.p_cost = p_cost.ok_or_else(|| de::Error::missing_field("p_cost"))?;
+        Ok(argon2)
+    }
+}
+
+/// `m_cost`, `t_cost` and `p_cost` are required, the other fields default to the values of
+/// [`Argon2::new`]
+impl<'de> Deserialize<'de> for Argon2 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("Argon2", FIELDS, Argon2Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::IntoDeserializer;
+    use serde::de::value::{
+        Error as ValueError, MapDeserializer, StrDeserializer, U64Deserializer,
+    };
+
+    /// A config value as found in TOML or JSON
+    enum Value {
+        Num(u64),
+        Str(&'static str),
+    }
+
+    impl<'de> IntoDeserializer<'de, ValueError> for Value {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    impl<'de> Deserializer<'de> for Value {
+        type Error = ValueError;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+            match self {
+                Value::Num(n) => visitor.visit_u64(n),
+                Value::Str(s) => visitor.visit_borrowed_str(s),
+            }
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    fn deserialize_argon2(fields: Vec<(&'static str, Value)>) -> Result<Argon2, ValueError> {
+        Argon2::deserialize(MapDeserializer::new(fields.into_iter()))
+    }
+
+    #[test]
+    fn test_deserialize_enums() {
+        let algorithm = Algorithm::deserialize(StrDeserializer::<ValueError>::new("argon2i"));
+        assert_eq!(algorithm.unwrap(), Algorithm::Argon2i);
+        assert!(Algorithm::deserialize(StrDeserializer::<ValueError>::new("argon3")).is_err());
+
+        let version = Version::deserialize(StrDeserializer::<ValueError>::new("0x10"));
+        assert_eq!(version.unwrap(), Version::V0x10);
+        let version = Version::deserialize(U64Deserializer::<ValueError>::new(19));
+        assert_eq!(version.unwrap(), Version::V0x13);
+        assert!(Version::deserialize(U64Deserializer::<ValueError>::new(20)).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_argon2() {
+        let argon2 = deserialize_argon2(vec![
+            ("m_cost", Value::Num(65536)),
+            ("t_cost", Value::Num(3)),
+            ("p_cost", Value::Num(4)),
+            ("algorithm", Value::Str("argon2i")),
+            ("version", Value::Str("0x13")),
+            ("ad", Value::Str("dXNlcjE")),
+            ("prehash", Value::Num(1024)),
+            ("normalization", Value::Str("nfc")),
+            ("threads", Value::Num(2)),
+        ])
+        .unwrap();
+
+        let expected = Argon2::new(65536, 3, 4)
+            .with_algorithm(Algorithm::Argon2i)
+            .with_ad(b"user1")
+            .with_prehash(1024)
+            .with_normalization(crate::normalize::Normalization::Nfc)
+            .with_threads(2);
+        assert_eq!(argon2, expected);
+
+        let defaults = deserialize_argon2(vec![
+            ("m_cost", Value::Num(1024)),
+            ("t_cost", Value::Num(2)),
+            ("p_cost", Value::Num(1)),
+        ]);
+        assert_eq!(defaults.unwrap(), Argon2::new(1024, 2, 1));
+
+        assert!(deserialize_argon2(vec![("m_cost", Value::Num(1024))]).is_err());
+        assert!(
+            deserialize_argon2(vec![
+                ("m_cost", Value::Num(1024)),
+                ("t_cost", Value::Num(2)),
+                ("p_cost", Value::Num(1)),
+                ("memory", Value::Num(1)),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let argon2 = Argon2::new(65536, 3, 4)
+            .with_hash_length(32)
+            .with_algorithm(Algorithm::Argon2d)
+            .with_version(Version::V0x10)
+            .with_ad(b"user1")
+            .with_prehash(1024)
+            .with_normalization(crate::normalize::Normalization::Nfkc)
+            .with_threads(2);
+
+        let json = serde_json::to_string(&argon2).unwrap();
+        assert_eq!(
+            json,
+            r#"{"m_cost":65536,"t_cost":3,"p_cost":4,"hash_length":32,"algorithm":"argon2d","version":"0x10","ad":"dXNlcjE","prehash":1024,"normalization":"nfkc","threads":2}"#
+        );
+        assert_eq!(serde_json::from_str::<Argon2>(&json).unwrap(), argon2);
+
+        // Unset optional fields are left out and read back as unset
+        let plain = Argon2::new(1024, 2, 1);
+        let json = serde_json::to_string(&plain).unwrap();
+        for field in ["ad", "prehash", "normalization", "threads"] {
+            assert!(!json.contains(field), "{json}");
+        }
+        assert_eq!(serde_json::from_str::<Argon2>(&json).unwrap(), plain);
+    }
+}
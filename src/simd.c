@@ -0,0 +1,14 @@
+/* Compiled with the same flags as the optimized argon2 implementation, reports the
+   instruction set it was built for */
+
+const char *argon2rs_simd_backend(void) {
+#if defined(__AVX512F__)
+    return "avx512";
+#elif defined(__AVX2__)
+    return "avx2";
+#elif defined(__SSSE3__)
+    return "ssse3";
+#else
+    return "sse2";
+#endif
+}
@@ -0,0 +1,130 @@
+/// The code path the Argon2 compression function is compiled to
+///
+/// The C implementation has no runtime dispatch, the code path is chosen when the crate is built:
+///
+/// - Without the `simd` feature the portable reference implementation is used.
+/// - With the `simd` feature the optimized implementation is compiled with `-march=native`, it
+///   uses the widest instruction set of the build machine.
+///
+/// To pin a consistent code path across a fleet, build with the `simd` feature and limit the
+/// instruction set through `CFLAGS`, e.g. `CFLAGS="-mno-avx512f"` for AVX2 or
+/// `CFLAGS="-mno-avx512f -mno-avx2"` for SSSE3. Builds on AMD machines with XOP additionally use
+/// XOP rotations, disable them with `-mno-xop`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SimdBackend {
+    /// The portable C reference implementation
+    Reference,
+    Sse2,
+    Ssse3,
+    Avx2,
+    Avx512,
+    /// The RustCrypto implementation used with the `pure-rust` feature and on wasm32
+    RustCrypto,
+}
+
+impl SimdBackend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reference => "reference",
+            Self::Sse2 => "sse2",
+            Self::Ssse3 => "ssse3",
+            Self::Avx2 => "avx2",
+            Self::Avx512 => "avx512",
+            Self::RustCrypto => "rustcrypto",
+        }
+    }
+
+    /// Whether the CPU of this machine supports the instructions of the code path
+    ///
+    /// A binary built on a newer machine crashes with an illegal instruction on an older one,
+    /// check this at startup when deploying to a heterogeneous fleet.
+    pub fn is_supported(&self) -> bool {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            match self {
+                Self::Reference | Self::RustCrypto => true,
+                Self::Sse2 => std::is_x86_feature_detected!("sse2"),
+                Self::Ssse3 => std::is_x86_feature_detected!("ssse3"),
+                Self::Avx2 => std::is_x86_feature_detected!("avx2"),
+                Self::Avx512 => std::is_x86_feature_detected!("avx512f"),
+            }
+        }
+
+        #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+        {
+            matches!(self, Self::Reference | Self::RustCrypto)
+        }
+    }
+}
+
+impl std::fmt::Display for SimdBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The code path this build hashes with
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::simd;
+///
+/// let backend = simd::backend();
+/// println!("hashing with the {backend} backend");
+/// assert!(backend.is_supported());
+/// ```
+pub fn backend() -> SimdBackend {
+    #[cfg(any(target_arch = "wasm32", feature = "pure-rust"))]
+    {
+        SimdBackend::RustCrypto
+    }
+
+    #[cfg(all(
+        feature = "simd",
+        not(feature = "pure-rust"),
+        any(target_arch = "x86", target_arch = "x86_64")
+    ))]
+    {
+        unsafe extern "C" {
+            fn argon2rs_simd_backend() -> *const std::ffi::c_char;
+        }
+
+        let name = unsafe { std::ffi::CStr::from_ptr(argon2rs_simd_backend()) };
+        match name.to_bytes() {
+            b"avx512" => SimdBackend::Avx512,
+            b"avx2" => SimdBackend::Avx2,
+            b"ssse3" => SimdBackend::Ssse3,
+            _ => SimdBackend::Sse2,
+        }
+    }
+
+    #[cfg(not(any(
+        target_arch = "wasm32",
+        feature = "pure-rust",
+        all(feature = "simd", any(target_arch = "x86", target_arch = "x86_64"))
+    )))]
+    {
+        SimdBackend::Reference
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend() {
+        let backend = backend();
+        assert!(backend.is_supported());
+
+        #[cfg(feature = "pure-rust")]
+        assert_eq!(backend, SimdBackend::RustCrypto);
+
+        #[cfg(not(any(feature = "simd", feature = "pure-rust")))]
+        assert_eq!(backend, SimdBackend::Reference);
+
+        #[cfg(all(feature = "simd", not(feature = "pure-rust"), target_arch = "x86_64"))]
+        assert_ne!(backend, SimdBackend::Reference);
+    }
+}
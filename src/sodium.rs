@@ -0,0 +1,206 @@
+use super::{Algorithm, Argon2, Error, Version};
+
+/// `crypto_pwhash_OPSLIMIT_MIN`
+pub const OPSLIMIT_MIN: u32 = 1;
+/// `crypto_pwhash_MEMLIMIT_MIN`
+pub const MEMLIMIT_MIN: usize = 8192;
+
+/// `crypto_pwhash_OPSLIMIT_INTERACTIVE`
+pub const OPSLIMIT_INTERACTIVE: u32 = 2;
+/// `crypto_pwhash_MEMLIMIT_INTERACTIVE` (64 MiB)
+pub const MEMLIMIT_INTERACTIVE: usize = 67_108_864;
+
+/// `crypto_pwhash_OPSLIMIT_MODERATE`
+pub const OPSLIMIT_MODERATE: u32 = 3;
+/// `crypto_pwhash_MEMLIMIT_MODERATE` (256 MiB)
+pub const MEMLIMIT_MODERATE: usize = 268_435_456;
+
+/// `crypto_pwhash_OPSLIMIT_SENSITIVE`
+pub const OPSLIMIT_SENSITIVE: u32 = 4;
+/// `crypto_pwhash_MEMLIMIT_SENSITIVE` (1 GiB)
+pub const MEMLIMIT_SENSITIVE: usize = 1_073_741_824;
+
+/// `crypto_pwhash_SALTBYTES`
+pub const SALTBYTES: usize = 16;
+
+/// The length of the hash embedded by `crypto_pwhash_str`
+pub const STR_HASH_LENGTH: u64 = 32;
+
+/// The `Argon2` parameters libsodium uses for the given limits
+///
+/// libsodium expresses the cost of a hash as an `opslimit` (iterations) and a `memlimit` (bytes).
+/// The algorithm is `Argon2id` version `0x13` (`crypto_pwhash_ALG_DEFAULT`) with a single lane and
+/// a [`STR_HASH_LENGTH`] byte hash.
+///
+/// ## Arguments
+///
+/// - `opslimit` - The number of iterations
+/// - `memlimit` - The memory cost in bytes, rounded down to whole kibibytes
+pub fn params(opslimit: u32, memlimit: usize) -> Argon2 {
+    let m_cost = u32::try_from(memlimit / 1024).unwrap_or(u32::MAX);
+
+    Argon2 {
+        m_cost,
+        t_cost: opslimit,
+        p_cost: 1,
+        hash_length: STR_HASH_LENGTH,
+        algorithm: Algorithm::Argon2id,
+        version: Version::V0x13,
+        ..Default::default()
+    }
+}
+
+/// Equivalent of `crypto_pwhash`, derives a key of `out.len()` bytes
+///
+/// ## Arguments
+///
+/// - `out` - The buffer to write the key into
+/// - `password` - The password to derive the key from
+/// - `salt` - The salt, libsodium requires exactly [`SALTBYTES`] bytes
+/// - `opslimit` - The number of iterations
+/// - `memlimit` - The memory cost in bytes
+pub fn derive_key(
+    out: &mut [u8],
+    password: impl AsRef<[u8]>,
+    salt: &[u8; SALTBYTES],
+    opslimit: u32,
+    memlimit: usize,
+) -> Result<(), Error> {
+    params(opslimit, memlimit).hash_password_into(password, salt, out)
+}
+
+/// Equivalent of `crypto_pwhash_str` with a caller-provided salt
+///
+/// The string is identical to the one produced by libsodium for the same salt.
+///
+/// ## Arguments
+///
+/// - `password` - The password to hash
+/// - `salt` - The salt to use for hashing
+/// - `opslimit` - The number of iterations
+/// - `memlimit` - The memory cost in bytes
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::sodium;
+///
+/// let salt = [7u8; sodium::SALTBYTES];
+/// let encoded = sodium::hash_str_with_salt(
+///     "password",
+///     &salt,
+///     sodium::OPSLIMIT_MIN,
+///     sodium::MEMLIMIT_MIN,
+/// )
+/// .unwrap();
+///
+/// assert!(encoded.starts_with("$argon2id$v=19$m=8,t=1,p=1$"));
+/// assert!(sodium::verify_str("password", &encoded).unwrap());
+/// ```
+pub fn hash_str_with_salt(
+    password: impl AsRef<[u8]>,
+    salt: &[u8; SALTBYTES],
+    opslimit: u32,
+    memlimit: usize,
+) -> Result<String, Error> {
+    params(opslimit, memlimit).hash_password_encoded(password, salt)
+}
+
+/// Equivalent of `crypto_pwhash_str`, the salt is generated with the OS CSPRNG
+///
+/// ## Arguments
+///
+/// - `password` - The password to hash
+/// - `opslimit` - The number of iterations
+/// - `memlimit` - The memory cost in bytes
+#[cfg(feature = "getrandom")]
+pub fn hash_str(
+    password: impl AsRef<[u8]>,
+    opslimit: u32,
+    memlimit: usize,
+) -> Result<String, Error> {
+    let mut salt = [0u8; SALTBYTES];
+    getrandom::getrandom(&mut salt).map_err(|e| Error::Random(e.to_string()))?;
+    hash_str_with_salt(password, &salt, opslimit, memlimit)
+}
+
+/// Equivalent of `crypto_pwhash_str_verify`
+///
+/// Accepts both `$argon2id$` and `$argon2i$` strings as libsodium does.
+pub fn verify_str(password: impl AsRef<[u8]>, encoded: &str) -> Result<bool, Error> {
+    Argon2::verify_encoded(password, encoded)
+}
+
+/// Equivalent of `crypto_pwhash_str_needs_rehash`
+///
+/// Returns `true` if the string was not created with the given limits.
+pub fn str_needs_rehash(encoded: &str, opslimit: u32, memlimit: usize) -> Result<bool, Error> {
+    params(opslimit, memlimit).needs_rehash(encoded)
+}
+
+// libsodium presets
+impl Argon2 {
+    /// The parameters of libsodium for interactive logins, 64 MiB of memory and 2 passes
+    ///
+    /// Same as [`params`] with [`OPSLIMIT_INTERACTIVE`] and [`MEMLIMIT_INTERACTIVE`], hashes are
+    /// interchangeable with `crypto_pwhash_str`.
+    pub fn interactive() -> Self {
+        params(OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE)
+    }
+
+    /// The parameters of libsodium for moderately sensitive data, 256 MiB of memory and 3 passes
+    pub fn moderate() -> Self {
+        params(OPSLIMIT_MODERATE, MEMLIMIT_MODERATE)
+    }
+
+    /// The parameters of libsodium for highly sensitive data, 1 GiB of memory and 4 passes
+    pub fn sensitive() -> Self {
+        params(OPSLIMIT_SENSITIVE, MEMLIMIT_SENSITIVE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params() {
+        let argon2 = params(OPSLIMIT_INTERACTIVE, MEMLIMIT_INTERACTIVE);
+        assert_eq!(argon2.m_cost, 65536);
+        assert_eq!(argon2.t_cost, 2);
+        assert_eq!(argon2.p_cost, 1);
+
+        assert_eq!(params(OPSLIMIT_MODERATE, MEMLIMIT_MODERATE).m_cost, 262144);
+        assert_eq!(
+            params(OPSLIMIT_SENSITIVE, MEMLIMIT_SENSITIVE).m_cost,
+            1048576
+        );
+
+        assert_eq!(Argon2::interactive(), argon2);
+        assert_eq!(
+            Argon2::moderate(),
+            Argon2::new(262144, 3, 1).with_hash_length(32)
+        );
+        assert_eq!(
+            Argon2::sensitive(),
+            Argon2::new(1048576, 4, 1).with_hash_length(32)
+        );
+    }
+
+    #[test]
+    fn test_hash_str() {
+        let salt = *b"somesaltsomesalt";
+        let encoded = hash_str_with_salt("password", &salt, 2, 1 << 20).unwrap();
+        assert!(encoded.starts_with("$argon2id$v=19$m=1024,t=2,p=1$c29tZXNhbHRzb21lc2FsdA$"));
+
+        assert!(verify_str("password", &encoded).unwrap());
+        assert!(!verify_str("wrong", &encoded).unwrap());
+
+        assert!(!str_needs_rehash(&encoded, 2, 1 << 20).unwrap());
+        assert!(str_needs_rehash(&encoded, 3, 1 << 20).unwrap());
+
+        let mut key = [0u8; 32];
+        derive_key(&mut key, "password", &salt, 2, 1 << 20).unwrap();
+        assert!(encoded.ends_with(&crate::phc::b64_encode(&key)));
+    }
+}
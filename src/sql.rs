@@ -0,0 +1,128 @@
+use super::{Error, stored::MAX_ENCODED_LENGTH, stored::StoredHash};
+
+/// Encodes a hash for a text column, rejecting strings the column could truncate
+fn to_column(stored: &StoredHash) -> Result<String, Error> {
+    let encoded = stored.to_string();
+
+    if encoded.len() > MAX_ENCODED_LENGTH {
+        return Err(Error::InvalidPhcString(
+            "longer than the maximum encoded length",
+        ));
+    }
+
+    Ok(encoded)
+}
+
+/// Decodes a hash read from a text column, the length is checked before parsing
+fn from_column(encoded: &str) -> Result<StoredHash, Error> {
+    if encoded.len() > MAX_ENCODED_LENGTH {
+        return Err(Error::InvalidPhcString(
+            "longer than the maximum encoded length",
+        ));
+    }
+
+    encoded.parse()
+}
+
+#[cfg(feature = "sqlx")]
+mod sqlx_impls {
+    use super::*;
+    use sqlx::encode::IsNull;
+    use sqlx::error::BoxDynError;
+    use sqlx::{Database, Decode, Encode, Type};
+
+    /// Stored as a PHC string in any text column
+    impl<DB: Database> Type<DB> for StoredHash
+    where
+        str: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <str as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <str as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for StoredHash
+    where
+        String: Encode<'q, DB>,
+    {
+        fn encode_by_ref(
+            &self,
+            buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+        ) -> Result<IsNull, BoxDynError> {
+            <String as Encode<'q, DB>>::encode(to_column(self)?, buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for StoredHash
+    where
+        &'r str: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+            let encoded = <&'r str as Decode<'r, DB>>::decode(value)?;
+            Ok(from_column(encoded)?)
+        }
+    }
+}
+
+#[cfg(feature = "diesel")]
+mod diesel_impls {
+    use super::*;
+    use diesel::backend::Backend;
+    use diesel::deserialize::{self, FromSql};
+    use diesel::query_builder::bind_collector::RawBytesBindCollector;
+    use diesel::serialize::{self, IsNull, Output, ToSql};
+    use diesel::sql_types::Text;
+    use std::io::Write;
+
+    /// Written as a PHC string, supported by the backends that collect binds as raw bytes
+    /// (PostgreSQL and MySQL)
+    impl<DB> ToSql<Text, DB> for StoredHash
+    where
+        for<'a> DB: Backend<BindCollector<'a> = RawBytesBindCollector<DB>>,
+    {
+        fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+            out.write_all(to_column(self)?.as_bytes())?;
+            Ok(IsNull::No)
+        }
+    }
+
+    impl<DB> FromSql<Text, DB> for StoredHash
+    where
+        DB: Backend,
+        String: FromSql<Text, DB>,
+    {
+        fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<Self> {
+            let encoded = <String as FromSql<Text, DB>>::from_sql(bytes)?;
+            Ok(from_column(&encoded)?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Argon2;
+
+    #[test]
+    fn test_column_length() {
+        let stored = Argon2::new(1024, 2, 1)
+            .hash_password_stored("password", b"somesaltsomesalt")
+            .unwrap();
+
+        let encoded = to_column(&stored).unwrap();
+        assert_eq!(from_column(&encoded).unwrap(), stored);
+
+        let long = StoredHash::new(Argon2::new(1024, 2, 1), [0u8; 16], [0u8; 256]);
+        assert!(matches!(to_column(&long), Err(Error::InvalidPhcString(_))));
+
+        let padded = format!("{encoded}{}", "A".repeat(MAX_ENCODED_LENGTH));
+        assert!(matches!(
+            from_column(&padded),
+            Err(Error::InvalidPhcString(_))
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+use super::{Argon2, Error, phc};
+
+/// The longest PHC string accepted when reading or writing a [`StoredHash`] column
+///
+/// Sized for a `VARCHAR(255)` column, which holds the default parameters with a 64 byte hash and
+/// leaves room for associated data and a key ID. Longer strings are rejected rather than silently
+/// truncated by the database.
+pub const MAX_ENCODED_LENGTH: usize = 255;
+
+/// A hash together with everything needed to verify it
+///
+/// This is the canonical type to persist: it displays as a PHC string and parses back from one,
+/// with the `serde` feature it is (de)serialized as that same string and with the `sqlx` or
+/// `diesel` feature it can be used as a text column.
+///
+/// ## Example
+///
+/// ```
+/// use argon2_rs::{Argon2, stored::StoredHash};
+///
+/// let argon2 = Argon2::new(1024, 2, 1);
+/// let stored = argon2.hash_password_stored("password", b"somesaltsomesalt").unwrap();
+///
+/// let encoded = stored.to_string();
+/// let parsed: StoredHash = encoded.parse().unwrap();
+/// assert_eq!(parsed, stored);
+/// assert!(parsed.verify("password").unwrap());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "diesel",
+    derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow),
+    diesel(sql_type = diesel::sql_types::Text)
+)]
+pub struct StoredHash {
+    /// The algorithm, version and parameters the hash was created with
+    pub argon2: Argon2,
+    /// The key ID of the pepper the hash was created with, if any
+    pub keyid: Option<Vec<u8>>,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+impl StoredHash {
+    /// ## Arguments
+    ///
+    /// - `argon2` - The parameters the hash was created with
+    /// - `salt` - The salt the hash was created with
+    /// - `hash` - The hash in its raw byte form
+    pub fn new(argon2: Argon2, salt: impl Into<Vec<u8>>, hash: impl Into<Vec<u8>>) -> Self {
+        Self {
+            argon2,
+            keyid: None,
+            salt: salt.into(),
+            hash: hash.into(),
+        }
+    }
+
+    pub fn with_keyid(mut self, keyid: impl Into<Vec<u8>>) -> Self {
+        self.keyid = Some(keyid.into());
+        self
+    }
+
+    /// Verifies the given password against this hash
+    ///
+    /// Returns [`Error::PepperRequired`] if the hash was created with a pepper
+    pub fn verify(&self, password: impl AsRef<[u8]>) -> Result<bool, Error> {
+        if self.keyid.is_some() {
+            return Err(Error::PepperRequired);
+        }
+
+        self.argon2.verify_raw(password, &self.salt, &self.hash)
+    }
+
+    /// Encodes this hash into a compact, versioned binary record
+    ///
+    /// The record is meant for embedded databases and constrained storage where a PHC string is
+    /// too verbose. Records produced by this version of the crate can always be decoded by later
+    /// versions with [`StoredHash::from_bytes`].
+    ///
+    /// ## Layout
+    ///
+    /// All lengths and costs are unsigned LEB128 varints.
+    ///
+    /// - `u8` format version, `1`, or `2` if the parameters have a pre-hash threshold or a
+    ///   normalization form
+    /// - `u8` algorithm, `0` = argon2d, `1` = argon2i, `2` = argon2id
+    /// - `u8` version, `0x10` or `0x13`
+    /// - `m_cost`, `t_cost`, `p_cost`
+    /// - `u8` options, format version `2` only, `1` = pre-hash threshold, `2` = NFC, `4` = NFKC
+    /// - the pre-hash threshold if its option is set
+    /// - length-prefixed associated data
+    /// - `u8` keyid tag, `0` = none, `1` = followed by the length-prefixed key ID
+    /// - length-prefixed salt
+    /// - length-prefixed hash
+    ///
+    /// The `flags` and the `hash_length` of [`Argon2`] are not stored, the `hash_length` is
+    /// taken from the length of the hash when decoding.
+    #[cfg(feature = "binary-record")]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        super::record::encode(&self.argon2, self.keyid.as_deref(), &self.salt, &self.hash)
+    }
+
+    /// Decodes a binary record produced by [`StoredHash::to_bytes`]
+    ///
+    /// Returns [`Error::InvalidRecord`] if the record is malformed, truncated, has trailing data
+    /// or uses an unknown format version.
+    #[cfg(feature = "binary-record")]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let decoded = super::record::decode(bytes)?;
+        Ok(Self {
+            argon2: decoded.argon2,
+            keyid: decoded.keyid,
+            salt: decoded.salt,
+            hash: decoded.hash,
+        })
+    }
+}
+
+impl Argon2 {
+    /// Hashes the given password and bundles the result with its parameters and salt
+    ///
+    /// ## Arguments
+    ///
+    /// - `password` - The password to hash
+    /// - `salt` - The salt to use for hashing
+    pub fn hash_password_stored(
+        &self,
+        password: impl AsRef<[u8]>,
+        salt: &[u8],
+    ) -> Result<StoredHash, Error> {
+        let hash = self.hash_password(password, salt)?;
+        Ok(StoredHash::new(self.clone(), salt, hash))
+    }
+}
+
+impl std::fmt::Display for StoredHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let encoded = phc::encode(&self.argon2, self.keyid.as_deref(), &self.salt, &self.hash);
+        f.write_str(&encoded)
+    }
+}
+
+impl std::str::FromStr for StoredHash {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let decoded = phc::decode(s)?;
+        Ok(Self {
+            argon2: decoded.argon2,
+            keyid: decoded.keyid,
+            salt: decoded.salt,
+            hash: decoded.hash,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for StoredHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for StoredHash {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        encoded.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_hash_roundtrip() {
+        let argon2 = Argon2::new(1024, 2, 1).with_ad(b"context");
+        let salt = b"somesaltsomesalt";
+        let stored = argon2.hash_password_stored("password", salt).unwrap();
+
+        let encoded = argon2.hash_password_encoded("password", salt).unwrap();
+        assert_eq!(stored.to_string(), encoded);
+        assert_eq!(encoded.parse::<StoredHash>().unwrap(), stored);
+
+        assert!(stored.verify("password").unwrap());
+        assert!(!stored.verify("wrong").unwrap());
+
+        let keyed = stored.with_keyid("v1");
+        let parsed: StoredHash = keyed.to_string().parse().unwrap();
+        assert_eq!(parsed.keyid.as_deref(), Some(&b"v1"[..]));
+        assert!(matches!(
+            parsed.verify("password"),
+            Err(Error::PepperRequired)
+        ));
+
+        assert!("$argon2id$invalid".parse::<StoredHash>().is_err());
+    }
+
+    #[cfg(feature = "binary-record")]
+    #[test]
+    fn test_stored_hash_bytes_roundtrip() {
+        let argon2 = Argon2::new(1024, 2, 1).with_ad(b"context");
+        let stored = argon2
+            .hash_password_stored("password", b"somesaltsomesalt")
+            .unwrap()
+            .with_keyid("v1");
+
+        let bytes = stored.to_bytes();
+        assert!(bytes.len() < stored.to_string().len());
+        assert_eq!(StoredHash::from_bytes(&bytes).unwrap(), stored);
+
+        let plain = argon2
+            .hash_password_stored("password", b"somesaltsomesalt")
+            .unwrap();
+        let parsed = StoredHash::from_bytes(&plain.to_bytes()).unwrap();
+        assert!(parsed.verify("password").unwrap());
+
+        assert!(matches!(
+            StoredHash::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidRecord(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_stored_hash_deserialize() {
+        use serde::Deserialize;
+        use serde::de::value::{Error as ValueError, StrDeserializer};
+
+        let encoded = "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$aGFzaGhhc2g";
+        let deserializer = StrDeserializer::<ValueError>::new(encoded);
+        let stored = StoredHash::deserialize(deserializer).unwrap();
+        assert_eq!(stored.to_string(), encoded);
+
+        let deserializer = StrDeserializer::<ValueError>::new("invalid");
+        assert!(StoredHash::deserialize(deserializer).is_err());
+    }
+}
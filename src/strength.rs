@@ -0,0 +1,206 @@
+use super::{Algorithm, Argon2};
+
+/// Memory in kibibytes times passes beyond which a hash is [`StrengthRating::Excessive`]
+///
+/// Four times the first recommended option of RFC 9106, 2 GiB with 1 pass.
+const EXCESSIVE_COST: u64 = 4 * 2 * 1024 * 1024;
+
+/// The shortest tag that is not [`StrengthRating::Weak`], 128 bits
+const MIN_TAG_LENGTH: u64 = 16;
+
+/// How the parameters of an [`Argon2`] compare to published guidance
+///
+/// See [`Argon2::strength`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub enum StrengthRating {
+    /// Below every configuration of the OWASP Password Storage Cheat Sheet, or a tag shorter
+    /// than 128 bits
+    Weak,
+    /// Meets the OWASP Password Storage Cheat Sheet but not RFC 9106
+    Acceptable,
+    /// Meets one of the recommended options of RFC 9106
+    Strong,
+    /// Costs far beyond RFC 9106, every hash ties up the server for long enough to invite denial
+    /// of service
+    Excessive,
+}
+
+impl StrengthRating {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StrengthRating::Weak => "weak",
+            StrengthRating::Acceptable => "acceptable",
+            StrengthRating::Strong => "strong",
+            StrengthRating::Excessive => "excessive",
+        }
+    }
+}
+
+impl std::fmt::Display for StrengthRating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Argon2 {
+    /// Rates the parameters against the OWASP Password Storage Cheat Sheet and RFC 9106
+    ///
+    /// Only the memory, passes and tag length are rated, the cost of hashing on a given machine
+    /// is measured by [`Argon2::calibrate`]. Argon2d and Argon2i are never rated above
+    /// [`StrengthRating::Acceptable`], both recommendations are for Argon2id.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::{Argon2, strength::StrengthRating};
+    ///
+    /// assert_eq!(Argon2::new(8 * 1024, 1, 1).strength(), StrengthRating::Weak);
+    /// assert_eq!(Argon2::owasp().strength(), StrengthRating::Acceptable);
+    /// assert_eq!(Argon2::rfc9106_low_memory().strength(), StrengthRating::Strong);
+    /// ```
+    pub fn strength(&self) -> StrengthRating {
+        self.rate().0
+    }
+
+    /// A sentence explaining the [`Argon2::strength`] rating, for admin UIs and config validators
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let explanation = Argon2::new(8 * 1024, 1, 1).strength_explanation();
+    /// assert!(explanation.contains("OWASP"));
+    /// ```
+    pub fn strength_explanation(&self) -> String {
+        self.rate().1
+    }
+
+    fn rate(&self) -> (StrengthRating, String) {
+        let memory = format_memory(self.m_cost);
+        let cost = self.m_cost as u64 * self.t_cost as u64;
+
+        if self.hash_length < MIN_TAG_LENGTH {
+            return (
+                StrengthRating::Weak,
+                format!(
+                    "a {}-byte tag is shorter than the {MIN_TAG_LENGTH} bytes needed to resist collisions",
+                    self.hash_length
+                ),
+            );
+        }
+
+        if !meets_any(self, &Argon2::owasp_configurations()) {
+            return (
+                StrengthRating::Weak,
+                format!(
+                    "{memory} with {} is below every OWASP configuration, use at least 19 MiB with 2 passes",
+                    passes(self.t_cost)
+                ),
+            );
+        }
+
+        if cost > EXCESSIVE_COST {
+            return (
+                StrengthRating::Excessive,
+                format!(
+                    "{memory} with {} is over 4 times the RFC 9106 recommendation, a burst of logins can exhaust the server",
+                    passes(self.t_cost)
+                ),
+            );
+        }
+
+        if self.algorithm != Algorithm::Argon2id {
+            return (
+                StrengthRating::Acceptable,
+                format!("{} is not the recommended Argon2id", self.algorithm),
+            );
+        }
+
+        if !meets_any(
+            self,
+            &[Argon2::rfc9106_high_memory(), Argon2::rfc9106_low_memory()],
+        ) {
+            return (
+                StrengthRating::Acceptable,
+                format!(
+                    "{memory} with {} meets OWASP but not RFC 9106, which recommends 64 MiB with 3 passes",
+                    passes(self.t_cost)
+                ),
+            );
+        }
+
+        (
+            StrengthRating::Strong,
+            format!(
+                "{memory} with {} meets the RFC 9106 recommendation",
+                passes(self.t_cost)
+            ),
+        )
+    }
+}
+
+/// Whether `argon2` uses at least the memory and passes of one of `configurations`
+fn meets_any(argon2: &Argon2, configurations: &[Argon2]) -> bool {
+    configurations
+        .iter()
+        .any(|c| argon2.m_cost >= c.m_cost && argon2.t_cost >= c.t_cost)
+}
+
+fn passes(t_cost: u32) -> String {
+    match t_cost {
+        1 => "1 pass".to_string(),
+        t => format!("{t} passes"),
+    }
+}
+
+fn format_memory(m_cost: u32) -> String {
+    match m_cost {
+        m if m % (1024 * 1024) == 0 => format!("{} GiB", m / (1024 * 1024)),
+        m if m % 1024 == 0 => format!("{} MiB", m / 1024),
+        m => format!("{m} KiB"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strength() {
+        let ratings = [
+            (Argon2::new(8 * 1024, 1, 1), StrengthRating::Weak),
+            (Argon2::new(19 * 1024, 1, 1), StrengthRating::Weak),
+            (Argon2::owasp().with_hash_length(8), StrengthRating::Weak),
+            (Argon2::new(46 * 1024, 1, 1), StrengthRating::Acceptable),
+            (
+                Argon2::rfc9106_low_memory().with_algorithm(Algorithm::Argon2i),
+                StrengthRating::Acceptable,
+            ),
+            (Argon2::default(), StrengthRating::Strong),
+            (Argon2::rfc9106_high_memory(), StrengthRating::Strong),
+            (
+                Argon2::new(4 * 1024 * 1024, 3, 4),
+                StrengthRating::Excessive,
+            ),
+        ];
+
+        for (argon2, expected) in ratings {
+            assert_eq!(
+                argon2.strength(),
+                expected,
+                "{}",
+                argon2.strength_explanation()
+            );
+        }
+
+        for argon2 in Argon2::owasp_configurations() {
+            assert_eq!(argon2.strength(), StrengthRating::Acceptable);
+        }
+
+        assert_eq!(
+            Argon2::new(8 * 1024, 1, 1).strength_explanation(),
+            "8 MiB with 1 pass is below every OWASP configuration, use at least 19 MiB with 2 passes"
+        );
+    }
+}
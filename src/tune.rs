@@ -0,0 +1,281 @@
+#[cfg(feature = "sysinfo")]
+use super::cgroup;
+use super::{Argon2, Error, RECOMMENDED_HASH_LENGTH, config, platform_len};
+use std::time::{Duration, Instant};
+
+/// The memory cost calibration starts from, 1 MiB
+const START_M_COST: u32 = 1024;
+
+/// The largest memory cost [`Argon2::estimate_duration`] probes with, 4 MiB
+const PROBE_M_COST: u32 = 4096;
+
+/// [`Argon2::auto`] uses at most this fraction of the available memory
+#[cfg(feature = "sysinfo")]
+const AUTO_MEMORY_FRACTION: u64 = 4;
+
+/// What a lane thread uses besides the working memory, the blocks it keeps on its stack
+const THREAD_OVERHEAD: u64 = 8 * 1024;
+
+/// The largest parallelism [`Argon2::calibrate`] picks, as recommended by RFC 9106
+const MAX_CALIBRATED_LANES: u32 = 4;
+
+impl Argon2 {
+    /// Picks parameters that take about `target` to hash on this machine
+    ///
+    /// The parallelism is the number of available cores up to 4. The memory cost is doubled from
+    /// 1 MiB until a single pass takes `target` or the memory reaches `max_memory`, then scaled
+    /// down to hit the target. If a single pass over `max_memory` is still faster than `target`,
+    /// the remaining time is spent on more passes.
+    ///
+    /// Each step hashes once, calibrating for a target of 1 second takes a few seconds.
+    ///
+    /// ## Arguments
+    ///
+    /// - `target` - The time a hash should take
+    /// - `max_memory` - The largest memory cost in kibibytes
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::InvalidConfig`] if `max_memory` is below 8 KiB
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    /// use std::time::Duration;
+    ///
+    /// let argon2 = Argon2::calibrate(Duration::from_millis(20), 64 * 1024).unwrap();
+    /// assert!(argon2.m_cost <= 64 * 1024);
+    /// ```
+    pub fn calibrate(target: Duration, max_memory: u32) -> Result<Self, Error> {
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get() as u32);
+        Self::calibrate_with(target, max_memory, cores)
+    }
+
+    fn calibrate_with(target: Duration, max_memory: u32, cores: u32) -> Result<Self, Error> {
+        if max_memory < 8 {
+            return Err(Error::InvalidConfig(
+                "max_memory must be at least 8 KiB".to_string(),
+            ));
+        }
+
+        let p_cost = cores.clamp(1, MAX_CALIBRATED_LANES).min(max_memory / 8);
+
+        let mut argon2 = Argon2::new(START_M_COST.min(max_memory), 1, p_cost);
+        loop {
+            let elapsed = argon2.time_hash()?;
+
+            if elapsed >= target {
+                let scaled = argon2.m_cost as f64 * ratio(target, elapsed);
+                argon2.m_cost = (scaled as u32).max(8 * p_cost);
+                return Ok(argon2);
+            }
+
+            if argon2.m_cost == max_memory {
+                argon2.t_cost = ratio(target, elapsed).round().max(1.0) as u32;
+                return Ok(argon2);
+            }
+
+            argon2.m_cost = argon2.m_cost.saturating_mul(2).min(max_memory);
+        }
+    }
+
+    /// Picks parameters that take about `target` to hash on this machine without exhausting its
+    /// memory
+    ///
+    /// Like [`Argon2::calibrate`] with the memory limited to a quarter of the currently available
+    /// RAM, or of the container memory limit if lower, and the parallelism to the physical cores,
+    /// for desktop apps installed on unknown hardware.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    /// use std::time::Duration;
+    ///
+    /// let argon2 = Argon2::auto(Duration::from_millis(20)).unwrap();
+    /// assert!(argon2.p_cost >= 1);
+    /// ```
+    #[cfg(feature = "sysinfo")]
+    pub fn auto(target: Duration) -> Result<Self, Error> {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+
+        // Containers see the memory of the whole host
+        let available = match cgroup::memory_limit() {
+            Some(limit) => system.available_memory().min(limit),
+            None => system.available_memory(),
+        };
+        let max_memory = available / AUTO_MEMORY_FRACTION / 1024;
+        let cores = sysinfo::System::physical_core_count()
+            .or_else(|| {
+                std::thread::available_parallelism()
+                    .ok()
+                    .map(|cores| cores.get())
+            })
+            .unwrap_or(1);
+
+        Self::calibrate_with(
+            target,
+            max_memory.min(u32::MAX as u64) as u32,
+            cores.min(u32::MAX as usize) as u32,
+        )
+    }
+
+    /// Predicts how long a hash with these parameters takes on this machine
+    ///
+    /// A single pass over at most 4 MiB with the same parallelism is timed and extrapolated
+    /// linearly to the memory and time costs. The hashing time is close to linear in both, but
+    /// caches make the probe relatively faster, expect the estimate to be somewhat low for memory
+    /// costs in the GiB range.
+    ///
+    /// ## Returns
+    ///
+    /// [`Error::InvalidConfig`] for parameters the hash would reject
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    /// use std::time::Duration;
+    ///
+    /// let estimate = Argon2::very_slow().estimate_duration().unwrap();
+    /// if estimate > Duration::from_secs(5) {
+    ///     println!("unlocking the vault takes about {}s", estimate.as_secs());
+    /// }
+    /// ```
+    pub fn estimate_duration(&self) -> Result<Duration, Error> {
+        // Invalid parameters would only fail when hashing at the full cost
+        let mut probe = config::check(self.clone())?;
+        probe.m_cost = self.m_cost.min(PROBE_M_COST).max(8 * self.p_cost);
+        probe.t_cost = 1;
+
+        // The fastest of two runs, the first one also pays for page faults
+        let elapsed = probe.time_hash()?.min(probe.time_hash()?);
+
+        let scale = self.m_cost.max(probe.m_cost) as f64 / probe.m_cost as f64;
+        Ok(elapsed.mul_f64(scale * self.t_cost as f64))
+    }
+
+    /// The expected peak memory usage of a hash in bytes
+    ///
+    /// This is the working memory, `m_cost` KiB rounded the same way as the C implementation (at
+    /// least 8 KiB per lane and a multiple of 4 KiB per lane), plus what each lane thread keeps on
+    /// its stack and the hash itself. Multiply by the number of concurrent hashes to bound the
+    /// memory of a service.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use argon2_rs::Argon2;
+    ///
+    /// let argon2 = Argon2::new(65536, 3, 4);
+    /// let concurrent = 8 * 1024 * 1024 * 1024 / argon2.memory_usage_bytes();
+    /// assert!(concurrent < 128);
+    /// ```
+    pub fn memory_usage_bytes(&self) -> u64 {
+        self.working_memory_bytes() + self.threads() as u64 * THREAD_OVERHEAD + self.hash_length
+    }
+
+    /// The size of the working memory in bytes after the rounding of the C implementation
+    pub(crate) fn working_memory_bytes(&self) -> u64 {
+        let lanes = self.p_cost.max(1) as u64;
+        let blocks = (self.m_cost as u64).max(8 * lanes);
+        blocks / (4 * lanes) * (4 * lanes) * 1024
+    }
+
+    /// The size of the working memory in bytes as a `usize`
+    ///
+    /// [`Error::MemoryTooLargeForPlatform`] if it does not fit in the address space, a memory cost
+    /// of 2 GiB or more on 32-bit targets.
+    pub(crate) fn working_memory_len(&self) -> Result<usize, Error> {
+        platform_len(self.working_memory_bytes())
+    }
+
+    /// Hashes once with a throwaway password and salt and returns how long it took
+    pub(crate) fn time_hash(&self) -> Result<Duration, Error> {
+        let salt = [0u8; 16];
+        let start = Instant::now();
+        self.clone()
+            .with_hash_length(RECOMMENDED_HASH_LENGTH)
+            .hash_password(b"password", &salt)?;
+        Ok(start.elapsed())
+    }
+}
+
+fn ratio(a: Duration, b: Duration) -> f64 {
+    a.as_secs_f64() / b.as_secs_f64().max(f64::MIN_POSITIVE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calibrate() {
+        let target = Duration::from_millis(30);
+
+        let argon2 = Argon2::calibrate(target, 256 * 1024).unwrap();
+        assert!(argon2.m_cost <= 256 * 1024);
+        assert!(argon2.m_cost >= 8 * argon2.p_cost);
+        assert!((1..=MAX_CALIBRATED_LANES).contains(&argon2.p_cost));
+        assert!(argon2.time_hash().unwrap() < target * 10);
+
+        // Too little memory to reach the target in a single pass
+        let argon2 = Argon2::calibrate(Duration::from_millis(200), 64).unwrap();
+        assert_eq!(argon2.m_cost, 64);
+        assert!(argon2.t_cost > 1);
+
+        assert!(matches!(
+            Argon2::calibrate(target, 4),
+            Err(Error::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_estimate_duration() {
+        let argon2 = Argon2::new(64 * 1024, 2, 2);
+        let estimate = argon2.estimate_duration().unwrap();
+        let actual = argon2.time_hash().unwrap();
+        assert!(estimate > actual / 10 && estimate < actual * 10);
+
+        // Scales with the costs without hashing at them
+        let huge = Argon2::new(u32::MAX, 100, 1).estimate_duration().unwrap();
+        assert!(huge > Argon2::new(4096, 1, 1).estimate_duration().unwrap() * 1000);
+
+        assert!(Argon2::new(1024, 0, 1).estimate_duration().is_err());
+    }
+
+    #[test]
+    fn test_memory_usage_bytes() {
+        let argon2 = Argon2::new(65536, 3, 4).with_hash_length(32);
+        assert_eq!(argon2.working_memory_bytes(), 64 * 1024 * 1024);
+        assert_eq!(
+            argon2.memory_usage_bytes(),
+            64 * 1024 * 1024 + 4 * THREAD_OVERHEAD + 32
+        );
+
+        // Rounded down to a multiple of 4 blocks per lane, at least 8 blocks per lane
+        assert_eq!(Argon2::new(30, 1, 3).working_memory_bytes(), 24 * 1024);
+        assert_eq!(Argon2::new(4, 1, 1).working_memory_bytes(), 8 * 1024);
+
+        // Fewer threads need less stack
+        let single = argon2.clone().with_threads(1);
+        assert!(single.memory_usage_bytes() < argon2.memory_usage_bytes());
+    }
+
+    #[cfg(feature = "sysinfo")]
+    #[test]
+    fn test_auto() {
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+
+        let argon2 = Argon2::auto(Duration::from_millis(30)).unwrap();
+        assert!(argon2.memory_usage_bytes() <= system.available_memory());
+        assert!(
+            argon2
+                .hash_password("password", b"somesaltsomesalt")
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use super::{Algorithm, Argon2, Error, Version};
+
+/// A known-answer test vector
+///
+/// The inputs are those of RFC 9106 section 5: a 32 byte password of `0x01`, a 16 byte salt of
+/// `0x02`, an 8 byte secret of `0x03` and 12 bytes of associated data of `0x04`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TestVector {
+    pub algorithm: Algorithm,
+    pub version: Version,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub password: &'static [u8],
+    pub salt: &'static [u8],
+    pub secret: &'static [u8],
+    pub ad: &'static [u8],
+    /// The expected hash
+    pub tag: &'static [u8],
+}
+
+impl TestVector {
+    /// The parameters of this vector, the `hash_length` is the length of the tag
+    pub fn argon2(&self) -> Argon2 {
+        Argon2::new(self.m_cost, self.t_cost, self.p_cost)
+            .with_algorithm(self.algorithm)
+            .with_version(self.version)
+            .with_hash_length(self.tag.len() as u64)
+            .with_ad(self.ad)
+    }
+
+    /// Computes the tag of this vector with the active backend
+    pub fn compute(&self) -> Result<Vec<u8>, Error> {
+        let mut out = vec![0u8; self.tag.len()];
+        self.argon2()
+            .hash_with_secret(self.password, self.salt, self.secret, &mut out)?;
+        Ok(out)
+    }
+
+    /// Whether the active backend produces the expected tag
+    pub fn check(&self) -> Result<bool, Error> {
+        Ok(self.compute()? == self.tag)
+    }
+}
+
+const PASSWORD: &[u8] = &[0x01; 32];
+const SALT: &[u8] = &[0x02; 16];
+const SECRET: &[u8] = &[0x03; 8];
+const AD: &[u8] = &[0x04; 12];
+
+const fn vector(algorithm: Algorithm, version: Version, tag: &'static [u8]) -> TestVector {
+    TestVector {
+        algorithm,
+        version,
+        m_cost: 32,
+        t_cost: 3,
+        p_cost: 4,
+        password: PASSWORD,
+        salt: SALT,
+        secret: SECRET,
+        ad: AD,
+        tag,
+    }
+}
+
+/// The vectors of RFC 9106 and the `kats` of the reference implementation
+///
+/// All three variants for both versions, the `0x13` vectors are the ones published in the RFC.
+///
+/// ## Example
+///
+/// ```
+/// # #[cfg(not(feature = "argon2id-only"))]
+/// # {
+/// use argon2_rs::vectors::VECTORS;
+///
+/// for vector in VECTORS {
+///     assert!(vector.check().unwrap(), "{:?} {:?}", vector.algorithm, vector.version);
+/// }
+/// # }
+/// ```
+pub const VECTORS: &[TestVector] = &[
+    vector(
+        Algorithm::Argon2d,
+        Version::V0x10,
+        &[
+            0x96, 0xa9, 0xd4, 0xe5, 0xa1, 0x73, 0x40, 0x92, 0xc8, 0x5e, 0x29, 0xf4, 0x10, 0xa4,
+            0x59, 0x14, 0xa5, 0xdd, 0x1f, 0x5c, 0xbf, 0x08, 0xb2, 0x67, 0x0d, 0xa6, 0x8a, 0x02,
+            0x85, 0xab, 0xf3, 0x2b,
+        ],
+    ),
+    vector(
+        Algorithm::Argon2i,
+        Version::V0x10,
+        &[
+            0x87, 0xae, 0xed, 0xd6, 0x51, 0x7a, 0xb8, 0x30, 0xcd, 0x97, 0x65, 0xcd, 0x82, 0x31,
+            0xab, 0xb2, 0xe6, 0x47, 0xa5, 0xde, 0xe0, 0x8f, 0x7c, 0x05, 0xe0, 0x2f, 0xcb, 0x76,
+            0x33, 0x35, 0xd0, 0xfd,
+        ],
+    ),
+    vector(
+        Algorithm::Argon2id,
+        Version::V0x10,
+        &[
+            0xb6, 0x46, 0x15, 0xf0, 0x77, 0x89, 0xb6, 0x6b, 0x64, 0x5b, 0x67, 0xee, 0x9e, 0xd3,
+            0xb3, 0x77, 0xae, 0x35, 0x0b, 0x6b, 0xfc, 0xbb, 0x0f, 0xc9, 0x51, 0x41, 0xea, 0x8f,
+            0x32, 0x26, 0x13, 0xc0,
+        ],
+    ),
+    vector(
+        Algorithm::Argon2d,
+        Version::V0x13,
+        &[
+            0x51, 0x2b, 0x39, 0x1b, 0x6f, 0x11, 0x62, 0x97, 0x53, 0x71, 0xd3, 0x09, 0x19, 0x73,
+            0x42, 0x94, 0xf8, 0x68, 0xe3, 0xbe, 0x39, 0x84, 0xf3, 0xc1, 0xa1, 0x3a, 0x4d, 0xb9,
+            0xfa, 0xbe, 0x4a, 0xcb,
+        ],
+    ),
+    vector(
+        Algorithm::Argon2i,
+        Version::V0x13,
+        &[
+            0xc8, 0x14, 0xd9, 0xd1, 0xdc, 0x7f, 0x37, 0xaa, 0x13, 0xf0, 0xd7, 0x7f, 0x24, 0x94,
+            0xbd, 0xa1, 0xc8, 0xde, 0x6b, 0x01, 0x6d, 0xd3, 0x88, 0xd2, 0x99, 0x52, 0xa4, 0xc4,
+            0x67, 0x2b, 0x6c, 0xe8,
+        ],
+    ),
+    vector(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        &[
+            0x0d, 0x64, 0x0d, 0xf5, 0x8d, 0x78, 0x76, 0x6c, 0x08, 0xc0, 0x37, 0xa3, 0x4a, 0x8b,
+            0x53, 0xc9, 0xd0, 0x1e, 0xf0, 0x45, 0x2d, 0x75, 0xb6, 0x5e, 0xb5, 0x25, 0x20, 0xe9,
+            0x6b, 0x01, 0xe6, 0x59,
+        ],
+    ),
+];
+
+// The tests hash with Argon2i and Argon2d, which the `argon2id-only` feature rejects
+#[cfg(all(test, not(feature = "argon2id-only")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        for vector in VECTORS {
+            assert_eq!(vector.compute().unwrap(), vector.tag);
+        }
+
+        let mut wrong = VECTORS[0];
+        wrong.algorithm = Algorithm::Argon2id;
+        assert!(!wrong.check().unwrap());
+    }
+}